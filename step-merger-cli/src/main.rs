@@ -1,16 +1,16 @@
 mod options;
 
-use std::{
-    fs::File,
-    io::{BufWriter, Write},
-    time::Instant,
-};
+use std::{fs::File, io::Write, path::Path, time::Instant};
 
 use anyhow::Result;
 use clap::Parser;
 use log::{error, info, LevelFilter};
 use options::Options;
-use step_merger::{merge_assembly_structure_to_step, Assembly};
+use step_merger::{
+    merge_assembly_structure_to_step_with_progress,
+    step::{PointBounds, StepData},
+    Assembly, LocalVfs, MergeOptions,
+};
 
 /// Parses the program arguments and returns None, if no arguments were provided and Some otherwise.
 fn parse_args() -> Result<Options> {
@@ -61,15 +61,59 @@ fn run_program() -> Result<()> {
 
     info!("Merge assembly structure into step file...");
     let t = Instant::now();
-    let out_file = BufWriter::new(File::create(options.output_file)?);
+    let out_file = File::create(&options.output_file)?;
 
-    let root_link = options.input_file.to_string_lossy().to_string();
-    merge_assembly_structure_to_step(&root_link, &assembly, !options.avoid_references, out_file)?;
+    let root_dir = options.input_file.parent().unwrap_or(Path::new("./"));
+    let merge_options = MergeOptions {
+        load_references: !options.avoid_references,
+        protocol: options.protocol.into(),
+        ..Default::default()
+    };
+
+    // loading the referenced step files dominates the runtime, thus each loaded file is reported
+    let mut references_loaded = 0;
+    let merge_output = merge_assembly_structure_to_step_with_progress(
+        &assembly,
+        &merge_options,
+        out_file,
+        &LocalVfs::new(root_dir),
+        |progress| {
+            if progress.references_loaded != references_loaded {
+                references_loaded = progress.references_loaded;
+                info!("Progress: {}", progress);
+            }
+        },
+    )?;
     info!(
         "Merge assembly structure into step file...DONE in {} s",
         t.elapsed().as_secs_f64()
     );
 
+    if options.fail_on_reference_errors && !merge_output.failed_references.is_empty() {
+        for (link, err) in merge_output.failed_references.iter() {
+            error!("Failed to load reference {}: {}", link, err);
+        }
+
+        anyhow::bail!(
+            "{} referenced step files could not be loaded",
+            merge_output.failed_references.len()
+        );
+    }
+
+    if options.bbox {
+        // the merged file is streamed to avoid loading it into memory
+        let mut bounds = PointBounds::default();
+        for entry in StepData::stream_from_file(&options.output_file)? {
+            bounds.add_entry(&entry?);
+        }
+
+        if bounds.is_valid() {
+            info!("Bounding box: {}", bounds);
+        } else {
+            info!("Bounding box: no points");
+        }
+    }
+
     Ok(())
 }
 
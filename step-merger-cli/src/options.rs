@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use clap::{Parser, ValueEnum};
 use log::{info, LevelFilter};
+use step_merger::step::ApplicationProtocol;
 
 /// Workaround for parsing the different log level
 #[derive(ValueEnum, Clone, Copy, Debug)]
@@ -25,6 +26,24 @@ impl From<LogLevel> for LevelFilter {
     }
 }
 
+/// Workaround for parsing the different application protocols
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Protocol {
+    AP203,
+    AP214,
+    AP242,
+}
+
+impl From<Protocol> for ApplicationProtocol {
+    fn from(value: Protocol) -> Self {
+        match value {
+            Protocol::AP203 => ApplicationProtocol::AP203,
+            Protocol::AP214 => ApplicationProtocol::AP214,
+            Protocol::AP242 => ApplicationProtocol::AP242,
+        }
+    }
+}
+
 /// CLI interface for merging step files into a single monolithic step file.
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -44,6 +63,18 @@ pub struct Options {
     /// Avoid loading references
     #[arg(short, long)]
     pub avoid_references: bool,
+
+    /// The application protocol of the merged step file
+    #[arg(short, value_enum, long, default_value_t = Protocol::AP203)]
+    pub protocol: Protocol,
+
+    /// Fail if any referenced step file cannot be loaded instead of skipping it
+    #[arg(long)]
+    pub fail_on_reference_errors: bool,
+
+    /// Print the bounding box of the points of the merged step file
+    #[arg(long)]
+    pub bbox: bool,
 }
 
 impl Options {
@@ -53,5 +84,11 @@ impl Options {
         info!("input_file: {:?}", self.input_file);
         info!("output_file: {:?}", self.output_file);
         info!("loading references: {:?}", !self.avoid_references);
+        info!("protocol: {:?}", self.protocol);
+        info!(
+            "fail on reference errors: {:?}",
+            self.fail_on_reference_errors
+        );
+        info!("bounding box: {:?}", self.bbox);
     }
 }
@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use step_merger::step::StepEntry;
+
+/// Creates a GEOMETRIC_SET entry referencing the given number of items.
+///
+/// # Arguments
+/// * `num_references` - The number of referenced items.
+fn create_geometric_set(num_references: usize) -> StepEntry {
+    let items: Vec<String> = (0..num_references)
+        .map(|i| format!("#{}", 1000 + i))
+        .collect();
+    StepEntry::new(
+        1,
+        &format!("GEOMETRIC_SET('it''s a set',({}))", items.join(",")),
+    )
+}
+
+/// Benchmark collecting the references of entries with many references
+pub fn get_references_bench(c: &mut Criterion) {
+    let entry = create_geometric_set(500);
+    c.bench_function("get_references 500", |b| {
+        b.iter(|| black_box(&entry).get_references())
+    });
+
+    let entry = create_geometric_set(10000);
+    c.bench_function("get_references 10000", |b| {
+        b.iter(|| black_box(&entry).get_references())
+    });
+}
+
+criterion_group!(benches, get_references_bench);
+criterion_main!(benches);
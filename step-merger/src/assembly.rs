@@ -1,8 +1,16 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+    sync::Arc,
+};
 
-use crate::{Error, Result};
+use crate::{decompose_matrix, linear_determinant, multiply_matrices, Error, Result};
 use serde::{Deserialize, Serialize};
 
+/// The maximum absolute cosine of the angle between the x- and z-axis of a valid transform.
+const AXIS_ORTHOGONALITY_TOLERANCE: f32 = 1e-3;
+
 /// Returns the identity matrix.
 pub const fn identity_matrix() -> [f32; 16] {
     [
@@ -17,6 +25,165 @@ pub struct MetadataEntry {
     pub value: String,
 }
 
+/// A problem with the metadata of the assembly nodes, which violates the metadata contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataDiagnostic {
+    /// The node with the given index has a metadata key outside the allowed set.
+    UnknownKey(usize, String),
+}
+
+impl Display for MetadataDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataDiagnostic::UnknownKey(node, key) => {
+                write!(f, "Node {} has unknown metadata key '{}'", node, key)
+            }
+        }
+    }
+}
+
+/// The edge from a parent node to one of its children together with the attributes of the
+/// occurrence of the child in the parent node. In JSON, an edge without attributes is the plain
+/// index of the child node, e.g., `1`, otherwise an object, e.g., `{"node": 1, ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ChildEdgeRepr", into = "ChildEdgeRepr")]
+pub struct ChildEdge {
+    node: usize,
+    relationship_name: Option<String>,
+    reference_designator: Option<String>,
+    instance_name: Option<String>,
+}
+
+impl ChildEdge {
+    /// Creates a new edge to the child node with the given index.
+    ///
+    /// # Arguments
+    /// * `node` - The index of the child node.
+    pub fn new(node: usize) -> ChildEdge {
+        ChildEdge {
+            node,
+            relationship_name: None,
+            reference_designator: None,
+            instance_name: None,
+        }
+    }
+
+    /// Returns the index of the child node.
+    #[inline]
+    pub fn get_node(&self) -> usize {
+        self.node
+    }
+
+    /// Sets the name of the relationship between the parent and the child node.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the relationship.
+    pub fn set_relationship_name(&mut self, name: &str) -> &mut Self {
+        self.relationship_name = Some(name.to_owned());
+        self
+    }
+
+    /// Returns the name of the relationship between the parent and the child node.
+    pub fn get_relationship_name(&self) -> Option<&str> {
+        self.relationship_name.as_deref()
+    }
+
+    /// Sets the reference designator of the occurrence of the child node, e.g., `A1`.
+    ///
+    /// # Arguments
+    /// * `designator` - The reference designator.
+    pub fn set_reference_designator(&mut self, designator: &str) -> &mut Self {
+        self.reference_designator = Some(designator.to_owned());
+        self
+    }
+
+    /// Returns the reference designator of the occurrence of the child node.
+    pub fn get_reference_designator(&self) -> Option<&str> {
+        self.reference_designator.as_deref()
+    }
+
+    /// Sets the name of the occurrence of the child node, e.g., `BOLT-1`. The name is written as
+    /// the id of the NEXT_ASSEMBLY_USAGE_OCCURRENCE instead of the label of the child node.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the occurrence.
+    pub fn set_instance_name(&mut self, name: &str) -> &mut Self {
+        self.instance_name = Some(name.to_owned());
+        self
+    }
+
+    /// Returns the name of the occurrence of the child node.
+    pub fn get_instance_name(&self) -> Option<&str> {
+        self.instance_name.as_deref()
+    }
+}
+
+/// The serialized representation of a child edge.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ChildEdgeRepr {
+    Index(usize),
+    Edge {
+        node: usize,
+
+        // The name of the relationship to the parent node, e.g., to identify mates.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        relationship_name: Option<String>,
+
+        // The reference designator of the occurrence of the child node, e.g., `A1`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference_designator: Option<String>,
+
+        // The name of the occurrence of the child node, e.g., `BOLT-1`, to distinguish repeated
+        // parts.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance_name: Option<String>,
+    },
+}
+
+impl From<ChildEdgeRepr> for ChildEdge {
+    fn from(repr: ChildEdgeRepr) -> Self {
+        match repr {
+            ChildEdgeRepr::Index(node) => ChildEdge::new(node),
+            ChildEdgeRepr::Edge {
+                node,
+                relationship_name,
+                reference_designator,
+                instance_name,
+            } => ChildEdge {
+                node,
+                relationship_name,
+                reference_designator,
+                instance_name,
+            },
+        }
+    }
+}
+
+impl From<ChildEdge> for ChildEdgeRepr {
+    fn from(edge: ChildEdge) -> Self {
+        match edge {
+            ChildEdge {
+                node,
+                relationship_name: None,
+                reference_designator: None,
+                instance_name: None,
+            } => ChildEdgeRepr::Index(node),
+            ChildEdge {
+                node,
+                relationship_name,
+                reference_designator,
+                instance_name,
+            } => ChildEdgeRepr::Edge {
+                node,
+                relationship_name,
+                reference_designator,
+                instance_name,
+            },
+        }
+    }
+}
+
 /// A single node in the assembly tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -31,8 +198,12 @@ pub struct Node {
     #[serde(default = "identity_matrix")]
     transform: [f32; 16],
 
+    // The unit of the translation of the transform, which overrides the unit of the assembly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unit: Option<LengthUnit>,
+
     #[serde(default)]
-    children: Vec<usize>,
+    children: Vec<ChildEdge>,
 }
 
 impl Node {
@@ -46,17 +217,19 @@ impl Node {
             label: label.to_owned(),
             metadata: Vec::new(),
             transform: identity_matrix(),
+            unit: None,
             children: Vec::new(),
         }
     }
 
-    /// Adds a child to the node.
+    /// Adds a child to the node and returns the edge to the child for setting its attributes.
     ///
     /// # Arguments
     /// * `child` - The child node to be added.
     #[inline]
-    pub fn add_child(&mut self, child: usize) {
-        self.children.push(child);
+    pub fn add_child(&mut self, child: usize) -> &mut ChildEdge {
+        self.children.push(ChildEdge::new(child));
+        self.children.last_mut().unwrap()
     }
 
     /// Sets the link of the node.
@@ -77,28 +250,123 @@ impl Node {
         &self.label
     }
 
-    /// Returns the children of the node.
-    pub fn get_children(&self) -> &[usize] {
+    /// Returns the edges to the children of the node.
+    pub fn get_children(&self) -> &[ChildEdge] {
         &self.children
     }
 
+    /// Returns the indices of the children of the node.
+    pub fn get_child_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.children.iter().map(ChildEdge::get_node)
+    }
+
+    /// Sets the column-major encoded transformation matrix of the node.
+    ///
+    /// # Arguments
+    /// * `transform` - The transformation matrix to be set.
+    #[inline]
+    pub fn set_transform(&mut self, transform: [f32; 16]) {
+        self.transform = transform;
+    }
+
     /// Returns the transformation matrix of the node.
     #[inline]
     pub fn get_transform(&self) -> &[f32; 16] {
         &self.transform
     }
 
+    /// Sets the unit of the translation of the node, which overrides the unit of the assembly.
+    ///
+    /// # Arguments
+    /// * `unit` - The length unit of the translation.
+    #[inline]
+    pub fn set_unit(&mut self, unit: LengthUnit) {
+        self.unit = Some(unit);
+    }
+
+    /// Returns the unit of the translation of the node, if declared.
+    #[inline]
+    pub fn get_unit(&self) -> Option<LengthUnit> {
+        self.unit
+    }
+
     /// Returns the metadata
     #[inline]
     pub fn get_metadata(&self) -> &[MetadataEntry] {
         &self.metadata
     }
+
+    /// Adds a metadata entry to the node.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the metadata entry.
+    /// * `value` - The value of the metadata entry.
+    pub fn add_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.push(MetadataEntry {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        });
+    }
+
+    /// Returns the value of the first metadata entry with the given key.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the metadata entry.
+    pub fn get_metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|m| m.key == key)
+            .map(|m| m.value.as_str())
+    }
+}
+
+/// Determines what happens to the children of a node removed by `Assembly::prune`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrunePolicy {
+    /// The children are attached to the parent of the removed node. The transformation of the
+    /// removed node is applied to the reattached children.
+    #[default]
+    Reparent,
+
+    /// The children are removed together with the node unless they are referenced elsewhere.
+    Drop,
+}
+
+/// The length unit in which the translations of an assembly are given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LengthUnit {
+    #[default]
+    #[serde(rename = "mm")]
+    Millimeter,
+
+    #[serde(rename = "m")]
+    Meter,
+
+    #[serde(rename = "inch")]
+    Inch,
+}
+
+impl LengthUnit {
+    /// Returns the length of the unit in meters, e.g., 0.001 for millimeters.
+    pub fn meters(&self) -> f64 {
+        match self {
+            LengthUnit::Millimeter => 1e-3,
+            LengthUnit::Meter => 1.0,
+            LengthUnit::Inch => 0.0254,
+        }
+    }
 }
 
 /// The assembly tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Assembly {
     pub nodes: Vec<Node>,
+
+    /// The unit of the translations, which is also the emitted length unit. If not declared, the
+    /// translations are given in meters and emitted as defined by the merge options. A node can
+    /// declare its own unit for its translation, which is converted to this unit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<LengthUnit>,
 }
 
 impl Assembly {
@@ -120,18 +388,540 @@ impl Assembly {
         Ok(assembly)
     }
 
+    /// Returns the transform of the given node with the translation converted from the unit of
+    /// the node to the unit of the assembly. If the assembly does not declare a unit, the
+    /// translation is converted to meters.
+    ///
+    /// # Arguments
+    /// * `node` - The node of the assembly.
+    pub fn get_node_transform(&self, node: &Node) -> [f32; 16] {
+        let mut transform = node.transform;
+        let Some(unit) = node.unit.filter(|unit| Some(*unit) != self.unit) else {
+            return transform;
+        };
+
+        let factor = unit.meters() / self.unit.map_or(1.0, |unit| unit.meters());
+        for value in transform[12..15].iter_mut() {
+            *value = (*value as f64 * factor) as f32;
+        }
+
+        transform
+    }
+
     /// Checks if the assembly is valid.
     pub fn is_valid(&self) -> Result<()> {
         let num_nodes = self.nodes.len();
 
         for node in self.nodes.iter() {
-            for child in node.get_children() {
-                if *child >= num_nodes {
-                    return Err(Error::InvalidFormat(*child, node.get_label().to_string()));
+            for child in node.get_child_indices() {
+                if child >= num_nodes {
+                    return Err(Error::InvalidFormat(child, node.get_label().to_string()));
+                }
+            }
+        }
+
+        self.check_cycles()
+    }
+
+    /// Checks that the children of the nodes do not form a cycle. Otherwise, returns an error
+    /// with the labels of the nodes along the cycle.
+    fn check_cycles(&self) -> Result<()> {
+        // the nodes on the current path are marked as active, fully explored nodes as done
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            Active,
+            Done,
+        }
+
+        let mut states = vec![State::Unvisited; self.nodes.len()];
+        for start in 0..self.nodes.len() {
+            if states[start] != State::Unvisited {
+                continue;
+            }
+
+            // the stack holds the path from the start node and the next child to visit
+            let mut path = vec![(start, 0usize)];
+            states[start] = State::Active;
+            while let Some((index, next_child)) = path.last_mut() {
+                let index = *index;
+                let Some(child) = self.nodes[index].get_child_indices().nth(*next_child) else {
+                    states[index] = State::Done;
+                    path.pop();
+                    continue;
+                };
+                *next_child += 1;
+
+                match states[child] {
+                    State::Unvisited => {
+                        states[child] = State::Active;
+                        path.push((child, 0));
+                    }
+                    State::Active => {
+                        let cycle_start = path.iter().position(|(i, _)| *i == child).unwrap();
+                        let labels = path[cycle_start..]
+                            .iter()
+                            .chain(std::iter::once(&(child, 0)))
+                            .map(|(i, _)| self.nodes[*i].get_label().to_owned())
+                            .collect();
+                        return Err(Error::CyclicAssembly(labels));
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the transforms of all nodes are well-formed, i.e., they consist of finite
+    /// values and have non-degenerate, orthogonal x- and z-axes. Otherwise, the merged placements
+    /// would consist of meaningless directions.
+    pub fn validate_transforms(&self) -> Result<()> {
+        for node in self.nodes.iter() {
+            let malformed = |reason: &str| {
+                Error::MalformedTransform(node.get_label().to_owned(), reason.to_owned())
+            };
+
+            let transform = node.get_transform();
+            if transform.iter().any(|v| !v.is_finite()) {
+                return Err(malformed("contains non-finite values"));
+            }
+
+            let decomposed = decompose_matrix(transform);
+            if decomposed.scale[0] <= f32::EPSILON || decomposed.scale[2] <= f32::EPSILON {
+                return Err(malformed("x- or z-axis has zero length"));
+            }
+
+            let [x, _, z] = decomposed.axes;
+            let cos_angle: f32 = x.iter().zip(z.iter()).map(|(a, b)| a * b).sum();
+            if cos_angle.abs() > AXIS_ORTHOGONALITY_TOLERANCE {
+                return Err(malformed("x- and z-axis are not orthogonal"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the metadata keys of all nodes against the given set of allowed keys and returns
+    /// a diagnostic for each key outside the set, e.g., to enforce consistent metadata in CI.
+    ///
+    /// # Arguments
+    /// * `allowed` - The allowed metadata keys.
+    pub fn validate_metadata_keys(&self, allowed: &HashSet<String>) -> Vec<MetadataDiagnostic> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, node)| {
+                node.get_metadata()
+                    .iter()
+                    .filter(|m| !allowed.contains(&m.key))
+                    .map(move |m| MetadataDiagnostic::UnknownKey(index, m.key.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the indices of the leaf nodes whose net transformation from the root node is
+    /// mirrored, i.e., its rotation part has a negative determinant. This usually indicates an
+    /// authoring error. A leaf is reported if it is mirrored on any of its paths from the root.
+    pub fn mirrored_leaves(&self) -> Vec<usize> {
+        let mut result = Vec::new();
+        if self.nodes.is_empty() {
+            return result;
+        }
+
+        // the handedness of the net transformation only depends on the signs of the determinants
+        let mut visited = HashSet::new();
+        let mut stack = vec![(0usize, false)];
+        while let Some((index, parent_mirrored)) = stack.pop() {
+            let node = &self.nodes[index];
+            let mirrored = parent_mirrored ^ (linear_determinant(node.get_transform()) < 0.0);
+            if !visited.insert((index, mirrored)) {
+                continue;
+            }
+
+            if node.get_children().is_empty() {
+                if mirrored {
+                    result.push(index);
                 }
+            } else {
+                stack.extend(node.get_child_indices().map(|child| (child, mirrored)));
             }
         }
 
+        result.sort_unstable();
+        result.dedup();
+
+        result
+    }
+
+    /// Removes all nodes for which `keep` returns false and rewires the children indices. The
+    /// root node, i.e., the first node, is always kept. Nodes that are not reachable from the root
+    /// node anymore are removed as well. Returns an error if the assembly is not valid, e.g., if
+    /// the children of the nodes form a cycle.
+    ///
+    /// # Arguments
+    /// * `keep` - Returns true if the given node is to be kept.
+    /// * `policy` - Determines what happens to the children of the removed nodes.
+    pub fn prune<F: Fn(&Node) -> bool>(&mut self, keep: F, policy: PrunePolicy) -> Result<()> {
+        if self.nodes.is_empty() {
+            return Ok(());
+        }
+
+        // the pruner follows the children recursively and would not terminate on a cycle
+        self.is_valid()?;
+
+        let mut pruner = Pruner {
+            assembly: self,
+            keep,
+            policy,
+            nodes: Vec::new(),
+            mapping: HashMap::new(),
+        };
+
+        pruner.add_node(0, &identity_matrix());
+
+        self.nodes = pruner.nodes;
+
         Ok(())
     }
 }
+
+/// Builds the nodes of a pruned assembly.
+struct Pruner<'a, F: Fn(&Node) -> bool> {
+    assembly: &'a Assembly,
+    keep: F,
+    policy: PrunePolicy,
+
+    /// The pruned nodes.
+    nodes: Vec<Node>,
+
+    /// Maps the original node index and the transformation of the removed ancestors, encoded as
+    /// bits, to the index of the pruned node.
+    mapping: HashMap<(usize, [u32; 16]), usize>,
+}
+
+impl<'a, F: Fn(&Node) -> bool> Pruner<'a, F> {
+    /// Adds the given node and its kept descendants and returns the new index of the node. A
+    /// reparented node is duplicated if it has to be placed with a different transformation.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node in the original assembly.
+    /// * `transform` - The transformation of the removed ancestors of the node.
+    fn add_node(&mut self, index: usize, transform: &[f32; 16]) -> usize {
+        let key = (index, transform.map(f32::to_bits));
+        if let Some(new_index) = self.mapping.get(&key) {
+            return *new_index;
+        }
+
+        let node = &self.assembly.nodes[index];
+        let mut new_node = Node {
+            children: Vec::new(),
+            ..node.clone()
+        };
+
+        // the combined translation is given in the unit of the assembly
+        if *transform != identity_matrix() {
+            new_node.transform =
+                multiply_matrices(transform, &self.assembly.get_node_transform(node));
+            new_node.unit = None;
+        }
+
+        let new_index = self.nodes.len();
+        self.nodes.push(new_node);
+        self.mapping.insert(key, new_index);
+
+        let mut children = Vec::new();
+        self.add_children(index, &identity_matrix(), &mut children);
+        self.nodes[new_index].children = children;
+
+        new_index
+    }
+
+    /// Adds the kept children of the given node and collects the edges to their new indices. The
+    /// attributes of the edges are kept.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node in the original assembly.
+    /// * `transform` - The transformation of the removed ancestors of the children.
+    /// * `children` - The collected edges to the children.
+    fn add_children(&mut self, index: usize, transform: &[f32; 16], children: &mut Vec<ChildEdge>) {
+        let assembly = self.assembly;
+
+        for edge in assembly.nodes[index].get_children() {
+            let child_node = &assembly.nodes[edge.get_node()];
+
+            if (self.keep)(child_node) {
+                children.push(ChildEdge {
+                    node: self.add_node(edge.get_node(), transform),
+                    ..edge.clone()
+                });
+            } else if self.policy == PrunePolicy::Reparent {
+                let child_transform =
+                    multiply_matrices(transform, &assembly.get_node_transform(child_node));
+                self.add_children(edge.get_node(), &child_transform, children);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Creates the assembly root -> (bolt, group -> (bolt, part)) with a translated group.
+    fn create_assembly() -> Assembly {
+        let mut root = Node::new("root");
+        root.add_child(1);
+        root.add_child(2);
+
+        let mut group = Node::new("group");
+        group.set_transform([
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 5.0, 0.0, 0.0, 1.0,
+        ]);
+        group.add_child(1);
+        group.add_child(3);
+
+        Assembly {
+            nodes: vec![root, Node::new("bolt"), group, Node::new("part")],
+            unit: None,
+        }
+    }
+
+    fn labels(assembly: &Assembly) -> Vec<&str> {
+        assembly.nodes.iter().map(|n| n.get_label()).collect()
+    }
+
+    #[test]
+    fn test_prune_leaves() {
+        let mut assembly = create_assembly();
+        assembly
+            .prune(|n| n.get_label() != "bolt", PrunePolicy::Reparent)
+            .unwrap();
+
+        assert!(assembly.is_valid().is_ok());
+        assert_eq!(labels(&assembly), vec!["root", "group", "part"]);
+        assert_eq!(
+            assembly.nodes[0].get_child_indices().collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(
+            assembly.nodes[1].get_child_indices().collect::<Vec<_>>(),
+            [2]
+        );
+        assert_eq!(assembly.nodes[2].get_transform(), &identity_matrix());
+    }
+
+    #[test]
+    fn test_prune_reparent() {
+        let mut assembly = create_assembly();
+        assembly
+            .prune(|n| n.get_label() != "group", PrunePolicy::Reparent)
+            .unwrap();
+
+        assert!(assembly.is_valid().is_ok());
+        assert_eq!(labels(&assembly), vec!["root", "bolt", "bolt", "part"]);
+        assert_eq!(
+            assembly.nodes[0].get_child_indices().collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+
+        // the reparented nodes are placed with the transformation of the removed group
+        assert_eq!(assembly.nodes[1].get_transform(), &identity_matrix());
+        assert_eq!(assembly.nodes[2].get_transform()[12], 5.0);
+        assert_eq!(assembly.nodes[3].get_transform()[12], 5.0);
+    }
+
+    #[test]
+    fn test_mirrored_leaves() {
+        let mut assembly = create_assembly();
+        assert!(assembly.mirrored_leaves().is_empty());
+
+        // mirroring the group flips the handedness of the part and of the bolt instance within
+        // the group
+        let mut mirror = identity_matrix();
+        mirror[0] = -1.0;
+        assembly.nodes[2].set_transform(mirror);
+        assert_eq!(assembly.mirrored_leaves(), vec![1, 3]);
+
+        // mirroring the part again restores its handedness
+        assembly.nodes[3].set_transform(mirror);
+        assert_eq!(assembly.mirrored_leaves(), vec![1]);
+    }
+
+    #[test]
+    fn test_validate_metadata_keys() {
+        let mut assembly = create_assembly();
+        assembly.nodes[1].add_metadata("material", "steel");
+        assembly.nodes[3].add_metadata("mass", "1.5");
+        assembly.nodes[3].add_metadata("colour", "red");
+
+        let allowed = ["material", "mass", "supplier"]
+            .into_iter()
+            .map(|k| k.to_owned())
+            .collect::<HashSet<_>>();
+        let diagnostics = assembly.validate_metadata_keys(&allowed);
+        assert_eq!(
+            diagnostics,
+            vec![MetadataDiagnostic::UnknownKey(3, "colour".to_owned())]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "Node 3 has unknown metadata key 'colour'"
+        );
+    }
+
+    #[test]
+    fn test_is_valid_cycles() {
+        let mut assembly = create_assembly();
+        assert!(assembly.is_valid().is_ok());
+
+        // the part references the group, which contains the part
+        assembly.nodes[3].add_child(2);
+        assert!(matches!(
+            assembly.is_valid(),
+            Err(Error::CyclicAssembly(labels)) if labels == ["group", "part", "group"]
+        ));
+
+        // a node referencing itself
+        let mut node = Node::new("self");
+        node.add_child(0);
+        let assembly = Assembly {
+            nodes: vec![node],
+            unit: None,
+        };
+        assert!(matches!(
+            assembly.is_valid(),
+            Err(Error::CyclicAssembly(labels)) if labels == ["self", "self"]
+        ));
+    }
+
+    #[test]
+    fn test_validate_transforms() {
+        let mut assembly = create_assembly();
+        assert!(assembly.validate_transforms().is_ok());
+
+        let check = |assembly: &mut Assembly, index: usize, value: f32| {
+            let mut transform = identity_matrix();
+            transform[index] = value;
+            assembly.nodes[3].set_transform(transform);
+            assembly.validate_transforms()
+        };
+
+        // uniform scaling and translations are fine
+        assert!(check(&mut assembly, 15, 1.0).is_ok());
+        assert!(check(&mut assembly, 12, 3.0).is_ok());
+
+        // non-finite values, degenerate axes and skewed axes are rejected
+        for (index, value) in [(13, f32::NAN), (0, 0.0), (10, 0.0), (8, 1.0)] {
+            assert!(matches!(
+                check(&mut assembly, index, value),
+                Err(Error::MalformedTransform(label, _)) if label == "part"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_prune_drop() {
+        let mut assembly = create_assembly();
+        assembly
+            .prune(|n| n.get_label() != "group", PrunePolicy::Drop)
+            .unwrap();
+
+        assert!(assembly.is_valid().is_ok());
+        assert_eq!(labels(&assembly), vec!["root", "bolt"]);
+        assert_eq!(
+            assembly.nodes[0].get_child_indices().collect::<Vec<_>>(),
+            [1]
+        );
+    }
+
+    #[test]
+    fn test_child_edge_serialization() {
+        let assembly: Assembly = serde_json::from_str(
+            r#"{"nodes": [
+                {"label": "root", "children": [1, {"node": 2, "relationship_name": "Mate 1"}]},
+                {"label": "bolt"},
+                {"label": "nut"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let children = assembly.nodes[0].get_children();
+        assert_eq!(children[0], ChildEdge::new(1));
+        assert_eq!(children[1].get_node(), 2);
+        assert_eq!(children[1].get_relationship_name(), Some("Mate 1"));
+
+        // edges without attributes are written as plain indices
+        let value = serde_json::to_value(&assembly.nodes[0]).unwrap();
+        assert_eq!(
+            value["children"],
+            serde_json::json!([1, {"node": 2, "relationship_name": "Mate 1"}])
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_edge_attributes() {
+        let mut assembly = create_assembly();
+        assembly.nodes[2].children[1].set_relationship_name("Mate 1");
+        assembly
+            .prune(|n| n.get_label() != "group", PrunePolicy::Reparent)
+            .unwrap();
+
+        let names: Vec<Option<&str>> = assembly.nodes[0]
+            .get_children()
+            .iter()
+            .map(ChildEdge::get_relationship_name)
+            .collect();
+        assert_eq!(names, vec![None, None, Some("Mate 1")]);
+    }
+
+    #[test]
+    fn test_node_transform_unit() {
+        let mut transform = identity_matrix();
+        transform[12] = 2.0;
+        transform[13] = -0.5;
+
+        let mut node = Node::new("Part");
+        node.set_transform(transform);
+        node.set_unit(LengthUnit::Inch);
+
+        let mut assembly = Assembly {
+            nodes: vec![node],
+            unit: Some(LengthUnit::Inch),
+        };
+        assert_eq!(assembly.get_node_transform(&assembly.nodes[0]), transform);
+
+        assembly.unit = Some(LengthUnit::Millimeter);
+        let converted = assembly.get_node_transform(&assembly.nodes[0]);
+        assert_eq!(converted[12..15], [50.8, -12.7, 0.0]);
+        assert_eq!(converted[..12], transform[..12]);
+
+        // nodes without a unit use the unit of the assembly
+        assembly.nodes[0] = serde_json::from_value(serde_json::json!({
+            "label": "Part",
+            "transform": transform,
+        }))
+        .unwrap();
+        assert_eq!(assembly.nodes[0].get_unit(), None);
+        assert_eq!(assembly.get_node_transform(&assembly.nodes[0]), transform);
+
+        let node: Node = serde_json::from_value(serde_json::json!({
+            "label": "Part",
+            "unit": "inch",
+        }))
+        .unwrap();
+        assert_eq!(node.get_unit(), Some(LengthUnit::Inch));
+    }
+
+    #[test]
+    fn test_prune_cycle() {
+        let mut assembly = create_assembly();
+        assembly.nodes[3].add_child(2);
+        assert!(matches!(
+            assembly.prune(|n| n.get_label() != "bolt", PrunePolicy::Reparent),
+            Err(Error::CyclicAssembly(_))
+        ));
+        assert_eq!(assembly.nodes.len(), 4);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc::{self, Sender},
+};
+
+use crate::{
+    merge_assembly_structure_to_step_with_vfs, Assembly, Error, MergeOptions, MergeOutput, Result,
+    Vfs,
+};
+
+/// The number of chunks that can be queued before the merge waits for the async writer.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// The size of the chunks sent over the channel.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A writer that sends the written data in chunks over a tokio channel, e.g., to stream the
+/// merged step file from a blocking task to an async consumer. The writer blocks while the
+/// channel is full and must therefore not be used within an async context, but within
+/// `tokio::task::spawn_blocking` or a separate thread.
+#[derive(Debug)]
+pub struct ChannelWriter {
+    sender: Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    /// Creates a new writer that sends the data over the given channel.
+    ///
+    /// # Arguments
+    /// * `sender` - The sending half of the channel.
+    pub fn new(sender: Sender<Vec<u8>>) -> Self {
+        ChannelWriter {
+            sender,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    /// Sends the buffered data over the channel.
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .blocking_send(chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Channel closed"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+impl Drop for ChannelWriter {
+    fn drop(&mut self) {
+        // the receiver may already be gone, e.g., if the async writer failed
+        let _ = self.send_buffer();
+    }
+}
+
+/// The function consumes the given assembly structure and writes the merged step data to the given
+/// async writer.
+/// The merge itself is executed on a blocking task of the tokio runtime, which streams the merged
+/// step data in chunks to the writer. Thus, the executor is not blocked by the merge.
+///
+/// # Arguments
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+/// * `writer` - The async writer for the merged step file.
+/// * `vfs` - The virtual file system for loading the referenced step files.
+pub async fn merge_assembly_structure_to_async_writer<W, V>(
+    assembly: Assembly,
+    options: MergeOptions,
+    writer: &mut W,
+    vfs: V,
+) -> Result<MergeOutput>
+where
+    W: AsyncWrite + Unpin,
+    V: Vfs + Send + 'static,
+{
+    let (sender, mut receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    let task = tokio::task::spawn_blocking(move || {
+        merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &options,
+            ChannelWriter::new(sender),
+            &vfs,
+        )
+    });
+
+    // dropping the receiver on error makes the merge fail with a broken pipe
+    while let Some(chunk) = receiver.recv().await {
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(|e| Error::StepFileWrite(Arc::new(e)))?;
+    }
+
+    writer
+        .flush()
+        .await
+        .map_err(|e| Error::StepFileWrite(Arc::new(e)))?;
+
+    task.await
+        .map_err(|e| Error::IO(Arc::new(io::Error::other(e))))?
+}
+
+#[cfg(test)]
+mod test {
+    use crate::MemoryVfs;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_merge_to_async_writer() {
+        let assembly = include_bytes!("../../test_data/cube-and-sphere.json");
+        let assembly: Assembly = serde_json::from_slice(assembly).unwrap();
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../test_data/cube.stp"));
+        vfs.insert("sphere.stp", include_bytes!("../../test_data/sphere.stp"));
+
+        let mut options = MergeOptions::default();
+        options.header_info.time_stamp = Some("2024-05-08T18:23:30".to_owned());
+
+        let mut expected = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut expected, &vfs)
+            .unwrap();
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_async_writer(assembly, options, &mut output, vfs)
+            .await
+            .unwrap();
+
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Assembly, Error, LengthUnit, Node, Result};
+
+/// The id of a node added to an `AssemblyBuilder`, i.e., the index of the node in the assembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Returns the index of the node in the built assembly.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A builder for constructing assembly structures programmatically. The first added node is the
+/// root node of the assembly.
+#[derive(Debug, Clone, Default)]
+pub struct AssemblyBuilder {
+    nodes: Vec<Node>,
+    unit: Option<LengthUnit>,
+}
+
+impl AssemblyBuilder {
+    /// Creates a new builder without any nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a new node with the given label and returns its id.
+    ///
+    /// # Arguments
+    /// * `label` - The label of the node.
+    pub fn add_node(&mut self, label: &str) -> NodeId {
+        self.nodes.push(Node::new(label));
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Sets the column-major 4x4 transformation matrix of the given node.
+    ///
+    /// # Arguments
+    /// * `node` - The id of the node.
+    /// * `transform` - The transformation matrix.
+    pub fn set_transform(&mut self, node: NodeId, transform: [f32; 16]) -> Result<&mut Self> {
+        self.node_mut(node)?.set_transform(transform);
+        Ok(self)
+    }
+
+    /// Adds a metadata entry to the given node.
+    ///
+    /// # Arguments
+    /// * `node` - The id of the node.
+    /// * `key` - The key of the metadata entry.
+    /// * `value` - The value of the metadata entry.
+    pub fn add_metadata(&mut self, node: NodeId, key: &str, value: &str) -> Result<&mut Self> {
+        self.node_mut(node)?.add_metadata(key, value);
+        Ok(self)
+    }
+
+    /// Links the given node to a STEP file.
+    ///
+    /// # Arguments
+    /// * `node` - The id of the node.
+    /// * `path` - The path to the STEP file.
+    pub fn link(&mut self, node: NodeId, path: &str) -> Result<&mut Self> {
+        self.node_mut(node)?.set_link(path);
+        Ok(self)
+    }
+
+    /// Adds the child node to the children of the parent node.
+    ///
+    /// # Arguments
+    /// * `parent` - The id of the parent node.
+    /// * `child` - The id of the child node.
+    pub fn add_child(&mut self, parent: NodeId, child: NodeId) -> Result<&mut Self> {
+        self.node_mut(child)?;
+        self.node_mut(parent)?.add_child(child.0);
+        Ok(self)
+    }
+
+    /// Sets the length unit of the assembly.
+    ///
+    /// # Arguments
+    /// * `unit` - The length unit.
+    pub fn set_unit(&mut self, unit: LengthUnit) -> &mut Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Builds the assembly and checks it for validity, e.g., for cycles between the nodes.
+    pub fn build(self) -> Result<Assembly> {
+        let assembly = Assembly {
+            nodes: self.nodes,
+            unit: self.unit,
+        };
+        assembly.is_valid()?;
+
+        Ok(assembly)
+    }
+
+    /// Returns the node with the given id or an error if it does not exist.
+    ///
+    /// # Arguments
+    /// * `node` - The id of the node.
+    fn node_mut(&mut self, node: NodeId) -> Result<&mut Node> {
+        self.nodes
+            .get_mut(node.0)
+            .ok_or(Error::InvalidNodeId(node.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::identity_matrix;
+
+    use super::*;
+
+    #[test]
+    fn test_assembly_builder() {
+        let mut builder = AssemblyBuilder::new();
+        let root = builder.add_node("Root");
+        let cube = builder.add_node("Cube");
+
+        let mut transform = identity_matrix();
+        transform[12] = 10.0;
+        builder
+            .add_child(root, cube)
+            .unwrap()
+            .link(cube, "cube.stp")
+            .unwrap()
+            .set_transform(cube, transform)
+            .unwrap()
+            .add_metadata(cube, "material", "steel")
+            .unwrap();
+
+        let assembly = builder.build().unwrap();
+        assert_eq!(assembly.nodes.len(), 2);
+        assert_eq!(
+            assembly.nodes[root.index()]
+                .get_child_indices()
+                .collect::<Vec<_>>(),
+            [cube.index()]
+        );
+        assert_eq!(assembly.nodes[1].get_link(), Some("cube.stp"));
+        assert_eq!(assembly.nodes[1].get_transform()[12], 10.0);
+        assert_eq!(
+            assembly.nodes[1].get_metadata_value("material"),
+            Some("steel")
+        );
+    }
+
+    #[test]
+    fn test_assembly_builder_invalid() {
+        let mut other = AssemblyBuilder::new();
+        other.add_node("A");
+        let foreign = other.add_node("B");
+
+        let mut builder = AssemblyBuilder::new();
+        let root = builder.add_node("Root");
+        assert!(matches!(
+            builder.add_child(root, foreign),
+            Err(Error::InvalidNodeId(1))
+        ));
+
+        // cycles are detected when building
+        builder.add_child(root, root).unwrap();
+        assert!(matches!(builder.build(), Err(Error::CyclicAssembly(_))));
+    }
+}
@@ -10,6 +10,12 @@ pub enum Error {
     #[error("Invalid child index {0} in node {1}")]
     InvalidFormat(usize, String),
 
+    #[error("Invalid node id {0}")]
+    InvalidNodeId(usize),
+
+    #[error("Cyclic assembly structure: {}", .0.join(" -> "))]
+    CyclicAssembly(Vec<String>),
+
     #[error("Failed to write step file")]
     StepFileWrite(#[source] Arc<std::io::Error>),
 
@@ -48,6 +54,48 @@ pub enum Error {
 
     #[error("No APPLICATION_CONTEXT entry found in step file {0}")]
     AppContextMissing(String),
+
+    #[error("Exceeded the maximum number of {0} entries")]
+    MaxEntriesExceeded(usize),
+
+    #[error("Malformed definition of entry #{0}")]
+    MalformedEntry(u64),
+
+    #[error("AXIS2_PLACEMENT_3D entry #{0} does not reference a point and two directions")]
+    InvalidPlacement(u64),
+
+    #[error("Invalid reference #0 in entry #{0}")]
+    ZeroReference(u64),
+
+    #[error("Transform of node {0} is singular and cannot be inverted")]
+    SingularTransform(String),
+
+    #[error("Transform of node {0} is malformed: {1}")]
+    MalformedTransform(String, String),
+
+    #[error("Entry #{0} has the entity {1}, which is unknown to the application protocol")]
+    UnknownEntity(u64, String),
+
+    #[error("Invalid control directive in string '{0}'")]
+    InvalidStringEncoding(String),
+
+    #[error("Duplicate entry id #{0}")]
+    DuplicateId(u64),
+
+    #[error("Entry #{0} references the nonexistent entry #{1}")]
+    UnresolvedReference(u64, u64),
+
+    #[error("Cannot write to the step writer after it has been finalized")]
+    WriterFinalized(),
+
+    #[error("Unsupported encoding {0}, expected ISO-10303-21")]
+    UnsupportedEncoding(String),
+
+    #[error("Incompatible merge options: {0}")]
+    IncompatibleOptions(String),
+
+    #[error("Step file {0} declares external references, which cannot be merged")]
+    ExternalReferences(String),
 }
 
 /// The result type used in this crate.
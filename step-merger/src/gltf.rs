@@ -0,0 +1,224 @@
+use std::{io::Write, sync::Arc};
+
+use serde_json::{json, Map, Value};
+
+use crate::{identity_matrix, Assembly, Error, Result};
+
+impl Assembly {
+    /// Writes the assembly structure as glTF 2.0 scene graph without any geometry. The
+    /// transformations are written as node matrices, whereas the metadata and the links to the
+    /// STEP files are written into the `extras` of the nodes. As glTF requires a strict tree,
+    /// nodes with multiple parents are duplicated for each instance. The translations are
+    /// converted into meters, which is the unit of glTF. Fails if the assembly is not valid, e.g.,
+    /// if it contains cycles.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the glTF JSON to.
+    pub fn to_gltf<W: Write>(&self, writer: W) -> Result<()> {
+        self.is_valid()?;
+
+        let scale = self.unit.map_or(1.0, |unit| unit.meters() as f32);
+        let mut nodes = Vec::new();
+        let scene_nodes = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![self.add_gltf_node(0, scale, &mut nodes)]
+        };
+
+        let gltf = json!({
+            "asset": {
+                "version": "2.0",
+                "generator": "step-merger",
+            },
+            "scene": 0,
+            "scenes": [{ "nodes": scene_nodes }],
+            "nodes": nodes,
+        });
+
+        serde_json::to_writer_pretty(writer, &gltf).map_err(|e| Error::IO(Arc::new(e.into())))
+    }
+
+    /// Adds the glTF node for the given assembly node and its descendants. Returns the index of
+    /// the added glTF node.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the assembly node.
+    /// * `scale` - The factor to convert the translations into meters.
+    /// * `nodes` - The glTF nodes.
+    fn add_gltf_node(&self, index: usize, scale: f32, nodes: &mut Vec<Value>) -> usize {
+        let node = &self.nodes[index];
+
+        let gltf_index = nodes.len();
+        nodes.push(Value::Null);
+
+        let children: Vec<usize> = node
+            .get_child_indices()
+            .map(|child| self.add_gltf_node(child, scale, nodes))
+            .collect();
+
+        let mut gltf_node = Map::new();
+        gltf_node.insert("name".to_owned(), json!(node.get_label()));
+        if !children.is_empty() {
+            gltf_node.insert("children".to_owned(), json!(children));
+        }
+
+        if *node.get_transform() != identity_matrix() {
+            let mut matrix = self.get_node_transform(node);
+            for value in matrix[12..15].iter_mut() {
+                *value *= scale;
+            }
+
+            gltf_node.insert("matrix".to_owned(), json!(matrix));
+        }
+
+        let mut extras = Map::new();
+        if let Some(link) = node.get_link() {
+            extras.insert("link".to_owned(), json!(link));
+        }
+
+        if !node.get_metadata().is_empty() {
+            extras.insert("metadata".to_owned(), json!(node.get_metadata()));
+        }
+
+        if !extras.is_empty() {
+            gltf_node.insert("extras".to_owned(), Value::Object(extras));
+        }
+
+        nodes[gltf_index] = Value::Object(gltf_node);
+
+        gltf_index
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{LengthUnit, MetadataEntry, Node};
+
+    use super::*;
+
+    /// Creates an assembly from the nodes of the given glTF JSON.
+    fn from_gltf(gltf: &Value) -> Assembly {
+        let nodes = gltf["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|gltf_node| {
+                let mut node = Node::new(gltf_node["name"].as_str().unwrap());
+
+                if let Some(matrix) = gltf_node.get("matrix") {
+                    node.set_transform(serde_json::from_value(matrix.clone()).unwrap());
+                }
+
+                if let Some(link) = gltf_node["extras"].get("link") {
+                    node.set_link(link.as_str().unwrap());
+                }
+
+                if let Some(metadata) = gltf_node["extras"].get("metadata") {
+                    let metadata: Vec<MetadataEntry> =
+                        serde_json::from_value(metadata.clone()).unwrap();
+                    for entry in metadata.iter() {
+                        node.add_metadata(&entry.key, &entry.value);
+                    }
+                }
+
+                for child in gltf_node["children"].as_array().into_iter().flatten() {
+                    node.add_child(child.as_u64().unwrap() as usize);
+                }
+
+                node
+            })
+            .collect();
+
+        Assembly { nodes, unit: None }
+    }
+
+    #[test]
+    fn test_to_gltf() {
+        let assembly = include_bytes!("../../test_data/cube-and-sphere.json");
+        let assembly: Assembly = serde_json::from_slice(assembly).unwrap();
+
+        let mut output = Vec::new();
+        assembly.to_gltf(&mut output).unwrap();
+
+        let gltf: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(gltf["asset"]["version"], "2.0");
+        assert_eq!(gltf["scenes"][0]["nodes"], json!([0]));
+
+        let imported = from_gltf(&gltf);
+        assert_eq!(
+            serde_json::to_value(&imported).unwrap(),
+            serde_json::to_value(&assembly).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_gltf_shared_node() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(1);
+        let mut cube = Node::new("Cube");
+        cube.set_link("cube.stp");
+        let assembly = Assembly {
+            nodes: vec![root, cube],
+            unit: None,
+        };
+
+        let mut output = Vec::new();
+        assembly.to_gltf(&mut output).unwrap();
+
+        // the shared node is duplicated for each instance
+        let imported = from_gltf(&serde_json::from_slice(&output).unwrap());
+        assert!(imported.is_valid().is_ok());
+        assert_eq!(imported.nodes.len(), 3);
+        assert_eq!(
+            imported.nodes[0].get_child_indices().collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert_eq!(imported.nodes[2].get_link(), Some("cube.stp"));
+    }
+
+    #[test]
+    fn test_to_gltf_unit() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        let mut cube = Node::new("Cube");
+        let mut transform = identity_matrix();
+        transform[12] = 250.0;
+        cube.set_transform(transform);
+        let assembly = Assembly {
+            nodes: vec![root, cube],
+            unit: Some(LengthUnit::Millimeter),
+        };
+
+        let mut output = Vec::new();
+        assembly.to_gltf(&mut output).unwrap();
+
+        // the translations are given in meters
+        let gltf: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(gltf["nodes"][1]["matrix"][12], json!(0.25));
+    }
+
+    #[test]
+    fn test_to_gltf_invalid() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        let mut child = Node::new("Child");
+        child.add_child(0);
+        let assembly = Assembly {
+            nodes: vec![root, child],
+            unit: None,
+        };
+        assert!(matches!(
+            assembly.to_gltf(Vec::new()),
+            Err(Error::CyclicAssembly(_))
+        ));
+
+        let mut root = Node::new("Root");
+        root.add_child(5);
+        let assembly = Assembly {
+            nodes: vec![root],
+            unit: None,
+        };
+        assert!(assembly.to_gltf(Vec::new()).is_err());
+    }
+}
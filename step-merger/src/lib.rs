@@ -1,8 +1,27 @@
 mod assembly;
+#[cfg(feature = "tokio")]
+mod async_writer;
+mod builder;
 mod error;
+mod gltf;
+mod matrix;
 mod merge;
 pub mod step;
+mod tee;
+mod vfs;
 
 pub use assembly::*;
+#[cfg(feature = "tokio")]
+pub use async_writer::*;
+pub use builder::*;
 pub use error::*;
-pub use merge::{merge_assembly_structure_to_step, resolve_file};
+pub use matrix::*;
+pub use merge::{
+    merge_assembly_file_to_step, merge_assembly_structure_to_step,
+    merge_assembly_structure_to_step_with_options, merge_assembly_structure_to_step_with_progress,
+    merge_assembly_structure_to_step_with_vfs, resolve_file, Conformance, MergeDiagnostic,
+    MergeOptions, MergeOutput, MergeProgress, NodeStepIds, OnMissingReference, RootNodeKeywords,
+    TransformSemantics,
+};
+pub use tee::*;
+pub use vfs::*;
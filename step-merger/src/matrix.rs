@@ -0,0 +1,223 @@
+/// Multiplies the two given column-major encoded 4x4 matrices and returns `a * b`.
+///
+/// # Arguments
+/// * `a` - The left-hand side matrix.
+/// * `b` - The right-hand side matrix.
+pub fn multiply_matrices(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut result = [0.0; 16];
+
+    for col in 0..4 {
+        for row in 0..4 {
+            result[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+
+    result
+}
+
+/// A transformation matrix decomposed into translation, rotation and scaling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecomposedTransform {
+    /// The translation vector.
+    pub translation: [f32; 3],
+
+    /// The normalized x-, y- and z-axis. Axes with zero length are left unchanged.
+    pub axes: [[f32; 3]; 3],
+
+    /// The scaling factors along the x-, y- and z-axis, i.e., the lengths of the axes.
+    pub scale: [f32; 3],
+}
+
+/// Decomposes the given column-major encoded 4x4 matrix into translation, rotation and scaling.
+/// Shearing and projective parts are not considered.
+///
+/// # Arguments
+/// * `m` - The matrix to decompose.
+pub fn decompose_matrix(m: &[f32; 16]) -> DecomposedTransform {
+    let mut axes = [[0.0; 3]; 3];
+    let mut scale = [0.0; 3];
+
+    for (i, (axis, scale)) in axes.iter_mut().zip(scale.iter_mut()).enumerate() {
+        axis.copy_from_slice(&m[i * 4..i * 4 + 3]);
+        *scale = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+        if *scale > 0.0 {
+            axis.iter_mut().for_each(|v| *v /= *scale);
+        }
+    }
+
+    DecomposedTransform {
+        translation: [m[12], m[13], m[14]],
+        axes,
+        scale,
+    }
+}
+
+/// Computes the determinant of the upper-left 3x3 part of the given column-major encoded 4x4
+/// matrix, i.e., of its rotation and scaling. A negative determinant indicates a mirroring.
+///
+/// # Arguments
+/// * `m` - The matrix.
+pub fn linear_determinant(m: &[f32; 16]) -> f32 {
+    m[0] * (m[5] * m[10] - m[9] * m[6]) - m[4] * (m[1] * m[10] - m[9] * m[2])
+        + m[8] * (m[1] * m[6] - m[5] * m[2])
+}
+
+/// Computes the inverse of the given column-major encoded 4x4 matrix. Returns None if the matrix
+/// is not invertible.
+///
+/// # Arguments
+/// * `m` - The matrix to invert.
+pub fn invert_matrix(m: &[f32; 16]) -> Option<[f32; 16]> {
+    let mut inv = [0.0f32; 16];
+
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14]
+        + m[13] * m[6] * m[11]
+        - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14]
+        - m[12] * m[6] * m[11]
+        + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13]
+        + m[12] * m[5] * m[11]
+        - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13]
+        - m[12] * m[5] * m[10]
+        + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14]
+        - m[13] * m[2] * m[11]
+        + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14]
+        + m[12] * m[2] * m[11]
+        - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13]
+        - m[12] * m[1] * m[11]
+        + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13]
+        + m[12] * m[1] * m[10]
+        - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14]
+        + m[13] * m[2] * m[7]
+        - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14]
+        - m[12] * m[2] * m[7]
+        + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13]
+        + m[12] * m[1] * m[7]
+        - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13]
+        - m[12] * m[1] * m[6]
+        + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10]
+        - m[9] * m[2] * m[7]
+        + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10]
+        + m[8] * m[2] * m[7]
+        - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9]
+        - m[8] * m[1] * m[7]
+        + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9]
+        + m[8] * m[1] * m[6]
+        - m[8] * m[2] * m[5];
+
+    let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    Some(inv.map(|v| v * inv_det))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::identity_matrix;
+
+    use super::*;
+
+    /// Returns a translation matrix for the given offset.
+    fn translation(x: f32, y: f32, z: f32) -> [f32; 16] {
+        let mut m = identity_matrix();
+        m[12] = x;
+        m[13] = y;
+        m[14] = z;
+        m
+    }
+
+    #[test]
+    fn test_multiply_matrices() {
+        let a = translation(1.0, 2.0, 3.0);
+        let b = translation(-1.0, 1.0, 0.5);
+
+        assert_eq!(multiply_matrices(&a, &b), translation(0.0, 3.0, 3.5));
+        assert_eq!(multiply_matrices(&a, &identity_matrix()), a);
+
+        // rotation of 90 degrees around the z-axis followed by a translation
+        let rotation = [
+            0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ];
+        let m = multiply_matrices(&rotation, &translation(1.0, 0.0, 0.0));
+        assert_eq!(&m[12..15], &[0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_decompose_matrix() {
+        let m = [
+            0.0, 2.0, 0.0, 0.0, -3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 4.0, 0.0, 1.0, 2.0, 3.0, 1.0,
+        ];
+        let decomposed = decompose_matrix(&m);
+
+        assert_eq!(decomposed.translation, [1.0, 2.0, 3.0]);
+        assert_eq!(decomposed.scale, [2.0, 3.0, 4.0]);
+        assert_eq!(
+            decomposed.axes,
+            [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+        );
+
+        let decomposed = decompose_matrix(&identity_matrix());
+        assert_eq!(decomposed.scale, [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_linear_determinant() {
+        assert_eq!(linear_determinant(&identity_matrix()), 1.0);
+        assert_eq!(linear_determinant(&translation(1.0, 2.0, 3.0)), 1.0);
+
+        let mut mirror = identity_matrix();
+        mirror[0] = -2.0;
+        assert_eq!(linear_determinant(&mirror), -2.0);
+    }
+
+    #[test]
+    fn test_invert_matrix() {
+        let m = [
+            0.0, 2.0, 0.0, 0.0, -2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 1.0, 2.0, 3.0, 1.0,
+        ];
+        let inv = invert_matrix(&m).unwrap();
+
+        let product = multiply_matrices(&m, &inv);
+        for (v, expected) in product.iter().zip(identity_matrix().iter()) {
+            assert!((v - expected).abs() < 1e-6);
+        }
+
+        let mut singular = identity_matrix();
+        singular[0] = 0.0;
+        assert!(invert_matrix(&singular).is_none());
+    }
+}
@@ -44,7 +44,7 @@ impl<Item: Clone + Sized, I: Iterator<Item = Item>> BufferedIterator<Item, I> {
     }
 
     /// Returns an iterator over the items produced by the iterator.
-    pub fn iter(&mut self) -> BufferedIteratorIter<Item, I> {
+    pub fn iter(&mut self) -> BufferedIteratorIter<'_, Item, I> {
         BufferedIteratorIter { buffer: self }
     }
 
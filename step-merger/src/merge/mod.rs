@@ -1,25 +1,44 @@
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt::Display,
     fs::File,
-    io::{Read, Write},
+    hash::{Hash, Hasher},
+    io::{Cursor, Read, Write},
+    ops::Range,
     path::Path,
+    str::FromStr,
+    sync::Arc,
 };
 
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 
 use crate::{
-    identity_matrix,
-    merge::{
-        root_nodes::FindRootNodes,
-        utils::{get_ids_from_mechanical_part, NodeStepIds},
+    decompose_matrix, identity_matrix, invert_matrix,
+    merge::{root_nodes::FindRootNodes, utils::PlacementValidator},
+    multiply_matrices,
+    step::{
+        length_unit, plane_angle_unit, si_length_unit_factor, solid_angle_unit, EntryBuilder,
+        PresentationFilter, STEPReader, STEPReaderTrait, SiPrefix, StepData, StepEntry, StepHeader,
+        StepWriter, WriterFormat,
     },
-    step::{STEPReader, STEPReaderTrait, StepEntry, StepWriter},
-    Assembly, Error, Node, Result,
+    Assembly, ChildEdge, Error, LengthUnit, LocalVfs, Node, Result, Vfs,
 };
 
 use self::buffered_iterator::BufferedIterator;
 
+pub use self::options::{Conformance, MergeOptions, OnMissingReference, TransformSemantics};
+pub use self::output::{MergeDiagnostic, MergeOutput};
+pub use self::progress::MergeProgress;
+pub use self::root_nodes::RootNodeKeywords;
+pub use self::utils::NodeStepIds;
+
 mod buffered_iterator;
+mod options;
+mod output;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod progress;
 mod root_nodes;
 mod utils;
 
@@ -54,6 +73,33 @@ pub fn merge_assembly_structure_to_step<W>(
     load_references: bool,
     writer: W,
 ) -> Result<()>
+where
+    W: Write,
+{
+    let options = MergeOptions {
+        load_references,
+        ..Default::default()
+    };
+    merge_assembly_structure_to_step_with_options(root_link, assembly, &options, writer)?;
+
+    Ok(())
+}
+
+/// The function consumes the given assembly structure and writes the merged step data to the given
+/// writer like `merge_assembly_structure_to_step`, but with the given options for the merging
+/// process.
+///
+/// # Arguments
+/// * ``root_link`` - The link to the assembly file.
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+/// * `writer` - The writer for the merged step file.
+pub fn merge_assembly_structure_to_step_with_options<W>(
+    root_link: &str,
+    assembly: &Assembly,
+    options: &MergeOptions,
+    writer: W,
+) -> Result<MergeOutput>
 where
     W: Write,
 {
@@ -64,8 +110,99 @@ where
 
     info!("Root directory: {:?}", root_dir);
 
-    let resolver = |file_path: &str| resolve_file(file_path, root_dir);
-    merge_assembly_structure_to_step_with_resolver(assembly, load_references, writer, resolver)
+    let vfs = LocalVfs::new(root_dir);
+    merge_assembly_structure_to_step_with_vfs(assembly, options, writer, &vfs)
+}
+
+/// The function loads the assembly structure from the given JSON file and writes the merged step
+/// data to the given output file.
+/// The links of the assembly structure are resolved relative to the directory of the assembly
+/// file.
+/// If a reference cannot be loaded, an error is dumped to the log and the process continues.
+///
+/// # Arguments
+/// * `assembly_path` - The path to the assembly JSON file.
+/// * `output_path` - The path to the merged step file.
+/// * `options` - The options for the merging process.
+pub fn merge_assembly_file_to_step<P, Q>(
+    assembly_path: P,
+    output_path: Q,
+    options: &MergeOptions,
+) -> Result<MergeOutput>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let assembly_path = assembly_path.as_ref();
+    let assembly = Assembly::from_file(assembly_path)?;
+
+    let root_dir = match assembly_path.parent() {
+        Some(dir) => dir,
+        None => Path::new("./"),
+    };
+    info!("Root directory: {:?}", root_dir);
+
+    let output_path = output_path.as_ref();
+    let file = File::create(output_path).map_err(|e| {
+        Error::FailedOpenFile(Arc::new(e), output_path.to_string_lossy().to_string())
+    })?;
+
+    let vfs = LocalVfs::new(root_dir);
+    merge_assembly_structure_to_step_with_vfs(&assembly, options, file, &vfs)
+}
+
+/// The function consumes the given assembly structure and writes the merged step data to the given
+/// writer.
+/// All references to external step files are loaded from the given virtual file system and merged
+/// into the final step data.
+/// If a reference cannot be opened, an error is dumped to the log and the process continues.
+/// The whole merging process is executed in a streaming fashion to reduce the memory footprint.
+///
+/// # Arguments
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+/// * `writer` - The writer for the merged step file.
+/// * `vfs` - The virtual file system for loading the referenced step files.
+pub fn merge_assembly_structure_to_step_with_vfs<W, V>(
+    assembly: &Assembly,
+    options: &MergeOptions,
+    writer: W,
+    vfs: &V,
+) -> Result<MergeOutput>
+where
+    W: Write,
+    V: Vfs + ?Sized,
+{
+    let resolver = |link: &str| vfs.open(link);
+    merge_assembly_structure_to_step_with_resolver(assembly, options, writer, resolver)
+}
+
+/// The function consumes the given assembly structure and writes the merged step data to the given
+/// writer like `merge_assembly_structure_to_step_with_vfs`, while reporting the progress of the
+/// merge to the given callback, e.g., to show a progress bar. The callback is invoked after each
+/// created node and relation, after each processed referenced step file and once the merged step
+/// file is complete.
+///
+/// # Arguments
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+/// * `writer` - The writer for the merged step file.
+/// * `vfs` - The virtual file system for loading the referenced step files.
+/// * `progress` - The callback receiving the progress of the merge.
+pub fn merge_assembly_structure_to_step_with_progress<W, V, P>(
+    assembly: &Assembly,
+    options: &MergeOptions,
+    writer: W,
+    vfs: &V,
+    mut progress: P,
+) -> Result<MergeOutput>
+where
+    W: Write,
+    V: Vfs + ?Sized,
+    P: FnMut(MergeProgress),
+{
+    let resolver = |link: &str| vfs.open(link);
+    merge_with_progress(assembly, options, writer, resolver, &mut progress)
 }
 
 /// The function consumes the given assembly structure and writes the merged step data to the given
@@ -77,23 +214,404 @@ where
 ///
 /// # Arguments
 /// * `assembly` - The assembly structure to merged.
-/// * `load_references` - Flag to indicate if external references should be loaded.
+/// * `options` - The options for the merging process.
 /// * `writer` - The writer for the merged step file.
+/// * `resolver` - The resolver for loading the referenced step files.
 pub fn merge_assembly_structure_to_step_with_resolver<W, R, Resolver>(
     assembly: &Assembly,
-    load_references: bool,
+    options: &MergeOptions,
     writer: W,
     resolver: Resolver,
-) -> Result<()>
+) -> Result<MergeOutput>
 where
     W: Write,
     R: Read,
     Resolver: FnMut(&str) -> Result<R>,
 {
-    let mut merger = StepMerger::new(writer, assembly, resolver)?;
-    merger.merge(load_references)?;
+    merge_with_progress(assembly, options, writer, resolver, &mut |_| {})
+}
 
-    Ok(())
+/// Merges the given assembly structure like `merge_assembly_structure_to_step_with_resolver` and
+/// reports the progress to the given callback.
+///
+/// # Arguments
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+/// * `writer` - The writer for the merged step file.
+/// * `resolver` - The resolver for loading the referenced step files.
+/// * `progress` - The callback receiving the progress of the merge.
+fn merge_with_progress<W, R, Resolver>(
+    assembly: &Assembly,
+    options: &MergeOptions,
+    writer: W,
+    mut resolver: Resolver,
+    progress: &mut dyn FnMut(MergeProgress),
+) -> Result<MergeOutput>
+where
+    W: Write,
+    R: Read,
+    Resolver: FnMut(&str) -> Result<R>,
+{
+    // the comments would be lost when reading the buffered entries for the second pass
+    if options.provenance_comments
+        && (options.compact_ids || options.topological_order || options.merge_duplicate_geometry)
+    {
+        return Err(Error::IncompatibleOptions(
+            "provenance comments cannot be combined with compacting, reordering or merging the \
+             entries"
+                .to_owned(),
+        ));
+    }
+
+    // fast path for trivial assemblies consisting of a single linked node
+    if let Some(link) = passthrough_link(assembly, options) {
+        let parser = resolver(link)
+            .and_then(STEPReader::new)
+            .and_then(|parser| check_external_references(link, &parser).map(|_| parser));
+        match parser {
+            Ok(parser) => {
+                info!("Trivial assembly, copy step file {}...", link);
+                let output = passthrough(parser, link, options, writer, progress)?;
+                info!("Trivial assembly, copy step file {}...DONE", link);
+                return Ok(output);
+            }
+            // the regular merge is used as fallback
+            Err(err) => error!("Error loading step file {}: {}", link, err),
+        }
+    }
+
+    if !options.compact_ids && !options.topological_order && !options.merge_duplicate_geometry {
+        let mut merger = StepMerger::new(writer, assembly, options, resolver, progress)?;
+        return merger.merge();
+    }
+
+    // merge into a buffer to optimize, reorder and renumber the entries in a second pass
+    let mut buffer = Vec::new();
+    let mut last_progress = MergeProgress::default();
+    let mut output = StepMerger::new(&mut buffer, assembly, options, resolver, &mut |p| {
+        last_progress = p;
+        progress(p);
+    })?
+    .merge()?;
+
+    let mut step_data = StepData::from_str(std::str::from_utf8(&buffer)?)?;
+    if options.merge_duplicate_geometry {
+        let num_removed = step_data.merge_duplicates(&["CARTESIAN_POINT", "DIRECTION"]);
+        info!("Merged {} duplicate geometry entries", num_removed);
+    }
+
+    if options.topological_order && !step_data.sort_topologically() {
+        warn!("The merged entries contain cyclic references and are only partially ordered");
+    }
+
+    if !options.compact_ids {
+        let bytes_written = write_step_data(writer, &step_data, options)?;
+        progress(MergeProgress {
+            bytes_written,
+            ..last_progress
+        });
+
+        return Ok(output);
+    }
+
+    let id_map = step_data.compact_ids();
+    let map_id = |id: &mut u64| *id = id_map.get(id).copied().unwrap_or(*id);
+    for node_ids in output
+        .manifest
+        .iter_mut()
+        .flatten()
+        .flat_map(|(_, ids)| ids)
+    {
+        map_id(&mut node_ids.product_definition_id);
+        map_id(&mut node_ids.shape_representation_id);
+    }
+
+    for (node_ids, _) in output.root_origins.iter_mut().flatten() {
+        map_id(&mut node_ids.product_definition_id);
+        map_id(&mut node_ids.shape_representation_id);
+    }
+
+    // the ranges stay contiguous as the order of the ids is kept
+    for ranges in output.file_id_ranges.iter_mut() {
+        ranges.retain(|_, range| match compact_id_range(range, &id_map) {
+            Some(compacted) => {
+                *range = compacted;
+                true
+            }
+            None => false,
+        });
+    }
+
+    let bytes_written = write_step_data(writer, &step_data, options)?;
+    progress(MergeProgress {
+        bytes_written,
+        ..last_progress
+    });
+
+    Ok(output)
+}
+
+/// Returns the link of the single node if the assembly is trivial, i.e., it consists of a single
+/// linked node without children and metadata. Such an assembly does not need to be merged and the
+/// referenced step file can be copied instead, unless the options require the merged entries to be
+/// modified or tracked, e.g., by a length unit prefix or a manifest.
+///
+/// # Arguments
+/// * `assembly` - The assembly structure to merged.
+/// * `options` - The options for the merging process.
+fn passthrough_link<'a>(assembly: &'a Assembly, options: &MergeOptions) -> Option<&'a str> {
+    let defaults = MergeOptions::default();
+    if !options.load_references
+        || options.create_manifest
+        || options.compact_ids
+        || options.topological_order
+        || options.merge_duplicate_geometry
+        || options.provenance_comments
+        || options.id_block_size.is_some()
+        || options.revision_metadata_key.is_some()
+        || options.length_unit_prefix != defaults.length_unit_prefix
+        || options.conformance == Conformance::Strict
+        || assembly.unit.is_some()
+    {
+        return None;
+    }
+
+    match assembly.nodes.as_slice() {
+        [node] if node.get_children().is_empty() && node.get_metadata().is_empty() => {
+            node.get_link()
+        }
+        _ => None,
+    }
+}
+
+/// Copies the entries of the given step file into the merged step file. The schema of the step
+/// file is kept, as the entries are not converted to the targeted application protocol.
+///
+/// # Arguments
+/// * `parser` - The parser of the step file to copy.
+/// * `link` - The link to the step file.
+/// * `options` - The options for the merging process.
+/// * `writer` - The writer for the merged step file.
+/// * `progress` - The callback receiving the progress of the merge.
+fn passthrough<W: Write, R: Read>(
+    parser: STEPReader<R>,
+    link: &str,
+    options: &MergeOptions,
+    writer: W,
+    progress: &mut dyn FnMut(MergeProgress),
+) -> Result<MergeOutput> {
+    let header = parser.header().clone();
+    log_schema_diagnostics(link, &header);
+    let mut step_writer = if header.schemas.is_empty() {
+        create_writer(writer, options)?
+    } else {
+        create_writer_with_schemas(writer, options, &header.schemas)?
+    };
+
+    // the presentation entries are only known after reading the whole step file
+    let mut entries = BufferedIterator::new(parser);
+    let dropped_ids = if options.drop_presentation {
+        entries.set_buffering_mode();
+        let mut filter = PresentationFilter::new();
+        for entry in entries.iter() {
+            filter.add_entry(&entry?);
+        }
+        entries.reset();
+        filter.into_dropped_ids()
+    } else {
+        HashSet::new()
+    };
+
+    for entry in entries.iter() {
+        let entry = entry?;
+        if !dropped_ids.contains(&entry.get_id()) {
+            step_writer.write_entry(&entry)?;
+        }
+    }
+
+    step_writer.finalize()?;
+
+    // the single node is represented by the copied step file
+    progress(MergeProgress {
+        nodes_created: 1,
+        num_nodes: 1,
+        references_loaded: 1,
+        num_references: 1,
+        bytes_written: step_writer.bytes_written(),
+        ..Default::default()
+    });
+
+    // the fields are filled by the same rules as for the regular merge
+    let file_headers = options
+        .create_manifest
+        .then(|| HashMap::from([(link.to_owned(), header)]));
+
+    Ok(MergeOutput {
+        manifest: None,
+        file_id_ranges: None,
+        root_origins: None,
+        file_headers,
+        failed_references: Vec::new(),
+        diagnostics: Vec::new(),
+        passthrough: true,
+    })
+}
+
+/// Returns the range of the renumbered ids of the entries within the given id range. The endpoints
+/// are determined by the first and last id of the range that still exist, as entries may have
+/// been removed, e.g., by merging duplicates. Returns None if no entry of the range is left.
+///
+/// # Arguments
+/// * `range` - The id range before renumbering.
+/// * `id_map` - The mapping from the old to the new ids of the existing entries.
+fn compact_id_range(range: &Range<u64>, id_map: &HashMap<u64, u64>) -> Option<Range<u64>> {
+    // the renumbering keeps the order of the ids, i.e., only the endpoints have to be looked up
+    let first = range.clone().find_map(|id| id_map.get(&id).copied())?;
+    let last = range
+        .clone()
+        .rev()
+        .find_map(|id| id_map.get(&id).copied())?;
+
+    Some(first..last + 1)
+}
+
+/// Writes the given, buffered step data of the merged step file and returns the number of
+/// written bytes.
+///
+/// # Arguments
+/// * `writer` - The writer for the merged step file.
+/// * `step_data` - The step data of the merged step file.
+/// * `options` - The options for the merging process.
+fn write_step_data<W: Write>(
+    writer: W,
+    step_data: &StepData,
+    options: &MergeOptions,
+) -> Result<u64> {
+    let mut step_writer = create_writer(writer, options)?;
+    for entry in step_data.get_entries() {
+        step_writer.write_entry(entry)?;
+    }
+    step_writer.finalize()?;
+
+    Ok(step_writer.bytes_written())
+}
+
+/// Creates the writer for the merged step file.
+///
+/// # Arguments
+/// * `writer` - The writer for the merged step file.
+/// * `options` - The options for the merging process.
+fn create_writer<W: Write>(writer: W, options: &MergeOptions) -> Result<StepWriter<W>> {
+    let protocol = vec![options.protocol.schema().to_owned()];
+    create_writer_with_schemas(writer, options, &protocol)
+}
+
+/// Creates the writer for the merged step file with the given schemas in the FILE_SCHEMA entity.
+///
+/// # Arguments
+/// * `writer` - The writer for the merged step file.
+/// * `options` - The options for the merging process.
+/// * `schemas` - The schemas of the merged step file.
+fn create_writer_with_schemas<W: Write>(
+    writer: W,
+    options: &MergeOptions,
+    schemas: &[String],
+) -> Result<StepWriter<W>> {
+    StepWriter::with_header_info(
+        writer,
+        &[],
+        &options.header_info,
+        "2;1",
+        "",
+        schemas,
+        WriterFormat {
+            spacing: options.section_spacing,
+            line_ending: options.line_ending,
+            max_line_width: options.max_line_width,
+        },
+    )
+}
+
+/// Returns the given value with a negative zero replaced by zero, as `-0` is rejected by some
+/// importers.
+///
+/// # Arguments
+/// * `value` - The value to normalize.
+#[inline]
+fn normalize_zero(value: f32) -> f32 {
+    if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// Scales the given length by the given power of ten in double precision. The decimal exponent of
+/// the shortest representation of the value is shifted, such that, e.g., `1.1` meters become
+/// exactly `1100` millimeters and no digits are lost for small prefixes like nano.
+///
+/// # Arguments
+/// * `value` - The length to scale.
+/// * `exponent` - The decimal exponent of the scale factor, e.g., 3 for meters to millimeters.
+fn scale_length(value: f32, exponent: i32) -> f64 {
+    let value = format!("{:e}", value);
+    let (mantissa, value_exponent) = value.split_once('e').unwrap_or((&value, "0"));
+    let value_exponent: i32 = value_exponent.parse().unwrap_or(0);
+    let scaled: f64 = format!("{}e{}", mantissa, value_exponent + exponent)
+        .parse()
+        .unwrap_or(0.0);
+
+    // normalize the negative zero
+    if scaled == 0.0 {
+        0.0
+    } else {
+        scaled
+    }
+}
+
+/// Formats the given value as STEP real, which always contains a decimal point, e.g., `2.` instead
+/// of the integer `2`.
+///
+/// # Arguments
+/// * `value` - The value to format.
+fn format_real(value: impl Display) -> String {
+    let value = value.to_string();
+    if value.contains('.') {
+        value
+    } else {
+        format!("{}.", value)
+    }
+}
+
+/// The relative tolerance for comparing scaling factors.
+const SCALE_TOLERANCE: f32 = 1e-5;
+
+/// The scaling of a transformation.
+enum Scaling {
+    /// The transformation is not scaled.
+    None,
+
+    /// The transformation is scaled uniformly by the given factor.
+    Uniform(f32),
+
+    /// The transformation is scaled non-uniformly.
+    NonUniform,
+}
+
+/// Classifies the given scaling factors along the axes.
+///
+/// # Arguments
+/// * `scale` - The scaling factors along the x-, y- and z-axis.
+fn classify_scale(scale: &[f32; 3]) -> Scaling {
+    let equals = |a: f32, b: f32| (a - b).abs() <= SCALE_TOLERANCE * a.abs().max(b.abs());
+
+    if scale.iter().all(|s| equals(*s, 1.0)) {
+        Scaling::None
+    } else if scale[0] > 0.0 && equals(scale[0], scale[1]) && equals(scale[0], scale[2]) {
+        Scaling::Uniform(scale[0])
+    } else {
+        Scaling::NonUniform
+    }
 }
 
 /// The internal step merge operator
@@ -106,20 +624,47 @@ where
     /// The assembly structure to be merged.
     assembly: &'a Assembly,
 
+    /// The options for the merging process.
+    options: &'a MergeOptions,
+
     /// The writer for the merged step file.
     writer: StepWriter<W>,
 
     /// The reference resolver to load external step files.
     resolver: Resolver,
 
-    /// The id of the STEP entry for the default coordinate system.
+    /// The id of the STEP entry for the default coordinate system, which is the origin of all
+    /// transformations and, if shared, the placement of the node shapes.
     default_coordinate_system: u64,
 
+    /// The id of the transformation shared by the relations with an identity transform or None
+    /// if it has not been created yet.
+    identity_transformation: Option<u64>,
+
     /// The id counter for the step entries.
     id_counter: u64,
 
-    /// The list of referenced mechanical design entries
-    mechanical_design_ids: Vec<u64>,
+    /// The id ranges occupied by the entries of the loaded step files.
+    file_id_ranges: HashMap<String, Range<u64>>,
+
+    /// The headers of the loaded step files.
+    file_headers: HashMap<String, StepHeader>,
+
+    /// The self-check for the generated placements or None if disabled.
+    placement_validator: Option<PlacementValidator>,
+
+    /// The smallest global length uncertainty in meters of each loaded step file.
+    file_uncertainties: Vec<(String, f64)>,
+
+    /// The current progress of the merge.
+    progress: MergeProgress,
+
+    /// The callback receiving the progress of the merge.
+    on_progress: &'a mut dyn FnMut(MergeProgress),
+
+    /// The referenced step files parsed in parallel, which have not been added yet.
+    #[cfg(feature = "parallel")]
+    parsed_references: HashMap<String, Result<parallel::ParsedStep>>,
 }
 
 impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W, R, Resolver> {
@@ -128,26 +673,74 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
     /// # Arguments
     /// * `writer` - The writer for the merged step file.
     /// * `assembly` - The assembly structure to be merged.
-    pub fn new(writer: W, assembly: &'a Assembly, resolver: Resolver) -> Result<Self> {
-        let protocol = vec!["AP203_CONFIGURATION_CONTROLLED_3D_DESIGN_OF_MECHANICAL_PARTS_AND_ASSEMBLIES_MIM_LF { 1 0 10303 403 1 1 4 }".to_owned()];
-        let step_writer = StepWriter::new(writer, "2;1", "", &protocol)?;
+    /// * `options` - The options for the merging process.
+    /// * `resolver` - The resolver for loading the referenced step files.
+    /// * `on_progress` - The callback receiving the progress of the merge.
+    pub fn new(
+        writer: W,
+        assembly: &'a Assembly,
+        options: &'a MergeOptions,
+        resolver: Resolver,
+        on_progress: &'a mut dyn FnMut(MergeProgress),
+    ) -> Result<Self> {
+        let step_writer = create_writer(writer, options)?;
+
+        let num_references = if options.load_references {
+            assembly
+                .nodes
+                .iter()
+                .filter_map(|node| node.get_link())
+                .collect::<HashSet<_>>()
+                .len()
+        } else {
+            0
+        };
+
+        let progress = MergeProgress {
+            num_nodes: assembly.nodes.len(),
+            num_relations: assembly
+                .nodes
+                .iter()
+                .map(|node| node.get_children().len())
+                .sum(),
+            num_references,
+            ..Default::default()
+        };
 
         Ok(StepMerger {
             assembly,
+            options,
             writer: step_writer,
             resolver,
             default_coordinate_system: 0,
+            identity_transformation: None,
             id_counter: 0,
-            mechanical_design_ids: Vec::new(),
+            file_id_ranges: HashMap::new(),
+            file_headers: HashMap::new(),
+            placement_validator: if options.validate_placements {
+                Some(PlacementValidator::new())
+            } else {
+                None
+            },
+            file_uncertainties: Vec::new(),
+            progress,
+            on_progress,
+            #[cfg(feature = "parallel")]
+            parsed_references: HashMap::new(),
         })
     }
 
+    /// Reports the current progress including the bytes written so far.
+    fn report_progress(&mut self) {
+        self.progress.bytes_written = self.writer.bytes_written();
+        (self.on_progress)(self.progress);
+    }
+
     /// Merges the assembly structure into a single monolithic step file.
-    ///
-    /// # Arguments
-    /// * `load_references` - Flag to indicate if external references should be loaded.
-    pub fn merge(&mut self, load_references: bool) -> Result<()> {
+    pub fn merge(&mut self) -> Result<MergeOutput> {
         info!("Merging assembly structure into step file...");
+        self.assembly.validate_transforms()?;
+
         self.create_app_context()?;
 
         // create default coordinate system
@@ -155,12 +748,14 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         let coord_id = self.add_entry("CARTESIAN_POINT('',(0.,0.,0.))")?;
         self.add_entry("DIRECTION('',(0.,0.,1.))")?;
         self.add_entry("DIRECTION('',(1.,0.,0.))")?;
-        self.default_coordinate_system = self.add_entry(&format!(
-            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
-            coord_id,
-            coord_id + 1,
-            coord_id + 2
-        ))?;
+        self.default_coordinate_system = self.add_entry(
+            &EntryBuilder::new("AXIS2_PLACEMENT_3D")
+                .string("")
+                .reference(coord_id)
+                .reference(coord_id + 1)
+                .reference(coord_id + 2)
+                .build(),
+        )?;
         trace!(
             "Create default coordinate system...DONE, ID={}",
             self.default_coordinate_system
@@ -181,6 +776,9 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
             );
 
             node_step_ids.push(node_ids);
+
+            self.progress.nodes_created += 1;
+            self.report_progress();
         }
         info!(
             "Create assembly nodes...DONE, {} nodes created",
@@ -190,9 +788,26 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         // create the parent-child relations between the assembly nodes
         info!("Create parent-child relations...");
         for (node, node_ids) in self.assembly.nodes.iter().zip(node_step_ids.iter()) {
-            for child in node.get_children() {
-                let child_ids = &node_step_ids[*child];
-                let child = &self.assembly.nodes[*child];
+            // in absolute mode, the relative transform is inverse(parent_world) * child_world
+            let inverse_parent_world = match self.options.transform_semantics {
+                TransformSemantics::Relative => None,
+                TransformSemantics::Absolute => Some(
+                    invert_matrix(&self.assembly.get_node_transform(node))
+                        .ok_or_else(|| Error::SingularTransform(node.get_label().to_owned()))?,
+                ),
+            };
+
+            for edge in node.get_children() {
+                let child_ids = &node_step_ids[edge.get_node()];
+                let child = &self.assembly.nodes[edge.get_node()];
+
+                let transform = match inverse_parent_world.as_ref() {
+                    Some(inverse_parent_world) => multiply_matrices(
+                        inverse_parent_world,
+                        &self.assembly.get_node_transform(child),
+                    ),
+                    None => self.assembly.get_node_transform(child),
+                };
 
                 trace!(
                     "Create parent-child relation between {} and {}...",
@@ -204,30 +819,62 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
                     child.get_label(),
                     *node_ids,
                     *child_ids,
-                    child.get_transform(),
+                    &transform,
+                    Some(edge),
                 )?;
+
+                self.progress.relations_created += 1;
+                self.report_progress();
             }
         }
 
         info!("Create parent-child relations...DONE");
 
         // load all referenced step files and add them to the current step data
-        if load_references {
+        let mut root_origins = Vec::new();
+        let mut failed_references = Vec::new();
+        if self.options.load_references {
+            #[cfg(feature = "parallel")]
+            self.parse_references();
+
             info!("Load and add referenced step files...");
+            // the maps are only used for lookups, the files are loaded in the order of the nodes
+            // to keep the merged output reproducible
             let mut reference_map: HashMap<String, Vec<NodeStepIds>> = HashMap::new();
+            let mut content_map = ContentMap::new();
             for node in self.assembly.nodes.iter() {
                 trace!("Check node {} for references...", node.get_label());
                 if let Some(link) = node.get_link() {
                     info!("Got link {}...", link);
                     if !reference_map.contains_key(link) {
                         debug!("Load and add step file {}...", link);
-                        match self.load_and_add_step(link) {
+                        let result = if self.options.deduplicate_references {
+                            self.load_and_add_step_deduplicated(link, &mut content_map)
+                        } else {
+                            self.load_and_add_step(link)
+                        };
+
+                        self.progress.references_loaded += 1;
+                        self.report_progress();
+
+                        match result {
                             Ok(root_nodes) => {
                                 debug!("Root nodes: {:?}...", root_nodes);
+                                root_origins
+                                    .extend(root_nodes.iter().map(|ids| (*ids, link.to_owned())));
                                 reference_map.insert(link.to_owned(), root_nodes);
                             }
+                            // unknown entities are fatal as the output would not conform
+                            Err(err @ Error::UnknownEntity(..)) => return Err(err),
+                            Err(err)
+                                if self.options.on_missing_reference
+                                    == OnMissingReference::Fail =>
+                            {
+                                return Err(err)
+                            }
                             Err(err) => {
                                 error!("Error loading step file {}: {}", link, err);
+                                failed_references.push((link.to_owned(), err));
                                 continue;
                             }
                         }
@@ -250,6 +897,7 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
                                 *node_ids,
                                 *child_ids,
                                 &identity_matrix(),
+                                None,
                             )?;
                         }
                     }
@@ -258,15 +906,50 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
             info!("Create parent-child relations for referenced step files...DONE");
         }
 
-        debug!("Write mechanical part entries...");
-        self.write_mechanical_part_entries()?;
-        debug!("Write mechanical part entries...DONE");
+        let diagnostics = self.uncertainty_diagnostics();
+        for diagnostic in diagnostics.iter() {
+            warn!("{}", diagnostic);
+        }
 
         info!("Finalize step file...");
         self.writer.finalize()?;
         info!("Finalize step file...DONE");
 
-        Ok(())
+        self.report_progress();
+
+        let manifest = if self.options.create_manifest {
+            let mut manifest: HashMap<String, Vec<NodeStepIds>> = HashMap::new();
+            for (node, node_ids) in self.assembly.nodes.iter().zip(node_step_ids.iter()) {
+                manifest
+                    .entry(node.get_label().to_owned())
+                    .or_default()
+                    .push(*node_ids);
+            }
+
+            Some(manifest)
+        } else {
+            None
+        };
+
+        let (file_id_ranges, root_origins, file_headers) = if self.options.create_manifest {
+            (
+                Some(std::mem::take(&mut self.file_id_ranges)),
+                Some(root_origins),
+                Some(std::mem::take(&mut self.file_headers)),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        Ok(MergeOutput {
+            manifest,
+            file_id_ranges,
+            root_origins,
+            file_headers,
+            failed_references,
+            diagnostics,
+            passthrough: false,
+        })
     }
 
     /// Returns a new unique id.
@@ -285,6 +968,10 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         let id = self.get_new_id();
         let entry = StepEntry::new(id, definition);
 
+        if let Some(validator) = self.placement_validator.as_mut() {
+            validator.check_entry(&entry)?;
+        }
+
         self.add_entry_full(&entry)?;
 
         Ok(id)
@@ -296,6 +983,16 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
     /// * `entry` - The entry to be added.
     #[inline]
     fn add_entry_full(&mut self, entry: &StepEntry) -> Result<()> {
+        if self.options.conformance == Conformance::Strict {
+            if let Some(keyword) = entry
+                .keywords()
+                .into_iter()
+                .find(|k| !self.options.protocol.is_known_entity(k))
+            {
+                return Err(Error::UnknownEntity(entry.get_id(), keyword.to_owned()));
+            }
+        }
+
         self.writer.write_entry(entry)?;
 
         Ok(())
@@ -303,14 +1000,12 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
 
     /// Creates the application context and protocol definition.
     fn create_app_context(&mut self) -> Result<()> {
-        let app_id = self.add_entry(
-            "APPLICATION_CONTEXT('Configuration controlled 3D designs of mechanical parts and assemblies')",
-        )?;
+        let protocol = &self.options.protocol;
+        let app_id = self.add_entry(protocol.application_context())?;
 
         assert_eq!(app_id, 1);
 
-        self.add_entry("APPLICATION_PROTOCOL_DEFINITION('international standard', 'configuration_control_3d_design_ed2_mim',2004, #1)",
-        )?;
+        self.add_entry(&protocol.application_protocol_definition(app_id))?;
 
         Ok(())
     }
@@ -321,6 +1016,14 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
     /// # Arguments
     /// * `link` - The link to the step file.
     fn load_and_add_step(&mut self, link: &str) -> Result<Vec<NodeStepIds>> {
+        #[cfg(feature = "parallel")]
+        if let Some(parsed) = self.parsed_references.remove(link) {
+            let parsed = parsed?;
+            log_schema_diagnostics(link, &parsed.header);
+            self.record_header(link, &parsed.header);
+            return self.load_and_add_step_entries(parsed.entries.into_iter().map(Ok), link);
+        }
+
         info!("Load step file {}...", link);
 
         trace!("Open step file {}...", link);
@@ -329,6 +1032,9 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         trace!("Create step reader...");
         let parser = STEPReader::new(r)?;
         debug!("STEP reader: {}", parser.get_name());
+        check_external_references(link, &parser)?;
+        log_schema_diagnostics(link, parser.header());
+        self.record_header(link, parser.header());
 
         debug!("Stream step entries...");
         let result = self.load_and_add_step_entries(parser.into_iter(), link)?;
@@ -338,52 +1044,225 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         Ok(result)
     }
 
-    /// Adds the given step file as step entries to the current step data.
-    /// Returns the STEP ids of the root nodes.
+    /// Reads the distinct referenced step files and parses them in parallel. The parsed files are
+    /// consumed while their entries are added in the order of the nodes, which keeps the merged
+    /// output identical to loading the files sequentially. Note that all referenced step files
+    /// are kept in memory until they are added.
+    #[cfg(feature = "parallel")]
+    fn parse_references(&mut self) {
+        info!("Parse referenced step files in parallel...");
+        let assembly = self.assembly;
+        let mut links = HashSet::new();
+        let mut contents = Vec::new();
+        for link in assembly.nodes.iter().filter_map(|node| node.get_link()) {
+            if links.insert(link) {
+                let content = (self.resolver)(link).and_then(|mut r| {
+                    let mut content = Vec::new();
+                    r.read_to_end(&mut content)
+                        .map_err(|e| Error::IO(Arc::new(e)))?;
+                    Ok(content)
+                });
+
+                contents.push((link.to_owned(), content));
+            }
+        }
+
+        self.parsed_references = parallel::parse_step_files(contents);
+        info!("Parse referenced step files in parallel...DONE");
+    }
+
+    /// Loads the given step file and adds the loaded step data to the current step data unless a
+    /// step file with identical content has already been added. Returns the STEP ids of the root
+    /// nodes, which are shared by all step files with identical content.
     ///
     /// # Arguments
-    /// * `entries` - The entries of another step file to be added.
-    /// * `filename` - The name of the step file.
-    fn load_and_add_step_entries<I>(
+    /// * `link` - The link to the step file.
+    /// * `content_map` - The already added step files by the hash of their content.
+    fn load_and_add_step_deduplicated(
+        &mut self,
+        link: &str,
+        content_map: &mut ContentMap,
+    ) -> Result<Vec<NodeStepIds>> {
+        #[cfg(feature = "parallel")]
+        if let Some(parsed) = self.parsed_references.remove(link) {
+            let parsed = parsed?;
+            let entries = parsed.entries.into_iter().map(Ok);
+            return self.add_step_deduplicated(
+                link,
+                parsed.content_hash,
+                None,
+                &parsed.header,
+                entries,
+                content_map,
+            );
+        }
+
+        let content = self.read_content(link)?;
+        let parser = STEPReader::new(Cursor::new(content.as_slice()))?;
+        debug!("STEP reader: {}", parser.get_name());
+        check_external_references(link, &parser)?;
+        let header = parser.header().clone();
+
+        self.add_step_deduplicated(
+            link,
+            content_hash(&content),
+            Some(&content),
+            &header,
+            parser.into_iter(),
+            content_map,
+        )
+    }
+
+    /// Reads the whole content of the given step file.
+    ///
+    /// # Arguments
+    /// * `link` - The link to the step file.
+    fn read_content(&mut self, link: &str) -> Result<Vec<u8>> {
+        trace!("Read step file {}...", link);
+        let mut content = Vec::new();
+        (self.resolver)(link)?
+            .read_to_end(&mut content)
+            .map_err(|e| Error::IO(Arc::new(e)))?;
+
+        Ok(content)
+    }
+
+    /// Adds the given entries of a step file to the current step data unless a step file with
+    /// identical content has already been added. Returns the STEP ids of the root nodes.
+    ///
+    /// # Arguments
+    /// * `link` - The link to the step file.
+    /// * `content_hash` - The hash of the content of the step file.
+    /// * `content` - The content of the step file. If None, the content is read again in case of
+    ///   a matching hash.
+    /// * `header` - The header of the step file.
+    /// * `entries` - The entries of the step file.
+    /// * `content_map` - The already added step files by the hash of their content.
+    fn add_step_deduplicated<I>(
         &mut self,
+        link: &str,
+        content_hash: u64,
+        content: Option<&[u8]>,
+        header: &StepHeader,
         entries: I,
-        filename: &str,
+        content_map: &mut ContentMap,
     ) -> Result<Vec<NodeStepIds>>
     where
         I: Iterator<Item = Result<StepEntry>>,
     {
-        let mut entries = BufferedIterator::new(entries);
+        self.record_header(link, header);
+
+        // a matching hash is only a candidate, the contents are compared to rule out collisions
+        if let Some(candidates) = content_map.get(&content_hash) {
+            let content = match content {
+                Some(content) => Cow::Borrowed(content),
+                None => Cow::Owned(self.read_content(link)?),
+            };
+
+            for (other_link, root_nodes) in candidates {
+                let identical = self
+                    .read_content(other_link)
+                    .is_ok_and(|other| other == *content);
+                if identical {
+                    info!(
+                        "Step file {} is identical to the already loaded file {}...",
+                        link, other_link
+                    );
+                    return Ok(root_nodes.clone());
+                }
+            }
+        }
 
-        // Find the id for the APPLICATION_CONTEXT entry. We use the buffered iterator to reuse the
-        // entries that have been read to find the APPLICATION_CONTEXT entry.
-        debug!(
+        log_schema_diagnostics(link, header);
+
+        let root_nodes = self.load_and_add_step_entries(entries, link)?;
+        content_map
+            .entry(content_hash)
+            .or_default()
+            .push((link.to_owned(), root_nodes.clone()));
+
+        Ok(root_nodes)
+    }
+
+    /// Records the header of the given step file for tracing the provenance of the merged data.
+    ///
+    /// # Arguments
+    /// * `link` - The link to the step file.
+    /// * `header` - The header of the step file.
+    fn record_header(&mut self, link: &str, header: &StepHeader) {
+        debug!(
+            "Step file {}: name='{}', time stamp='{}', originating system='{}'",
+            link, header.name, header.time_stamp, header.originating_system
+        );
+
+        self.file_headers.insert(link.to_owned(), header.clone());
+    }
+
+    /// Adds the given step file as step entries to the current step data.
+    /// Returns the STEP ids of the root nodes.
+    ///
+    /// # Arguments
+    /// * `entries` - The entries of another step file to be added.
+    /// * `filename` - The name of the step file.
+    fn load_and_add_step_entries<I>(
+        &mut self,
+        entries: I,
+        filename: &str,
+    ) -> Result<Vec<NodeStepIds>>
+    where
+        I: Iterator<Item = Result<StepEntry>>,
+    {
+        let mut entries = BufferedIterator::new(entries);
+
+        // Find the id for the APPLICATION_CONTEXT entry. We use the buffered iterator to reuse the
+        // entries that have been read to find the APPLICATION_CONTEXT entry. If the presentation
+        // is dropped, the whole step file is read to determine the presentation entries.
+        debug!(
             "Find APPLICATION_CONTEXT entry in step file {}...",
             filename
         );
         entries.set_buffering_mode();
         let mut app_context_id = 0;
+        let mut presentation_filter = self.options.drop_presentation.then(PresentationFilter::new);
         for (index, entry) in entries.iter().enumerate() {
             let entry = entry?;
-            if entry
-                .get_definition()
-                .trim_start()
-                .starts_with("APPLICATION_CONTEXT")
+            if let Some(filter) = presentation_filter.as_mut() {
+                filter.add_entry(&entry);
+            }
+
+            if app_context_id == 0
+                && entry
+                    .get_definition()
+                    .trim_start()
+                    .starts_with("APPLICATION_CONTEXT")
             {
                 app_context_id = entry.get_id();
                 debug!(
                     "APPLICATION_CONTEXT entry is {} at index={}",
                     app_context_id, index
                 );
-                break;
+
+                if presentation_filter.is_none() {
+                    break;
+                }
             }
         }
 
+        let dropped_ids = presentation_filter
+            .map(PresentationFilter::into_dropped_ids)
+            .unwrap_or_default();
+
         if app_context_id == 0 {
             return Err(Error::AppContextMissing(filename.to_string()));
         }
 
         entries.reset();
 
+        // reserve a separate block of ids for the entries of the step file
+        if let Some(block_size) = self.options.id_block_size.filter(|b| *b > 0) {
+            self.id_counter = self.id_counter.div_ceil(block_size) * block_size;
+        }
+
         // We define an update function to make sure that:
         // - all ids are shifted by the current id counter (offset)
         // - the APPLICATION_CONTEXT id is redirected to 1
@@ -397,9 +1276,18 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
             }
         };
 
+        if self.options.provenance_comments {
+            self.writer.write_comment(&format!(" from {} ", filename))?;
+        }
+
         // stream the entries into the output step file
+        let mut min_id = u64::MAX;
         let mut max_id = 0u64;
-        let mut find_root_nodes = FindRootNodes::new();
+        let mut find_root_nodes =
+            FindRootNodes::with_keywords(self.options.root_node_keywords.clone());
+        let check_uncertainty = self.options.max_uncertainty_ratio.is_some();
+        let mut length_units: HashMap<u64, f64> = HashMap::new();
+        let mut uncertainties: Vec<(f64, u64)> = Vec::new();
         for entry in entries.iter() {
             let entry = entry?;
             let definition = entry.get_definition().trim_start();
@@ -411,22 +1299,58 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
                 continue;
             }
 
+            if dropped_ids.contains(&entry.get_id()) {
+                continue;
+            }
+
+            // collect the length uncertainties together with the units they are given in
+            if check_uncertainty {
+                if let Some(factor) = si_length_unit_factor(&entry) {
+                    length_units.insert(entry.get_id(), factor);
+                } else if definition.starts_with("UNCERTAINTY_MEASURE_WITH_UNIT")
+                    && definition.contains("LENGTH_MEASURE")
+                {
+                    if let (Some(value), Some(unit)) =
+                        (entry.get_numbers().first(), entry.get_references().first())
+                    {
+                        uncertainties.push((*value, *unit));
+                    }
+                }
+            }
+
             // create new updated entry where the ids have been patched
-            let new_entry = entry.update_references(update_id);
+            let new_entry = entry.try_update_references(update_id)?;
+            min_id = min_id.min(new_entry.get_id());
             max_id = max_id.max(new_entry.get_id());
 
-            // catch special case of MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION
-            if definition.starts_with("MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION") {
-                get_ids_from_mechanical_part(&new_entry, &mut self.mechanical_design_ids);
-                continue;
-            } else {
-                find_root_nodes.add_entry(&new_entry);
-                self.add_entry_full(&new_entry)?;
-            }
+            // the presentation entries are kept per file, as they refer to the representation
+            // context of the file, e.g., MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION
+            find_root_nodes.add_entry(&new_entry);
+            self.add_entry_full(&new_entry)?;
+        }
+
+        if self.options.provenance_comments {
+            self.writer
+                .write_comment(&format!(" end of {} ", filename))?;
+        }
+
+        // uncertainties given in units other than SI length units are ignored
+        let uncertainty = uncertainties
+            .iter()
+            .filter_map(|(value, unit)| length_units.get(unit).map(|factor| value * factor))
+            .reduce(f64::min);
+        if let Some(uncertainty) = uncertainty {
+            debug!("Uncertainty of step file {} is {} m", filename, uncertainty);
+            self.file_uncertainties
+                .push((filename.to_owned(), uncertainty));
         }
 
         // update the id counter to the new max id
-        self.id_counter = max_id;
+        self.id_counter = self.id_counter.max(max_id);
+        if min_id <= max_id {
+            self.file_id_ranges
+                .insert(filename.to_owned(), min_id..max_id + 1);
+        }
 
         // extract the root nodes from the loaded step data
         let root_nodes = find_root_nodes.get_root_nodes();
@@ -437,42 +1361,77 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         Ok(root_nodes)
     }
 
-    /// Writes the final MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION entry.
-    fn write_mechanical_part_entries(&mut self) -> Result<()> {
-        // write related entries
-        let length = self.add_entry("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))")?;
-        let angle_units = self.add_entry("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))")?;
-        self.add_entry(&format!(
-            "PLANE_ANGLE_MEASURE_WITH_UNIT(PLANE_ANGLE_MEASURE(1.745329251994E-02),#{})",
-            angle_units
-        ))?;
-        let dim_exp = self.add_entry("DIMENSIONAL_EXPONENTS(0.,0.,0.,0.,0.,0.,0.)")?;
-        let angle = self.add_entry(&format!(
-            "(CONVERSION_BASED_UNIT('DEGREE',#{})NAMED_UNIT(#101)PLANE_ANGLE_UNIT())",
-            dim_exp
-        ))?;
-        let unit = self.add_entry("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())")?;
-        let uncertain = self.add_entry(&format!("UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(10.E-03),#{},'distance_accuracy_value','Confusion accuracy')", length))?;
-        let full = self.add_entry(&format!("(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))", uncertain, length, angle, unit))?;
-
-        // compile list of referenced ids
-        let mut list = String::new();
-        for id in self.mechanical_design_ids.iter() {
-            if list.is_empty() {
-                list.push_str(&format!("#{}", id));
-            } else {
-                list.push_str(&format!(",#{}", id));
+    /// Checks if the uncertainties of the loaded step files span more than the maximum ratio of
+    /// the options and returns the detected mismatches.
+    fn uncertainty_diagnostics(&self) -> Vec<MergeDiagnostic> {
+        let Some(max_ratio) = self.options.max_uncertainty_ratio else {
+            return Vec::new();
+        };
+
+        let min = self
+            .file_uncertainties
+            .iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let max = self
+            .file_uncertainties
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match (min, max) {
+            (Some(min), Some(max)) if max.1 > min.1 * max_ratio => {
+                vec![MergeDiagnostic::UncertaintyMismatch(
+                    min.clone(),
+                    max.clone(),
+                )]
             }
+            _ => Vec::new(),
         }
+    }
 
-        let entry = format!(
-            "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION('',({}),#{})",
-            list, full
-        );
+    /// Adds the entries of the emitted length unit and returns the id of the unit entry. The
+    /// unit declared by the assembly takes precedence over the length unit prefix of the options.
+    fn add_length_unit(&mut self) -> Result<u64> {
+        match self.assembly.unit {
+            None => self.add_entry(&length_unit(self.options.length_unit_prefix)),
+            Some(LengthUnit::Millimeter) => self.add_entry(&length_unit(SiPrefix::Milli)),
+            Some(LengthUnit::Meter) => self.add_entry(&length_unit(SiPrefix::None)),
+            Some(LengthUnit::Inch) => {
+                let millimeter_id = self.add_entry(&length_unit(SiPrefix::Milli))?;
+                let measure_id = self.add_entry(&format!(
+                    "LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#{})",
+                    millimeter_id
+                ))?;
+                let dim_exp_id = self.add_entry("DIMENSIONAL_EXPONENTS(1.,0.,0.,0.,0.,0.,0.)")?;
+                self.add_entry(&format!(
+                    "(CONVERSION_BASED_UNIT('INCH',#{})LENGTH_UNIT()NAMED_UNIT(#{}))",
+                    measure_id, dim_exp_id
+                ))
+            }
+        }
+    }
+
+    /// Returns the decimal exponent of the factor to scale the translations of the assembly to the
+    /// emitted length unit.
+    fn translation_scale_exponent(&self) -> i32 {
+        match self.assembly.unit {
+            // the translations are given in meters
+            None => -self.options.length_unit_prefix.exponent(),
+            // the translations are given in the emitted unit
+            Some(_) => 0,
+        }
+    }
 
-        self.add_entry(&entry)?;
+    /// Returns the length uncertainty of the representation contexts in the emitted length unit.
+    /// The uncertainty corresponds to `0.1E-12` millimeters.
+    fn length_uncertainty(&self) -> String {
+        let prefix = match self.assembly.unit {
+            None => self.options.length_unit_prefix,
+            Some(LengthUnit::Millimeter) => SiPrefix::Milli,
+            Some(LengthUnit::Meter) => SiPrefix::None,
+            Some(LengthUnit::Inch) => return format!("{:E}", 0.1E-12 / 25.4),
+        };
 
-        Ok(())
+        format!("0.1E{}", -15 - prefix.exponent())
     }
 
     /// Creates a new node in the step data. Returns a tuple consisting of the PRODUCT_DEFINITION
@@ -483,69 +1442,88 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
     fn create_node(&mut self, node: &Node) -> Result<NodeStepIds> {
         let label = node.get_label();
 
-        let start_id = self.add_entry("CARTESIAN_POINT('',(0.,0.,0.))")?;
-        self.add_entry("DIRECTION('',(0.,0.,1.))")?;
-        self.add_entry("DIRECTION('',(1.,0.,0.))")?;
-        self.add_entry(&format!(
-            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
-            start_id,
-            start_id + 1,
-            start_id + 2
-        ))?;
-
-        self.add_entry("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())")?;
-        self.add_entry("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))")?;
-        self.add_entry("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))")?;
+        // the shape representation is placed in the shared default coordinate system or in its
+        // own, equivalent coordinate system
+        let placement_id = if self.options.share_node_coordinate_system {
+            self.default_coordinate_system
+        } else {
+            let point_id = self.add_entry("CARTESIAN_POINT('',(0.,0.,0.))")?;
+            self.add_entry("DIRECTION('',(0.,0.,1.))")?;
+            self.add_entry("DIRECTION('',(1.,0.,0.))")?;
+            self.add_entry(&format!(
+                "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
+                point_id,
+                point_id + 1,
+                point_id + 2
+            ))?
+        };
 
-        self.add_entry("PRODUCT_CONTEXT('',#1,'mechanical')")?;
-        self.add_entry(&format!(
-            "PRODUCT('{}','{}','',(#{}))",
-            label,
-            label,
-            start_id + 7
-        ))?;
-        self.add_entry("PRODUCT_DEFINITION_CONTEXT('part_definition',#1,'')")?;
-        self.add_entry(&format!(
-            "PRODUCT_DEFINITION_FORMATION('','',#{})",
-            start_id + 8
-        ))?;
+        let solid_angle_id = self.add_entry(solid_angle_unit())?;
+        let length_id = self.add_length_unit()?;
+        let plane_angle_id = self.add_entry(plane_angle_unit())?;
+
+        let product_context_id = self.add_entry("PRODUCT_CONTEXT('',#1,'mechanical')")?;
+        let product_id = self.add_entry(
+            &EntryBuilder::new("PRODUCT")
+                .string(label)
+                .string(label)
+                .string("")
+                .references(&[product_context_id])
+                .build(),
+        )?;
+        let product_definition_context_id =
+            self.add_entry(&self.options.protocol.product_definition_context(1))?;
+        let revision = self
+            .options
+            .revision_metadata_key
+            .as_deref()
+            .and_then(|key| node.get_metadata_value(key))
+            .unwrap_or_default();
+        let formation_id = self.add_entry(
+            &EntryBuilder::new("PRODUCT_DEFINITION_FORMATION")
+                .string(revision)
+                .string("")
+                .reference(product_id)
+                .build(),
+        )?;
         let product_definition_id = self.add_entry(&format!(
             "PRODUCT_DEFINITION('','',#{},#{})",
-            start_id + 10,
-            start_id + 9
+            formation_id, product_definition_context_id
         ))?;
-        self.add_entry(&format!(
+        let product_definition_shape_id = self.add_entry(&format!(
             "PRODUCT_DEFINITION_SHAPE('',$,#{})",
-            start_id + 11
-        ))?;
-        self.add_entry(&format!(
-            "PRODUCT_RELATED_PRODUCT_CATEGORY('component','',(#{}))",
-            start_id + 8
-        ))?;
-        self.add_entry(&format!("UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(0.1E-12),#{},'distance accuracy value','edge curve and vertex point accuracy')", start_id + 5))?;
-        self.add_entry(&format!("(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))", start_id + 14, start_id + 5, start_id + 6, start_id + 4))?;
-        let shape_representation_id = self.add_entry(&format!(
-            "SHAPE_REPRESENTATION('{}',(#{}),#{})",
-            label,
-            start_id + 3,
-            start_id + 15
+            product_definition_id
         ))?;
+        self.add_entry(&self.options.protocol.product_category(product_id))?;
+        let uncertainty_id = self.add_entry(&format!("UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE({}),#{},'distance accuracy value','edge curve and vertex point accuracy')", self.length_uncertainty(), length_id))?;
+        let context_id = self.add_entry(&format!("(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))", uncertainty_id, length_id, plane_angle_id, solid_angle_id))?;
+        let shape_representation_id = self.add_entry(
+            &EntryBuilder::new("SHAPE_REPRESENTATION")
+                .string(label)
+                .references(&[placement_id])
+                .reference(context_id)
+                .build(),
+        )?;
         self.add_entry(&format!(
             "SHAPE_DEFINITION_REPRESENTATION(#{},#{})",
-            start_id + 12,
-            start_id + 16
+            product_definition_shape_id, shape_representation_id
         ))?;
 
         // add metadata
         for metadata in node.get_metadata() {
-            let prop_def_id = self.add_entry(&format!(
-                "PROPERTY_DEFINITION('{}','',#{})",
-                metadata.key, product_definition_id
-            ))?;
-            let desc_rep_item_id = self.add_entry(&format!(
-                "DESCRIPTIVE_REPRESENTATION_ITEM('{}','{}')",
-                metadata.key, metadata.value
-            ))?;
+            let prop_def_id = self.add_entry(
+                &EntryBuilder::new("PROPERTY_DEFINITION")
+                    .string(&metadata.key)
+                    .string("")
+                    .reference(product_definition_id)
+                    .build(),
+            )?;
+            let desc_rep_item_id = self.add_entry(
+                &EntryBuilder::new("DESCRIPTIVE_REPRESENTATION_ITEM")
+                    .string(&metadata.key)
+                    .string(&metadata.value)
+                    .build(),
+            )?;
 
             let rep_id =
                 self.add_entry(&format!("REPRESENTATION('',(#{}),$)", desc_rep_item_id))?;
@@ -570,6 +1548,9 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
     /// * `parent_ids` - The step ids of the parent node.
     /// * `child_ids` - The step ids of the child node.
     /// * `transform` - The transformation matrix from the parent to the child node.
+    /// * `edge` - The edge of the assembly defining the name of the representation relationship,
+    ///   which defaults to `Child > Parent`, and the instance name and the reference designator of
+    ///   the occurrence, which default to the child label.
     fn create_parent_child_relation(
         &mut self,
         parent_label: &str,
@@ -577,79 +1558,228 @@ impl<'a, W: Write, R: Read, Resolver: FnMut(&str) -> Result<R>> StepMerger<'a, W
         parent_ids: NodeStepIds,
         child_ids: NodeStepIds,
         transform: &[f32; 16],
+        edge: Option<&ChildEdge>,
     ) -> Result<()> {
-        // determine the position and translate it from meter to millimeter
-        let position = [
-            transform[12] * 1000.0,
-            transform[13] * 1000.0,
-            transform[14] * 1000.0,
-        ];
-
-        // extract the position, x-axis and z-axis vector
-        let x_axis = &transform[0..3];
-        let z_axis = &transform[8..11];
-
-        let start_id = self.add_entry(&format!(
-            "CARTESIAN_POINT('',({},{},{}))",
-            position[0], position[1], position[2],
-        ))?;
-        self.add_entry(&format!(
-            "DIRECTION('',({},{},{}))",
-            z_axis[0], z_axis[1], z_axis[2]
-        ))?;
-        self.add_entry(&format!(
-            "DIRECTION('',({},{},{}))",
-            x_axis[0], x_axis[1], x_axis[2]
-        ))?;
-        self.add_entry(&format!(
-            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
-            start_id,
-            start_id + 1,
-            start_id + 2
-        ))?;
-        self.add_entry(&format!(
-            "ITEM_DEFINED_TRANSFORMATION('','',#{},#{})",
-            self.default_coordinate_system,
-            start_id + 3
-        ))?;
-        self.add_entry(&format!(
-            "(REPRESENTATION_RELATIONSHIP('Child > Parent','{} > {}',#{}, #{})REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION(#{})SHAPE_REPRESENTATION_RELATIONSHIP())",
-            child_label,
-            parent_label,
-            child_ids.shape_representation_id,
-            parent_ids.shape_representation_id,
-            start_id + 4
+        let relationship_name = edge
+            .and_then(ChildEdge::get_relationship_name)
+            .unwrap_or("Child > Parent");
+        let reference_designator = edge
+            .and_then(ChildEdge::get_reference_designator)
+            .unwrap_or(child_label);
+        let instance_name = edge
+            .and_then(ChildEdge::get_instance_name)
+            .unwrap_or(child_label);
+
+        let transformation_id =
+            if self.options.share_identity_transforms && *transform == identity_matrix() {
+                self.identity_transformation()?
+            } else {
+                self.add_transformation(child_label, transform)?
+            };
+
+        // the builder only covers a single partial instance of the complex entity
+        let relationship = EntryBuilder::new("REPRESENTATION_RELATIONSHIP")
+            .string(relationship_name)
+            .string(&format!("{} > {}", child_label, parent_label))
+            .reference(child_ids.shape_representation_id)
+            .reference(parent_ids.shape_representation_id)
+            .build();
+        let relationship_id = self.add_entry(&format!(
+            "({}REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION(#{})SHAPE_REPRESENTATION_RELATIONSHIP())",
+            relationship, transformation_id
         ))?;
+        let occurrence_id = self.add_entry(
+            &EntryBuilder::new("NEXT_ASSEMBLY_USAGE_OCCURRENCE")
+                .string(instance_name)
+                .string("")
+                .string(child_label)
+                .reference(parent_ids.product_definition_id)
+                .reference(child_ids.product_definition_id)
+                .string(reference_designator)
+                .build(),
+        )?;
+
+        let product_definition_shape_id = self.add_entry(
+            &EntryBuilder::new("PRODUCT_DEFINITION_SHAPE")
+                .string(child_label)
+                .unset()
+                .reference(occurrence_id)
+                .build(),
+        )?;
+
         self.add_entry(&format!(
-            "NEXT_ASSEMBLY_USAGE_OCCURRENCE('{}','','{}',#{},#{},'{}')",
-            child_label,
-            child_label,
-            parent_ids.product_definition_id,
-            child_ids.product_definition_id,
-            child_label,
+            "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION(#{},#{})",
+            relationship_id, product_definition_shape_id
         ))?;
 
-        self.add_entry(&format!(
-            "PRODUCT_DEFINITION_SHAPE('{}',$,#{})",
-            child_label,
-            start_id + 6
+        Ok(())
+    }
+
+    /// Adds the entries for the given transform of a parent-child relation and returns the id
+    /// of the transformation.
+    ///
+    /// # Arguments
+    /// * `child_label` - The label of the child node.
+    /// * `transform` - The transformation matrix of the child node relative to its parent.
+    fn add_transformation(&mut self, child_label: &str, transform: &[f32; 16]) -> Result<u64> {
+        // determine the position and translate it to the emitted length unit
+        let exponent = self.translation_scale_exponent();
+        let coordinate = |value: f32| format_real(scale_length(normalize_zero(value), exponent));
+        let point_id = self.add_entry(&format!(
+            "CARTESIAN_POINT('',({},{},{}))",
+            coordinate(transform[12]),
+            coordinate(transform[13]),
+            coordinate(transform[14]),
         ))?;
 
+        // placements cannot carry a scaling, thus a uniformly scaled transform is written as
+        // cartesian transformation operator
+        let decomposed = decompose_matrix(transform);
+        match classify_scale(&decomposed.scale) {
+            Scaling::None => {
+                let x_axis = &transform[0..3];
+                let z_axis = &transform[8..11];
+                self.add_item_defined_transformation(point_id, x_axis, z_axis)
+            }
+            Scaling::Uniform(scale) => {
+                let [x_axis, y_axis, z_axis] = decomposed.axes;
+                let x_axis_id = self.add_direction(&x_axis)?;
+                let y_axis_id = self.add_direction(&y_axis)?;
+                let z_axis_id = self.add_direction(&z_axis)?;
+                self.add_entry(&format!(
+                    "CARTESIAN_TRANSFORMATION_OPERATOR_3D('','',$,#{},#{},#{},{},#{})",
+                    x_axis_id,
+                    y_axis_id,
+                    point_id,
+                    format_real(scale),
+                    z_axis_id
+                ))
+            }
+            Scaling::NonUniform => {
+                warn!(
+                    "Non-uniform scaling {:?} of {} is not supported and dropped",
+                    decomposed.scale, child_label
+                );
+                let [x_axis, _, z_axis] = decomposed.axes;
+                self.add_item_defined_transformation(point_id, &x_axis, &z_axis)
+            }
+        }
+    }
+
+    /// Returns the id of the transformation from the default coordinate system onto itself,
+    /// which is shared by all relations with an identity transform. Creates it on first use.
+    fn identity_transformation(&mut self) -> Result<u64> {
+        if let Some(transformation_id) = self.identity_transformation {
+            return Ok(transformation_id);
+        }
+
+        let transformation_id = self.add_entry(
+            &EntryBuilder::new("ITEM_DEFINED_TRANSFORMATION")
+                .string("")
+                .string("")
+                .reference(self.default_coordinate_system)
+                .reference(self.default_coordinate_system)
+                .build(),
+        )?;
+        self.identity_transformation = Some(transformation_id);
+
+        Ok(transformation_id)
+    }
+
+    /// Adds a direction entry and returns its id.
+    ///
+    /// # Arguments
+    /// * `direction` - The direction vector.
+    fn add_direction(&mut self, direction: &[f32]) -> Result<u64> {
         self.add_entry(&format!(
-            "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION(#{},#{})",
-            start_id + 5,
-            start_id + 7
-        ))?;
+            "DIRECTION('',({},{},{}))",
+            format_real(normalize_zero(direction[0])),
+            format_real(normalize_zero(direction[1])),
+            format_real(normalize_zero(direction[2]))
+        ))
+    }
+
+    /// Adds an ITEM_DEFINED_TRANSFORMATION from the default coordinate system to the placement
+    /// with the given origin and axes. Returns the id of the transformation.
+    ///
+    /// # Arguments
+    /// * `point_id` - The id of the origin of the placement.
+    /// * `x_axis` - The x-axis of the placement.
+    /// * `z_axis` - The z-axis of the placement.
+    fn add_item_defined_transformation(
+        &mut self,
+        point_id: u64,
+        x_axis: &[f32],
+        z_axis: &[f32],
+    ) -> Result<u64> {
+        let z_axis_id = self.add_direction(z_axis)?;
+        let x_axis_id = self.add_direction(x_axis)?;
+        let placement_id = self.add_entry(
+            &EntryBuilder::new("AXIS2_PLACEMENT_3D")
+                .string("")
+                .reference(point_id)
+                .reference(z_axis_id)
+                .reference(x_axis_id)
+                .build(),
+        )?;
+
+        self.add_entry(
+            &EntryBuilder::new("ITEM_DEFINED_TRANSFORMATION")
+                .string("")
+                .string("")
+                .reference(self.default_coordinate_system)
+                .reference(placement_id)
+                .build(),
+        )
+    }
+}
+
+/// The already added step files by the hash of their content. As different contents may share the
+/// same hash, each hash maps to the links of the step files together with their root nodes.
+type ContentMap = HashMap<u64, Vec<(String, Vec<NodeStepIds>)>>;
+
+/// Returns the hash of the given content of a step file for detecting identical files.
+///
+/// # Arguments
+/// * `content` - The content of the step file.
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
 
+/// Logs the problems with the schemas declared in the header of the given step file.
+///
+/// # Arguments
+/// * `link` - The link to the step file.
+/// * `header` - The header of the step file.
+fn log_schema_diagnostics(link: &str, header: &StepHeader) {
+    for diagnostic in header.schema_diagnostics() {
+        warn!("Step file {}: {}", link, diagnostic);
+    }
+}
+
+/// Returns an error if the given step file declares external references in its REFERENCE
+/// section, as the references to the externally defined entries would be dangling after merging.
+///
+/// # Arguments
+/// * `link` - The link to the step file.
+/// * `parser` - The parser of the step file.
+fn check_external_references<R: Read>(link: &str, parser: &STEPReader<R>) -> Result<()> {
+    if parser.get_external_references().is_empty() {
         Ok(())
+    } else {
+        Err(Error::ExternalReferences(link.to_owned()))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::{
-        io::{BufRead, BufReader, Cursor},
-        sync::Arc,
+    use std::{collections::HashSet, io::Cursor, str::FromStr, sync::Arc};
+
+    use crate::{
+        step::{ApplicationProtocol, HeaderInfo, SiPrefix, StepData},
+        AssemblyBuilder, MemoryVfs,
     };
 
     use super::*;
@@ -678,28 +1808,2136 @@ mod test {
         let mut output = Vec::new();
         merge_assembly_structure_to_step_with_resolver(
             &serde_json::from_slice::<Assembly>(assembly).unwrap(),
-            true,
+            &MergeOptions::default(),
             &mut output,
             resolver,
         )
         .unwrap();
 
-        let mut skip_lines = true;
-        for (l, (expected, actual)) in BufReader::new(merged.as_ref())
-            .lines()
-            .zip(BufReader::new(output.as_slice()).lines())
-            .enumerate()
-        {
-            let expected = expected.unwrap();
-            let actual = actual.unwrap();
-
-            if expected.starts_with("DATA;") {
-                skip_lines = false;
-            }
+        compare_data_sections(merged, &output);
+    }
+
+    #[test]
+    fn test_merge_assembly_structure_to_step_with_vfs() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let merged = include_bytes!("../../../test_data/cube-and-sphere.stp");
+
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &MergeOptions::default(),
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        compare_data_sections(merged, &output);
+    }
 
-            if !skip_lines {
-                assert_eq!(expected, actual, "Mismatch at line {}", l + 1);
+    #[test]
+    fn test_merge_progress() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        for compact_ids in [false, true] {
+            let options = MergeOptions {
+                compact_ids,
+                ..Default::default()
+            };
+
+            let mut reports = Vec::new();
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_progress(
+                &assembly,
+                &options,
+                &mut output,
+                &vfs,
+                |progress| reports.push(progress),
+            )
+            .unwrap();
+
+            // the counters never decrease
+            for (previous, next) in reports.iter().zip(reports.iter().skip(1)) {
+                assert!(previous.nodes_created <= next.nodes_created);
+                assert!(previous.relations_created <= next.relations_created);
+                assert!(previous.references_loaded <= next.references_loaded);
             }
+
+            let last = reports.last().unwrap();
+            assert_eq!(last.nodes_created, assembly.nodes.len());
+            assert_eq!(last.num_nodes, assembly.nodes.len());
+            assert_eq!(last.relations_created, last.num_relations);
+            assert_eq!(last.references_loaded, 2);
+            assert_eq!(last.num_references, 2);
+            assert_eq!(last.bytes_written, output.len() as u64);
+        }
+    }
+
+    /// Merges the given assembly structure with the given options without loading the referenced
+    /// step files. Returns the output of the merge together with the merged step file.
+    fn merge_structure_output(assembly: &Assembly, options: MergeOptions) -> (MergeOutput, String) {
+        let options = MergeOptions {
+            load_references: false,
+            ..options
+        };
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_resolver(
+            assembly,
+            &options,
+            &mut output,
+            |_: &str| -> Result<Cursor<&[u8]>> { unreachable!() },
+        )
+        .unwrap();
+
+        (merge_output, String::from_utf8(output).unwrap())
+    }
+
+    /// Merges the given assembly structure with the given options without loading the referenced
+    /// step files and parses the merged step data.
+    fn merge_structure(assembly: &Assembly, options: MergeOptions) -> StepData {
+        let (_, output) = merge_structure_output(assembly, options);
+        StepData::from_str(&output).unwrap()
+    }
+
+    /// Compares the entries of the given expected and actual STEP output in their canonical form,
+    /// i.e., the insignificant whitespace of the definitions is ignored.
+    fn compare_data_sections(merged: &[u8], output: &[u8]) {
+        let expected = StepData::from_str(std::str::from_utf8(merged).unwrap()).unwrap();
+        let actual = StepData::from_str(std::str::from_utf8(output).unwrap()).unwrap();
+        assert_eq!(
+            expected.get_entries().len(),
+            actual.get_entries().len(),
+            "Mismatch of the number of entries"
+        );
+
+        for (expected, actual) in expected.get_entries().iter().zip(actual.get_entries()) {
+            assert_eq!(expected, actual, "Mismatch at entry #{}", expected.get_id());
         }
     }
+
+    #[test]
+    fn test_merge_assembly_file_to_step() {
+        let output_path = std::env::temp_dir().join(format!(
+            "step-merger-test-{}-cube-and-sphere.stp",
+            std::process::id()
+        ));
+
+        // the links are resolved relative to the assembly file and not to the working directory
+        let result = merge_assembly_file_to_step(
+            "../test_data/cube-and-sphere.json",
+            &output_path,
+            &MergeOptions::default(),
+        );
+        let output = std::fs::read_to_string(&output_path);
+        std::fs::remove_file(&output_path).unwrap();
+        result.unwrap();
+
+        let step_data = StepData::from_str(&output.unwrap()).unwrap();
+        let num_products = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "PRODUCT_DEFINITION")
+            .count();
+        assert_eq!(num_products, 5);
+    }
+
+    #[test]
+    fn test_merge_assembly_structure_to_step() {
+        let root_link = "../test_data/cube-and-sphere.json";
+        let assembly = Assembly::from_file(root_link).unwrap();
+        let merged = include_bytes!("../../../test_data/cube-and-sphere.stp");
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step(root_link, &assembly, true, &mut output).unwrap();
+        compare_data_sections(merged, &output);
+
+        let mut output = Vec::new();
+        let options = MergeOptions::default();
+        merge_assembly_structure_to_step_with_options(root_link, &assembly, &options, &mut output)
+            .unwrap();
+        compare_data_sections(merged, &output);
+    }
+
+    #[test]
+    fn test_merge_conformance() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        let mut node = Node::new("Part");
+        node.set_link("part.stp");
+        let assembly = Assembly {
+            nodes: vec![root, node],
+            unit: None,
+        };
+
+        let part = b"ISO-10303-21; HEADER; ENDSEC; DATA;
+            #1=APPLICATION_CONTEXT('');
+            #2=CARTESIAN_PIONT('',(0.,0.,0.));
+            ENDSEC; END-ISO-10303-21;";
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("part.stp", part);
+
+        let merge = |conformance: Conformance| {
+            let options = MergeOptions {
+                conformance,
+                ..Default::default()
+            };
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, Vec::new(), &vfs)
+        };
+
+        // the misspelled entity is only flagged under strict conformance
+        assert!(merge(Conformance::Lenient).is_ok());
+        assert!(matches!(
+            merge(Conformance::Strict),
+            Err(Error::UnknownEntity(_, keyword)) if keyword == "CARTESIAN_PIONT"
+        ));
+
+        // the regular entities are known
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../../test_data/cube.stp"));
+        vfs.insert(
+            "sphere.stp",
+            include_bytes!("../../../test_data/sphere.stp"),
+        );
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let options = MergeOptions {
+            conformance: Conformance::Strict,
+            ..Default::default()
+        };
+        assert!(merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            Vec::new(),
+            &vfs,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_merge_schema() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        let merge = |protocol: ApplicationProtocol| {
+            let options = MergeOptions {
+                protocol,
+                ..Default::default()
+            };
+            merge_structure(&assembly, options)
+        };
+
+        let step_data = merge(ApplicationProtocol::AP242);
+        assert_eq!(
+            step_data.get_header().schemas,
+            vec!["AP242_MANAGED_MODEL_BASED_3D_ENGINEERING_MIM_LF { 1 0 10303 442 1 1 4 }"]
+        );
+        assert!(step_data.get_entries()[1]
+            .get_definition()
+            .contains("'ap242_managed_model_based_3d_engineering'"));
+
+        let schema = "CONFIG_CONTROL_DESIGN { 1 0 10303 203 1 1 }";
+        let step_data = merge(ApplicationProtocol::Custom(schema.to_owned()));
+        assert_eq!(step_data.get_header().schemas, vec![schema]);
+        assert!(step_data.get_entries()[1]
+            .get_definition()
+            .contains("'configuration_control_3d_design_ed2_mim'"));
+    }
+
+    #[test]
+    fn test_merge_ap214() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let options = MergeOptions {
+            protocol: ApplicationProtocol::AP214,
+            ..Default::default()
+        };
+        let (_, output) = merge_structure_output(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            options,
+        );
+        assert!(output.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));"));
+
+        let step_data = StepData::from_str(&output).unwrap();
+        let entries = step_data.get_entries();
+        assert_eq!(
+            entries[1].get_definition(),
+            "APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#1)"
+        );
+
+        let find = |keyword: &str| {
+            entries
+                .iter()
+                .filter(|entry| entry.keyword() == keyword)
+                .collect::<Vec<_>>()
+        };
+
+        let contexts = find("PRODUCT_DEFINITION_CONTEXT");
+        assert!(!contexts.is_empty());
+        assert!(contexts.iter().all(|entry| entry.get_definition()
+            == "PRODUCT_DEFINITION_CONTEXT('part definition',#1,'design')"));
+
+        // the presentation is only taken from the referenced step files
+        let presentation = find("MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION");
+        assert!(presentation.is_empty());
+    }
+
+    #[test]
+    fn test_merge_manifest() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../../test_data/cube.stp"));
+        vfs.insert(
+            "sphere.stp",
+            include_bytes!("../../../test_data/sphere.stp"),
+        );
+
+        let options = MergeOptions {
+            create_manifest: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output =
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+        let manifest = merge_output.manifest.unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        let keyword_of = |id: u64| {
+            step_data
+                .get_entries()
+                .iter()
+                .find(|entry| entry.get_id() == id)
+                .map(|entry| entry.keyword())
+        };
+
+        assert_eq!(manifest.len(), assembly.nodes.len());
+
+        let mut ids = HashSet::new();
+        for node in assembly.nodes.iter() {
+            let [node_ids] = manifest[node.get_label()].as_slice() else {
+                panic!("Expected a single entry for {}", node.get_label());
+            };
+
+            assert_eq!(
+                keyword_of(node_ids.product_definition_id),
+                Some("PRODUCT_DEFINITION")
+            );
+            assert_eq!(
+                keyword_of(node_ids.shape_representation_id),
+                Some("SHAPE_REPRESENTATION")
+            );
+
+            assert!(ids.insert(node_ids.product_definition_id));
+            assert!(ids.insert(node_ids.shape_representation_id));
+        }
+    }
+
+    #[test]
+    fn test_merge_manifest_repeated_label() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(2);
+        let assembly = Assembly {
+            nodes: vec![root, Node::new("Cube"), Node::new("Cube")],
+            unit: None,
+        };
+
+        let options = MergeOptions {
+            create_manifest: true,
+            ..Default::default()
+        };
+        let (merge_output, _) = merge_structure_output(&assembly, options);
+        let manifest = merge_output.manifest.unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest["Root"].len(), 1);
+
+        // both nodes are listed in the order of the assembly
+        let cubes = &manifest["Cube"];
+        assert_eq!(cubes.len(), 2);
+        assert!(cubes[0].product_definition_id < cubes[1].product_definition_id);
+        assert_ne!(
+            cubes[0].shape_representation_id,
+            cubes[1].shape_representation_id
+        );
+    }
+
+    #[test]
+    fn test_validate_placements() {
+        let assembly = Assembly {
+            nodes: Vec::new(),
+            unit: None,
+        };
+        let options = MergeOptions {
+            validate_placements: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let mut on_progress = |_| {};
+        let mut merger = StepMerger::new(
+            &mut output,
+            &assembly,
+            &options,
+            |_: &str| -> Result<Cursor<&[u8]>> { unreachable!() },
+            &mut on_progress,
+        )
+        .unwrap();
+
+        // perturb the order of the point and the directions
+        let start_id = merger.add_entry("DIRECTION('',(0.,0.,1.))").unwrap();
+        merger.add_entry("CARTESIAN_POINT('',(0.,0.,0.))").unwrap();
+        merger.add_entry("DIRECTION('',(1.,0.,0.))").unwrap();
+        let result = merger.add_entry(&format!(
+            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
+            start_id,
+            start_id + 1,
+            start_id + 2
+        ));
+
+        assert!(matches!(result, Err(Error::InvalidPlacement(4))));
+    }
+
+    #[test]
+    fn test_scale_length() {
+        assert_eq!(scale_length(1.1, 3), 1100.0);
+        assert_eq!(scale_length(-4.0, 6), -4e6);
+        assert_eq!(scale_length(1.2345678, 9), 1234567800.0);
+        assert_eq!(scale_length(0.1, 9), 1e8);
+        assert_eq!(scale_length(2500.0, -3), 2.5);
+        assert!(scale_length(-0.0, 3).is_sign_positive());
+    }
+
+    #[test]
+    fn test_merge_micro_length_unit() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let options = MergeOptions {
+            length_unit_prefix: SiPrefix::Micro,
+            ..Default::default()
+        };
+
+        let step_data = merge_structure(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            options,
+        );
+        let definitions: Vec<&str> = step_data
+            .get_entries()
+            .iter()
+            .map(|entry| entry.get_definition())
+            .collect();
+
+        let length_units: Vec<&&str> = definitions
+            .iter()
+            .filter(|d| d.contains("LENGTH_UNIT()"))
+            .collect();
+        assert!(!length_units.is_empty());
+        assert!(length_units
+            .iter()
+            .all(|d| **d == "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MICRO.,.METRE.))"));
+
+        // the sphere is translated by -4 meters
+        assert!(definitions.contains(&"CARTESIAN_POINT('',(-4000000.,0.,0.))"));
+
+        // the uncertainty of 0.1E-12 millimeters is given in micrometers
+        assert!(definitions
+            .iter()
+            .filter(|d| d.starts_with("UNCERTAINTY_MEASURE_WITH_UNIT("))
+            .all(|d| d.starts_with("UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(0.1E-9),")));
+
+        // angle units are not affected by the length prefix
+        assert!(definitions.contains(&"(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))"));
+        assert!(definitions.contains(&"(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())"));
+    }
+
+    #[test]
+    fn test_merge_assembly_unit() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let mut assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        assert_eq!(assembly.unit, None);
+
+        // the unit of the assembly takes precedence over the length unit prefix
+        let mut value: serde_json::Value = serde_json::to_value(&assembly).unwrap();
+        value["unit"] = serde_json::Value::from("m");
+        assembly = serde_json::from_value(value).unwrap();
+        assert_eq!(assembly.unit, Some(LengthUnit::Meter));
+
+        let options = MergeOptions {
+            length_unit_prefix: SiPrefix::Micro,
+            ..Default::default()
+        };
+
+        let step_data = merge_structure(&assembly, options);
+        let definitions: Vec<&str> = step_data
+            .get_entries()
+            .iter()
+            .map(|entry| entry.get_definition())
+            .collect();
+
+        let length_units: Vec<&&str> = definitions
+            .iter()
+            .filter(|d| d.contains("LENGTH_UNIT()"))
+            .collect();
+        assert!(!length_units.is_empty());
+        assert!(length_units
+            .iter()
+            .all(|d| **d == "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT($,.METRE.))"));
+
+        // the sphere is translated by -4 meters without any scaling
+        assert!(definitions.contains(&"CARTESIAN_POINT('',(-4.,0.,0.))"));
+    }
+
+    #[test]
+    fn test_merge_assembly_unit_inch() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let mut assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        assembly.unit = Some(LengthUnit::Inch);
+
+        let step_data = merge_structure(&assembly, MergeOptions::default());
+        let entries = step_data.get_entries();
+
+        // every inch unit is defined as 25.4 millimeters
+        let inch_units: Vec<&StepEntry> = entries
+            .iter()
+            .filter(|e| {
+                e.get_definition()
+                    .starts_with("(CONVERSION_BASED_UNIT('INCH',")
+            })
+            .collect();
+        assert!(!inch_units.is_empty());
+        for unit in inch_units {
+            let measure = step_data.get_entry_by_id(unit.get_references()[0]).unwrap();
+            assert_eq!(measure.keyword(), "LENGTH_MEASURE_WITH_UNIT");
+            assert_eq!(measure.get_numbers(), vec![25.4]);
+
+            let millimeter = step_data
+                .get_entry_by_id(measure.get_references()[0])
+                .unwrap();
+            assert_eq!(si_length_unit_factor(millimeter), Some(1e-3));
+        }
+
+        // the sphere is translated by -4 inches without any scaling
+        assert!(entries
+            .iter()
+            .any(|e| e.get_definition() == "CARTESIAN_POINT('',(-4.,0.,0.))"));
+    }
+
+    #[test]
+    fn test_merge_node_unit() {
+        let translation = |x: f32| {
+            let mut m = identity_matrix();
+            m[12] = x;
+            m
+        };
+
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(2);
+        root.add_child(3);
+        let mut inch = Node::new("Inch");
+        inch.set_transform(translation(2.0));
+        inch.set_unit(LengthUnit::Inch);
+        let mut millimeter = Node::new("Millimeter");
+        millimeter.set_transform(translation(254.0));
+        millimeter.set_unit(LengthUnit::Millimeter);
+        let mut meter = Node::new("Meter");
+        meter.set_transform(translation(1.5));
+
+        let mut assembly = Assembly {
+            nodes: vec![root, inch, millimeter, meter],
+            unit: None,
+        };
+
+        let merge = |assembly: &Assembly| {
+            let step_data = merge_structure(assembly, MergeOptions::default());
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == "CARTESIAN_POINT")
+                .filter_map(|e| e.get_numbers().first().copied())
+                .filter(|x| *x != 0.0)
+                .collect::<Vec<f64>>()
+        };
+
+        // the translations of the nodes are converted to the emitted millimeters
+        assert_eq!(merge(&assembly), vec![50.8, 254.0, 1500.0]);
+
+        // the node units override the unit of the assembly
+        assembly.unit = Some(LengthUnit::Inch);
+        assert_eq!(merge(&assembly), vec![2.0, 10.0, 1.5]);
+    }
+
+    #[test]
+    fn test_merge_absolute_transforms() {
+        let translation = |x: f32, y: f32, z: f32| {
+            let mut m = identity_matrix();
+            m[12] = x;
+            m[13] = y;
+            m[14] = z;
+            m
+        };
+
+        // the same assembly once with relative and once with absolute transforms
+        let create_assembly = |root: [f32; 16], child: [f32; 16], grand_child: [f32; 16]| {
+            let mut nodes = vec![
+                Node::new("Root"),
+                Node::new("Child"),
+                Node::new("Grand Child"),
+            ];
+            nodes[0].set_transform(root);
+            nodes[0].add_child(1);
+            nodes[1].set_transform(child);
+            nodes[1].add_child(2);
+            nodes[2].set_transform(grand_child);
+
+            Assembly { nodes, unit: None }
+        };
+
+        let relative = create_assembly(
+            translation(1.0, 0.0, 0.0),
+            translation(0.0, 2.0, 0.0),
+            translation(0.0, 0.0, -3.0),
+        );
+        let absolute = create_assembly(
+            translation(1.0, 0.0, 0.0),
+            translation(1.0, 2.0, 0.0),
+            translation(1.0, 2.0, -3.0),
+        );
+
+        let merge = |assembly: &Assembly, transform_semantics: TransformSemantics| {
+            let options = MergeOptions {
+                transform_semantics,
+                ..Default::default()
+            };
+
+            merge_structure(assembly, options)
+        };
+
+        let relative = merge(&relative, TransformSemantics::Relative);
+        let absolute = merge(&absolute, TransformSemantics::Absolute);
+
+        assert_eq!(relative.get_entries(), absolute.get_entries());
+        assert!(absolute
+            .get_entries()
+            .iter()
+            .any(|entry| entry.get_definition() == "CARTESIAN_POINT('',(0.,0.,-3000.))"));
+    }
+
+    #[test]
+    fn test_merge_negative_zero() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+
+        let mut child = Node::new("Child");
+        child.set_transform([
+            1.0, -0.0, 0.0, 0.0, -0.0, 1.0, 0.0, 0.0, 0.0, -0.0, 1.0, 0.0, -0.0, 2.0, -0.0, 1.0,
+        ]);
+        let assembly = Assembly {
+            nodes: vec![root, child],
+            unit: None,
+        };
+
+        let (_, output) = merge_structure_output(&assembly, MergeOptions::default());
+        assert!(!output.contains("-0.,") && !output.contains("-0.)"));
+        assert!(output.contains("CARTESIAN_POINT('',(0.,2000.,0.))"));
+        assert!(output.contains("DIRECTION('',(1.,0.,0.))"));
+    }
+
+    #[test]
+    fn test_merge_singular_transform() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.set_transform([
+            1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ]);
+        let assembly = Assembly {
+            nodes: vec![root, Node::new("Child")],
+            unit: None,
+        };
+
+        let options = MergeOptions {
+            load_references: false,
+            transform_semantics: TransformSemantics::Absolute,
+            ..Default::default()
+        };
+
+        let result = merge_assembly_structure_to_step_with_resolver(
+            &assembly,
+            &options,
+            &mut Vec::new(),
+            |_: &str| -> Result<Cursor<&[u8]>> { unreachable!() },
+        );
+
+        assert!(matches!(result, Err(Error::SingularTransform(label)) if label == "Root"));
+    }
+
+    #[test]
+    fn test_merge_scaled_transforms() {
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(2);
+
+        // rotated by 90 degrees around the z-axis and uniformly scaled by 2
+        let mut uniform = Node::new("Uniform");
+        uniform.set_transform([
+            0.0, 2.0, 0.0, 0.0, -2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        ]);
+
+        let mut non_uniform = Node::new("Non-Uniform");
+        non_uniform.set_transform([
+            3.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ]);
+
+        let assembly = Assembly {
+            nodes: vec![root, uniform, non_uniform],
+            unit: None,
+        };
+
+        let step_data = merge_structure(&assembly, MergeOptions::default());
+        let definition = |id: u64| step_data.get_entry_by_id(id).unwrap().get_definition();
+        let transformations: Vec<&StepEntry> = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| {
+                e.keywords()
+                    .contains(&"REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION")
+            })
+            .map(|e| {
+                let transformation_id = *e.get_references().last().unwrap();
+                step_data.get_entry_by_id(transformation_id).unwrap()
+            })
+            .collect();
+        assert_eq!(transformations.len(), 2);
+
+        // the uniform scaling is kept by a cartesian transformation operator
+        let operator = transformations[0];
+        assert_eq!(operator.keyword(), "CARTESIAN_TRANSFORMATION_OPERATOR_3D");
+        assert!(operator.get_definition().contains(",2.,"));
+        let references = operator.get_references();
+        assert_eq!(definition(references[0]), "DIRECTION('',(0.,1.,0.))");
+        assert_eq!(definition(references[1]), "DIRECTION('',(-1.,0.,0.))");
+        assert_eq!(
+            definition(references[2]),
+            "CARTESIAN_POINT('',(1000.,0.,0.))"
+        );
+        assert_eq!(definition(references[3]), "DIRECTION('',(0.,0.,1.))");
+
+        // the non-uniform scaling is dropped and the axes are normalized
+        let transformation = transformations[1];
+        assert_eq!(transformation.keyword(), "ITEM_DEFINED_TRANSFORMATION");
+        let placement = step_data
+            .get_entry_by_id(transformation.get_references()[1])
+            .unwrap();
+        let references = placement.get_references();
+        assert_eq!(definition(references[1]), "DIRECTION('',(0.,0.,1.))");
+        assert_eq!(definition(references[2]), "DIRECTION('',(1.,0.,0.))");
+    }
+
+    #[test]
+    fn test_merge_revision_metadata() {
+        let mut nodes = vec![Node::new("Root"), Node::new("Part")];
+        nodes[0].add_child(1);
+        nodes[1].add_metadata("revision", "B.2");
+        let assembly = Assembly { nodes, unit: None };
+
+        let merge = |revision_metadata_key: Option<String>| {
+            let options = MergeOptions {
+                revision_metadata_key,
+                ..Default::default()
+            };
+
+            let step_data = merge_structure(&assembly, options);
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == "PRODUCT_DEFINITION_FORMATION")
+                .map(|e| e.get_strings()[0].clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(merge(None), vec!["", ""]);
+        assert_eq!(merge(Some("revision".to_owned())), vec!["", "B.2"]);
+    }
+
+    #[test]
+    fn test_merge_escaped_strings() {
+        let mut nodes = vec![Node::new("Root"), Node::new("O'Brien, Inc.")];
+        nodes[0].add_child(1);
+        nodes[1].add_metadata("supplier's name", "O'Brien, Inc.");
+        let assembly = Assembly { nodes, unit: None };
+
+        let options = MergeOptions {
+            load_references: false,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &options,
+            &mut output,
+            &MemoryVfs::default(),
+        )
+        .unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        let strings = |keyword: &str| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == keyword)
+                .map(|e| e.get_strings())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            strings("DESCRIPTIVE_REPRESENTATION_ITEM"),
+            vec![vec!["supplier's name", "O'Brien, Inc."]]
+        );
+        assert_eq!(
+            strings("PRODUCT")[1],
+            vec!["O'Brien, Inc.", "O'Brien, Inc.", ""]
+        );
+        assert_eq!(
+            strings("NEXT_ASSEMBLY_USAGE_OCCURRENCE"),
+            vec![vec!["O'Brien, Inc.", "", "O'Brien, Inc.", "O'Brien, Inc."]]
+        );
+    }
+
+    #[test]
+    fn test_merge_escaped_labels() {
+        let label = r"Vis d'assemblage \ M8";
+        let mut nodes = vec![Node::new("Root"), Node::new(label)];
+        nodes[0].add_child(1);
+        let assembly = Assembly { nodes, unit: None };
+
+        let options = MergeOptions {
+            load_references: false,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &options,
+            &mut output,
+            &MemoryVfs::default(),
+        )
+        .unwrap();
+
+        let output = std::str::from_utf8(&output).unwrap();
+        assert!(output.contains(r"PRODUCT('Vis d''assemblage \\ M8','Vis d''assemblage \\ M8'"));
+
+        let step_data = StepData::from_str(output).unwrap();
+        let first_strings = |keyword: &str| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == keyword)
+                .map(|e| e.decoded_strings().unwrap()[0].clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(first_strings("PRODUCT"), vec!["Root", label]);
+        assert_eq!(first_strings("SHAPE_REPRESENTATION"), vec!["Root", label]);
+        assert_eq!(first_strings("NEXT_ASSEMBLY_USAGE_OCCURRENCE"), vec![label]);
+    }
+
+    #[test]
+    fn test_merge_topological_order() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let options = MergeOptions {
+            topological_order: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+
+        let mut written = HashSet::new();
+        for entry in step_data.get_entries() {
+            for reference in entry
+                .get_references()
+                .into_iter()
+                .filter(|r| step_data.get_entry_by_id(*r).is_some())
+            {
+                assert!(
+                    written.contains(&reference),
+                    "#{} before #{}",
+                    entry.get_id(),
+                    reference
+                );
+            }
+
+            written.insert(entry.get_id());
+        }
+    }
+
+    #[test]
+    fn test_merge_duplicate_geometry() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let merge = |merge_duplicate_geometry: bool| {
+            let options = MergeOptions {
+                merge_duplicate_geometry,
+                ..Default::default()
+            };
+
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+            StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap()
+        };
+        let count_z_directions = |step_data: &StepData| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.get_definition() == "DIRECTION('',(0.,0.,1.))")
+                .count()
+        };
+
+        let step_data = merge(false);
+        assert!(count_z_directions(&step_data) > 1);
+
+        let optimized = merge(true);
+        assert_eq!(count_z_directions(&optimized), 1);
+        assert!(optimized.get_entries().len() < step_data.get_entries().len());
+
+        // the references to the removed duplicates have been redirected
+        assert_eq!(
+            optimized.dangling_references().len(),
+            step_data.dangling_references().len()
+        );
+        assert_eq!(
+            optimized.entity_histogram()["PRODUCT_DEFINITION"],
+            step_data.entity_histogram()["PRODUCT_DEFINITION"]
+        );
+    }
+
+    #[test]
+    fn test_merge_colors() {
+        // the cube is orange, the sphere grey
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let mut output = Vec::new();
+        merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &MergeOptions::default(),
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        assert!(step_data.dangling_references().is_empty());
+
+        let entries = step_data.get_entries();
+        let find = |keyword: &str| {
+            entries
+                .iter()
+                .filter(|entry| entry.keyword() == keyword)
+                .collect::<Vec<_>>()
+        };
+
+        let colors: HashSet<String> = find("COLOUR_RGB")
+            .iter()
+            .map(|entry| entry.get_definition().to_owned())
+            .collect();
+        assert!(colors.contains("COLOUR_RGB('Orange',1.,0.6,0.)"));
+        assert!(colors.contains("COLOUR_RGB('',0.627450980392,0.627450980392,0.627450980392)"));
+
+        // each styled item is still linked to the geometry of its part, i.e., the solid of the
+        // cube and the solid and the faces of the sphere
+        let mut styled_keywords: Vec<&str> = find("STYLED_ITEM")
+            .iter()
+            .map(|styled_item| {
+                let item = *styled_item.get_references().last().unwrap();
+                step_data.get_entry_by_id(item).unwrap().keyword()
+            })
+            .collect();
+        styled_keywords.sort();
+        assert_eq!(
+            styled_keywords,
+            vec![
+                "ADVANCED_FACE",
+                "ADVANCED_FACE",
+                "MANIFOLD_SOLID_BREP",
+                "MANIFOLD_SOLID_BREP"
+            ]
+        );
+
+        // each part keeps its presentation in its own representation context
+        let presentations = find("MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION");
+        assert_eq!(presentations.len(), 2);
+        for presentation in presentations {
+            let context = *presentation.get_references().last().unwrap();
+            assert!(step_data
+                .get_entry_by_id(context)
+                .unwrap()
+                .keywords()
+                .contains(&"GEOMETRIC_REPRESENTATION_CONTEXT"));
+        }
+    }
+
+    #[test]
+    fn test_merge_drop_presentation() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let merge = |drop_presentation: bool| {
+            let options = MergeOptions {
+                drop_presentation,
+                ..Default::default()
+            };
+
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+            StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap()
+        };
+
+        let step_data = merge(false);
+        let histogram = step_data.entity_histogram();
+        assert!(histogram.contains_key("STYLED_ITEM"));
+        assert!(histogram.contains_key("COLOUR_RGB"));
+
+        let dropped = merge(true);
+        let mut presentation = PresentationFilter::new();
+        for entry in dropped.get_entries() {
+            presentation.add_entry(entry);
+        }
+        assert!(presentation.into_dropped_ids().is_empty());
+        assert!(dropped.dangling_references().is_empty());
+
+        // the geometry and the assembly structure are kept
+        let dropped_histogram = dropped.entity_histogram();
+        for keyword in [
+            "CARTESIAN_POINT",
+            "ADVANCED_FACE",
+            "PRODUCT_DEFINITION",
+            "NEXT_ASSEMBLY_USAGE_OCCURRENCE",
+        ] {
+            assert_eq!(dropped_histogram[keyword], histogram[keyword]);
+        }
+    }
+
+    #[test]
+    fn test_merge_drop_presentation_referrers() {
+        let mut vfs = MemoryVfs::new();
+        vfs.insert(
+            "cube.stp",
+            include_bytes!("../../../test_data/cube-presentation.stp").as_slice(),
+        );
+
+        // a single node is copied, while the children are merged
+        let mut single = Node::new("Cube");
+        single.set_link("cube.stp");
+        let mut other = Node::new("Cube 2");
+        other.set_link("cube.stp");
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(2);
+        let cubes = vec![root, single.clone(), other];
+
+        for nodes in [vec![single], cubes] {
+            let assembly = Assembly { nodes, unit: None };
+            let options = MergeOptions {
+                drop_presentation: true,
+                ..Default::default()
+            };
+
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+            let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+            assert!(step_data.dangling_references().is_empty());
+
+            let histogram = step_data.entity_histogram();
+            for keyword in [
+                "SURFACE_STYLE_BOUNDARY",
+                "SURFACE_STYLE_SILHOUETTE",
+                "SURFACE_STYLE_REFLECTANCE_AMBIENT_DIFFUSE_SPECULAR",
+                "PRESENTATION_LAYER_USAGE",
+                "MECHANICAL_DESIGN_AND_DRAFTING_RELATIONSHIP",
+                "DRAUGHTING_MODEL_ITEM_ASSOCIATION",
+            ] {
+                assert!(!histogram.contains_key(keyword), "{}", keyword);
+            }
+            assert!(histogram.contains_key("MANIFOLD_SOLID_BREP"));
+        }
+    }
+
+    #[test]
+    fn test_merge_reproducible() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        for deduplicate_references in [false, true] {
+            let options = MergeOptions {
+                deduplicate_references,
+                header_info: HeaderInfo {
+                    time_stamp: Some("2024-01-01T00:00:00".to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            let merge = || {
+                let mut output = Vec::new();
+                merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                    .unwrap();
+                output
+            };
+
+            assert_eq!(merge(), merge());
+        }
+    }
+
+    #[test]
+    fn test_merge_compact_ids() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../../test_data/cube.stp"));
+        vfs.insert(
+            "sphere.stp",
+            include_bytes!("../../../test_data/sphere.stp"),
+        );
+
+        let options = MergeOptions {
+            create_manifest: true,
+            compact_ids: true,
+            merge_duplicate_geometry: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        let num_entries = step_data.get_entries().len() as u64;
+        assert_eq!(step_data.get_id_range(), 1..num_entries + 1);
+        assert!(step_data.validate_references().is_ok());
+
+        // the manifest refers to the renumbered entries
+        let keyword = |id: u64| step_data.get_entry_by_id(id).unwrap().keyword().to_owned();
+        for node_ids in merge_output.manifest.unwrap().values().flatten() {
+            assert_eq!(
+                keyword(node_ids.product_definition_id),
+                "PRODUCT_DEFINITION"
+            );
+            assert_eq!(
+                keyword(node_ids.shape_representation_id),
+                "SHAPE_REPRESENTATION"
+            );
+        }
+
+        // the ranges of the files start and end at existing entries
+        for range in merge_output.file_id_ranges.unwrap().values() {
+            assert!(step_data.get_entry_by_id(range.start).is_some());
+            assert!(step_data.get_entry_by_id(range.end - 1).is_some());
+        }
+    }
+
+    #[test]
+    fn test_compact_id_range() {
+        let id_map = HashMap::from([(3, 1), (5, 2), (6, 3), (9, 4)]);
+
+        // the endpoints 4 and 8 have been removed
+        assert_eq!(compact_id_range(&(4..9), &id_map), Some(2..4));
+        assert_eq!(compact_id_range(&(3..10), &id_map), Some(1..5));
+        assert_eq!(compact_id_range(&(7..9), &id_map), None);
+    }
+
+    #[test]
+    fn test_merge_root_origins() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../../test_data/cube.stp"));
+        vfs.insert(
+            "sphere.stp",
+            include_bytes!("../../../test_data/sphere.stp"),
+        );
+
+        let options = MergeOptions {
+            create_manifest: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        let root_origins = merge_output.root_origins.unwrap();
+        let links: Vec<&str> = root_origins.iter().map(|(_, link)| link.as_str()).collect();
+        assert_eq!(links, vec!["cube.stp", "sphere.stp"]);
+
+        // the reported roots are the products of the referenced step files
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        let file_id_ranges = merge_output.file_id_ranges.unwrap();
+        for (node_ids, link) in root_origins.iter() {
+            assert!(file_id_ranges[link].contains(&node_ids.product_definition_id));
+            assert_eq!(
+                step_data
+                    .get_entries()
+                    .iter()
+                    .find(|e| e.get_id() == node_ids.product_definition_id)
+                    .unwrap()
+                    .keyword(),
+                "PRODUCT_DEFINITION"
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_file_headers() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+
+        let part = |system: &str| {
+            format!(
+                "ISO-10303-21; HEADER;
+                FILE_NAME('part.stp','2024-01-01T00:00:00',('Jane'),(''),'','{}','');
+                ENDSEC; DATA; #1=APPLICATION_CONTEXT(''); ENDSEC; END-ISO-10303-21;",
+                system
+            )
+        };
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", part("Cube CAD 1.0"));
+        vfs.insert("sphere.stp", part("Sphere CAD 2.0"));
+
+        let options = MergeOptions {
+            create_manifest: true,
+            ..Default::default()
+        };
+
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            Vec::new(),
+            &vfs,
+        )
+        .unwrap();
+
+        let file_headers = merge_output.file_headers.unwrap();
+        assert_eq!(file_headers.len(), 2);
+        assert_eq!(file_headers["cube.stp"].originating_system, "Cube CAD 1.0");
+        assert_eq!(file_headers["cube.stp"].author, vec!["Jane"]);
+        assert_eq!(
+            file_headers["sphere.stp"].originating_system,
+            "Sphere CAD 2.0"
+        );
+    }
+
+    #[test]
+    fn test_merge_failed_references() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let vfs = MemoryVfs::from_iter([(
+            "cube.stp",
+            include_bytes!("../../../test_data/cube.stp").as_slice(),
+        )]);
+
+        // the merge continues without the missing sphere
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &MergeOptions::default(),
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        assert_eq!(merge_output.failed_references.len(), 1);
+        let (link, err) = &merge_output.failed_references[0];
+        assert_eq!(link, "sphere.stp");
+        assert!(matches!(err, Error::FailedOpenFile(_, path) if path == "sphere.stp"));
+        assert!(StepData::from_str(std::str::from_utf8(&output).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_merge_on_missing_reference_fail() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let vfs = MemoryVfs::from_iter([(
+            "cube.stp",
+            include_bytes!("../../../test_data/cube.stp").as_slice(),
+        )]);
+        let options = MergeOptions {
+            on_missing_reference: OnMissingReference::Fail,
+            ..Default::default()
+        };
+
+        let result = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            Vec::new(),
+            &vfs,
+        );
+        assert!(matches!(result, Err(Error::FailedOpenFile(_, path)) if path == "sphere.stp"));
+    }
+
+    #[test]
+    fn test_merge_sub_assembly_reference() {
+        let sub_stp = include_str!("../../../test_data/2-cubes-1-sphere.stp");
+
+        let mut root = Node::new("Assembly");
+        root.add_child(1);
+        let mut sub = Node::new("Sub");
+        sub.set_link("sub.stp");
+        let assembly = Assembly {
+            nodes: vec![root, sub],
+            unit: None,
+        };
+
+        let vfs = MemoryVfs::from_iter([("sub.stp", sub_stp)]);
+        let options = MergeOptions {
+            create_manifest: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output =
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        step_data.validate_references().unwrap();
+
+        let occurrences = |step_data: &StepData| -> Vec<StepEntry> {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == "NEXT_ASSEMBLY_USAGE_OCCURRENCE")
+                .cloned()
+                .collect()
+        };
+
+        // the internal occurrences are kept with shifted ids and references
+        let source = StepData::from_str(sub_stp).unwrap();
+        let source_occurrences = occurrences(&source);
+        assert_eq!(source_occurrences.len(), 7);
+
+        let last_id = source.get_entries().iter().map(|e| e.get_id()).max();
+        let offset = merge_output.file_id_ranges.unwrap()["sub.stp"].end - 1 - last_id.unwrap();
+        for occurrence in source_occurrences.iter() {
+            let merged = step_data
+                .get_entry_by_id(occurrence.get_id() + offset)
+                .unwrap();
+            assert_eq!(merged.keyword(), "NEXT_ASSEMBLY_USAGE_OCCURRENCE");
+            assert_eq!(
+                merged.get_references(),
+                occurrence
+                    .get_references()
+                    .iter()
+                    .map(|id| id + offset)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        // the root of the sub-assembly is linked to the assembly node
+        let node_ids = merge_output.manifest.unwrap()["Sub"][0];
+        let root_origins = merge_output.root_origins.unwrap();
+        assert_eq!(root_origins.len(), 1);
+        let sub_root_ids = root_origins[0].0;
+        assert!(occurrences(&step_data).iter().any(|e| e.get_references()
+            == vec![
+                node_ids.product_definition_id,
+                sub_root_ids.product_definition_id
+            ]));
+        assert_eq!(occurrences(&step_data).len(), source_occurrences.len() + 2);
+    }
+
+    #[test]
+    fn test_merge_id_blocks() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../../test_data/cube.stp"));
+        vfs.insert(
+            "sphere.stp",
+            include_bytes!("../../../test_data/sphere.stp"),
+        );
+
+        let options = MergeOptions {
+            create_manifest: true,
+            id_block_size: Some(10000),
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+
+        let file_id_ranges = merge_output.file_id_ranges.unwrap();
+        let cube = file_id_ranges["cube.stp"].clone();
+        let sphere = file_id_ranges["sphere.stp"].clone();
+
+        // each file occupies its own block
+        assert_eq!(cube.start / 10000, 1);
+        assert_eq!((cube.end - 1) / 10000, 1);
+        assert_eq!(sphere.start / 10000, 2);
+        assert_eq!((sphere.end - 1) / 10000, 2);
+
+        // the block of the cube is used exclusively by its entries
+        let step_data = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        let block_ids: Vec<u64> = step_data
+            .get_entries()
+            .iter()
+            .map(|e| e.get_id())
+            .filter(|id| (10000..20000).contains(id))
+            .collect();
+        assert!(!block_ids.is_empty());
+        assert!(block_ids.iter().all(|id| cube.contains(id)));
+    }
+
+    #[test]
+    fn test_merge_provenance_comments() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        let options = MergeOptions {
+            create_manifest: true,
+            provenance_comments: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &serde_json::from_slice::<Assembly>(assembly).unwrap(),
+            &options,
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+        let file_id_ranges = merge_output.file_id_ranges.unwrap();
+
+        // assign the entries to the file of the enclosing comments
+        let output = std::str::from_utf8(&output).unwrap();
+        let mut current_file: Option<&str> = None;
+        let mut num_entries = HashMap::new();
+        for line in output.lines() {
+            if let Some(link) = line.strip_prefix("/* from ") {
+                assert!(current_file.is_none());
+                current_file = link.strip_suffix(" */");
+            } else if let Some(link) = line.strip_prefix("/* end of ") {
+                assert_eq!(current_file, link.strip_suffix(" */"));
+                current_file = None;
+            } else if let Some(id) = line.strip_prefix('#').and_then(|l| l.split_once('=')) {
+                let id = id.0.parse::<u64>().unwrap();
+                match current_file {
+                    Some(link) => {
+                        assert!(file_id_ranges[link].contains(&id));
+                        *num_entries.entry(link).or_insert(0) += 1;
+                    }
+                    None => assert!(file_id_ranges.values().all(|r| !r.contains(&id))),
+                }
+            }
+        }
+
+        assert!(current_file.is_none());
+        assert_eq!(num_entries.len(), 2);
+
+        // the comments are skipped when reading the merged file
+        assert!(StepData::from_str(output).is_ok());
+    }
+
+    #[test]
+    fn test_merge_external_references() {
+        let external_stp = b"ISO-10303-21; HEADER; ENDSEC; \
+            REFERENCE; #10=<part.stp#body>; ENDSEC; \
+            DATA; #1=APPLICATION_CONTEXT('x'); #2=FOO(#10); ENDSEC; END-ISO-10303-21;";
+
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        let mut part = Node::new("Part");
+        part.set_link("external.stp");
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("external.stp", external_stp.as_slice());
+
+        // the single node is not copied and the file is reported as failed reference
+        for nodes in [vec![part.clone()], vec![root, part]] {
+            let assembly = Assembly { nodes, unit: None };
+            for deduplicate_references in [false, true] {
+                let options = MergeOptions {
+                    deduplicate_references,
+                    ..Default::default()
+                };
+
+                let merge_output = merge_assembly_structure_to_step_with_vfs(
+                    &assembly,
+                    &options,
+                    Vec::new(),
+                    &vfs,
+                )
+                .unwrap();
+                assert!(!merge_output.passthrough);
+                assert!(matches!(
+                    merge_output.failed_references.as_slice(),
+                    [(link, Error::ExternalReferences(_))] if link == "external.stp"
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_provenance_comments_incompatible() {
+        let assembly: Assembly =
+            serde_json::from_slice(include_bytes!("../../../test_data/cube-and-sphere.json"))
+                .unwrap();
+        let vfs = MemoryVfs::from_iter([
+            (
+                "cube.stp",
+                include_bytes!("../../../test_data/cube.stp").as_slice(),
+            ),
+            (
+                "sphere.stp",
+                include_bytes!("../../../test_data/sphere.stp").as_slice(),
+            ),
+        ]);
+
+        for options in [
+            MergeOptions {
+                compact_ids: true,
+                ..Default::default()
+            },
+            MergeOptions {
+                topological_order: true,
+                ..Default::default()
+            },
+            MergeOptions {
+                merge_duplicate_geometry: true,
+                ..Default::default()
+            },
+        ] {
+            let options = MergeOptions {
+                provenance_comments: true,
+                ..options
+            };
+
+            assert!(matches!(
+                merge_assembly_structure_to_step_with_vfs(&assembly, &options, Vec::new(), &vfs),
+                Err(Error::IncompatibleOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_merge_uncertainty_mismatch() {
+        let assembly: Assembly =
+            serde_json::from_slice(include_bytes!("../../../test_data/cube-and-sphere.json"))
+                .unwrap();
+        let cube_stp = include_bytes!("../../../test_data/cube.stp").as_slice();
+        let sphere_stp = include_bytes!("../../../test_data/sphere.stp").as_slice();
+        let merge = |sphere_stp: &[u8], options: &MergeOptions| {
+            let vfs = MemoryVfs::from_iter([("cube.stp", cube_stp), ("sphere.stp", sphere_stp)]);
+            merge_assembly_structure_to_step_with_vfs(&assembly, options, Vec::new(), &vfs)
+                .unwrap()
+                .diagnostics
+        };
+
+        let options = MergeOptions {
+            max_uncertainty_ratio: Some(1000.0),
+            ..Default::default()
+        };
+
+        // the sphere is modelled with a much finer accuracy than the cube
+        let diagnostics = merge(sphere_stp, &options);
+        assert_eq!(diagnostics.len(), 1);
+        match &diagnostics[0] {
+            MergeDiagnostic::UncertaintyMismatch((min_link, min), (max_link, max)) => {
+                assert_eq!(min_link, "sphere.stp");
+                assert_eq!(max_link, "cube.stp");
+                assert!((min - 1e-16).abs() < 1e-20);
+                assert!((max - 2e-8).abs() < 1e-12);
+            }
+        }
+
+        // the check is disabled by default
+        assert!(merge(sphere_stp, &MergeOptions::default()).is_empty());
+
+        // references with the same accuracy do not cause a mismatch
+        assert!(merge(cube_stp, &options).is_empty());
+    }
+
+    #[test]
+    fn test_merge_single_part_passthrough() {
+        let cube_stp = include_bytes!("../../../test_data/cube.stp");
+
+        let mut node = Node::new("Cube");
+        node.set_link("cube.stp");
+        let assembly = Assembly {
+            nodes: vec![node],
+            unit: None,
+        };
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", cube_stp.as_slice());
+
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &MergeOptions::default(),
+            &mut output,
+            &vfs,
+        )
+        .unwrap();
+        assert!(merge_output.passthrough);
+
+        let expected = StepData::from_str(std::str::from_utf8(cube_stp).unwrap()).unwrap();
+        let actual = StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+        assert_eq!(expected.get_entries(), actual.get_entries());
+
+        // the schema of the copied step file is kept
+        assert_eq!(
+            actual.get_header().schemas,
+            vec!["AUTOMOTIVE_DESIGN { 1 0 10303 214 3 1 1 1 }".to_owned()]
+        );
+
+        // the regular merge is used if the options require the entries to be modified or tracked
+        for options in [
+            MergeOptions {
+                create_manifest: true,
+                ..Default::default()
+            },
+            MergeOptions {
+                length_unit_prefix: SiPrefix::Micro,
+                ..Default::default()
+            },
+            MergeOptions {
+                id_block_size: Some(1000),
+                ..Default::default()
+            },
+            MergeOptions {
+                provenance_comments: true,
+                ..Default::default()
+            },
+            MergeOptions {
+                revision_metadata_key: Some("revision".to_owned()),
+                ..Default::default()
+            },
+        ] {
+            let mut output = Vec::new();
+            let merge_output =
+                merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                    .unwrap();
+            assert!(!merge_output.passthrough);
+        }
+
+        // the regular merge is used if the linked file cannot be opened
+        let mut output = Vec::new();
+        let merge_output = merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &MergeOptions::default(),
+            &mut output,
+            &MemoryVfs::new(),
+        )
+        .unwrap();
+        assert!(!merge_output.passthrough);
+    }
+
+    #[test]
+    fn test_merge_passthrough_output() {
+        let cube_stp = include_bytes!("../../../test_data/cube.stp");
+
+        let mut node = Node::new("Cube");
+        node.set_link("cube.stp");
+        let assembly = Assembly {
+            nodes: vec![node],
+            unit: None,
+        };
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", cube_stp.as_slice());
+        let options = MergeOptions::default();
+
+        let passthrough =
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, Vec::new(), &vfs)
+                .unwrap();
+
+        // the regular merge of the same assembly
+        let mut on_progress = |_| {};
+        let merged = StepMerger::new(
+            Vec::new(),
+            &assembly,
+            &options,
+            |link: &str| vfs.open(link),
+            &mut on_progress,
+        )
+        .unwrap()
+        .merge()
+        .unwrap();
+
+        // only the flag marking the copied step file differs
+        assert!(passthrough.passthrough);
+        assert!(!merged.passthrough);
+        assert_eq!(passthrough.manifest, merged.manifest);
+        assert_eq!(passthrough.file_id_ranges, merged.file_id_ranges);
+        assert_eq!(passthrough.root_origins, merged.root_origins);
+        assert_eq!(passthrough.file_headers, merged.file_headers);
+        assert_eq!(
+            passthrough.failed_references.len(),
+            merged.failed_references.len()
+        );
+        assert_eq!(passthrough.diagnostics, merged.diagnostics);
+    }
+
+    #[test]
+    fn test_merge_deduplicate_references() {
+        let cube_stp = include_bytes!("../../../test_data/cube.stp");
+
+        let mut root = Node::new("Root");
+        root.add_child(1);
+        root.add_child(2);
+        let mut cube1 = Node::new("Cube 1");
+        cube1.set_link("cube1.stp");
+        let mut cube2 = Node::new("Cube 2");
+        cube2.set_link("cube2.stp");
+        let assembly = Assembly {
+            nodes: vec![root, cube1, cube2],
+            unit: None,
+        };
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube1.stp", cube_stp.as_slice());
+        vfs.insert("cube2.stp", cube_stp.as_slice());
+
+        let merge = |deduplicate_references: bool| {
+            let options = MergeOptions {
+                deduplicate_references,
+                ..Default::default()
+            };
+
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_vfs(&assembly, &options, &mut output, &vfs)
+                .unwrap();
+
+            StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap()
+        };
+
+        let count = |step_data: &StepData, keyword: &str| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == keyword)
+                .count()
+        };
+
+        let duplicated = merge(false);
+        let deduplicated = merge(true);
+        assert!(deduplicated.get_entries().len() < duplicated.get_entries().len());
+        assert_eq!(count(&deduplicated, "MANIFOLD_SOLID_BREP"), 1);
+        assert_eq!(count(&duplicated, "MANIFOLD_SOLID_BREP"), 2);
+
+        // both nodes still reference the shared cube
+        assert_eq!(
+            count(&deduplicated, "NEXT_ASSEMBLY_USAGE_OCCURRENCE"),
+            count(&duplicated, "NEXT_ASSEMBLY_USAGE_OCCURRENCE")
+        );
+        assert!(deduplicated.validate_references().is_ok());
+    }
+
+    #[test]
+    fn test_deduplicate_references_hash_collision() {
+        let cube_stp = include_bytes!("../../../test_data/cube.stp");
+        let sphere_stp = include_bytes!("../../../test_data/sphere.stp");
+
+        let assembly = Assembly {
+            nodes: vec![Node::new("Root")],
+            unit: None,
+        };
+        let options = MergeOptions::default();
+        let vfs = MemoryVfs::from_iter([
+            ("cube.stp", cube_stp.as_slice()),
+            ("sphere.stp", sphere_stp.as_slice()),
+        ]);
+
+        let mut output = Vec::new();
+        let mut on_progress = |_| {};
+        let mut merger = StepMerger::new(
+            &mut output,
+            &assembly,
+            &options,
+            |link: &str| vfs.open(link),
+            &mut on_progress,
+        )
+        .unwrap();
+
+        // pretend that the cube has the same hash as the sphere
+        let cube_nodes = vec![NodeStepIds {
+            product_definition_id: 1,
+            shape_representation_id: 2,
+        }];
+        let hash = content_hash(sphere_stp);
+        let mut content_map =
+            ContentMap::from([(hash, vec![("cube.stp".to_owned(), cube_nodes.clone())])]);
+
+        let sphere_nodes = merger
+            .load_and_add_step_deduplicated("sphere.stp", &mut content_map)
+            .unwrap();
+        assert_ne!(sphere_nodes, cube_nodes);
+        assert_eq!(content_map[&hash].len(), 2);
+
+        // a file with identical content is still reused
+        assert_eq!(
+            merger
+                .load_and_add_step_deduplicated("sphere.stp", &mut content_map)
+                .unwrap(),
+            sphere_nodes
+        );
+        assert_eq!(content_map[&hash].len(), 2);
+    }
+
+    #[test]
+    fn test_merge_shared_identity_transforms() {
+        let mut builder = AssemblyBuilder::new();
+        let root = builder.add_node("root");
+        let a = builder.add_node("a");
+        let b = builder.add_node("b");
+        builder
+            .add_child(root, a)
+            .unwrap()
+            .add_child(root, b)
+            .unwrap();
+        let assembly = builder.build().unwrap();
+
+        let merge = |share_identity_transforms: bool| {
+            let options = MergeOptions {
+                load_references: false,
+                share_identity_transforms,
+                ..Default::default()
+            };
+
+            let mut output = Vec::new();
+            merge_assembly_structure_to_step_with_vfs(
+                &assembly,
+                &options,
+                &mut output,
+                &MemoryVfs::default(),
+            )
+            .unwrap();
+
+            StepData::from_str(std::str::from_utf8(&output).unwrap()).unwrap()
+        };
+        let count = |step_data: &StepData, keyword: &str| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == keyword)
+                .count()
+        };
+
+        let step_data = merge(false);
+        assert_eq!(count(&step_data, "ITEM_DEFINED_TRANSFORMATION"), 2);
+        let num_points = count(&step_data, "CARTESIAN_POINT");
+
+        // both relations reference the single transformation of the default coordinate system
+        let step_data = merge(true);
+        let transformations = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "ITEM_DEFINED_TRANSFORMATION")
+            .collect::<Vec<_>>();
+        assert_eq!(transformations.len(), 1);
+        assert_eq!(count(&step_data, "CARTESIAN_POINT"), num_points - 2);
+
+        let default_coordinate_system = transformations[0].get_references()[0];
+        assert_eq!(
+            transformations[0].get_references(),
+            vec![default_coordinate_system; 2]
+        );
+
+        let transformation_id = transformations[0].get_id();
+        let relationships = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| {
+                e.get_definition()
+                    .contains("REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(relationships.len(), 2);
+        assert!(relationships
+            .iter()
+            .all(|e| e.get_references().contains(&transformation_id)));
+    }
+
+    #[test]
+    fn test_merge_shared_node_coordinate_system() {
+        let assembly = include_bytes!("../../../test_data/cube-and-sphere.json");
+        let assembly = serde_json::from_slice::<Assembly>(assembly).unwrap();
+
+        let merge = |share_node_coordinate_system: bool| {
+            let options = MergeOptions {
+                share_node_coordinate_system,
+                ..Default::default()
+            };
+
+            merge_structure(&assembly, options)
+        };
+
+        // returns the placements referenced by the shape representations
+        let shape_placements = |step_data: &StepData| {
+            step_data
+                .get_entries()
+                .iter()
+                .filter(|e| e.keyword() == "SHAPE_REPRESENTATION")
+                .map(|e| e.get_references()[0])
+                .collect::<HashSet<u64>>()
+        };
+
+        let step_data = merge(false);
+        assert_eq!(shape_placements(&step_data).len(), assembly.nodes.len());
+
+        let step_data = merge(true);
+        let placements = shape_placements(&step_data);
+        assert_eq!(placements.len(), 1);
+
+        let placement_id = placements.into_iter().next().unwrap();
+        let placement = step_data.get_entry_by_id(placement_id).unwrap();
+        assert!(placement.get_definition().starts_with("AXIS2_PLACEMENT_3D"));
+
+        // the transformations originate from the same placement
+        let transformations: Vec<&StepEntry> = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "ITEM_DEFINED_TRANSFORMATION")
+            .collect();
+        assert!(!transformations.is_empty());
+        assert!(transformations
+            .iter()
+            .all(|t| t.get_references()[0] == placement_id));
+    }
+
+    #[test]
+    fn test_merge_relationship_name() {
+        let mut root = Node::new("Root");
+        root.add_child(1).set_relationship_name("Mate 1");
+        root.add_child(2);
+
+        let assembly: Assembly = serde_json::from_value(serde_json::json!({
+            "nodes": [root, Node::new("Bolt"), Node::new("Nut")]
+        }))
+        .unwrap();
+        assert_eq!(
+            assembly.nodes[0].get_children()[0].get_relationship_name(),
+            Some("Mate 1")
+        );
+
+        let step_data = merge_structure(&assembly, MergeOptions::default());
+        let relationship_names: Vec<String> = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| {
+                e.get_definition()
+                    .starts_with("(REPRESENTATION_RELATIONSHIP(")
+            })
+            .map(|e| e.get_strings().remove(0))
+            .collect();
+
+        assert_eq!(relationship_names, vec!["Mate 1", "Child > Parent"]);
+    }
+
+    #[test]
+    fn test_merge_reference_designator() {
+        let mut root = Node::new("Root");
+        root.add_child(1).set_reference_designator("A1");
+        root.add_child(1).set_reference_designator("A2");
+        root.add_child(2);
+
+        let assembly: Assembly = serde_json::from_value(serde_json::json!({
+            "nodes": [root, Node::new("Bolt"), Node::new("Nut")]
+        }))
+        .unwrap();
+        assert_eq!(
+            assembly.nodes[0].get_children()[0].get_reference_designator(),
+            Some("A1")
+        );
+
+        // the reference designator is the last string of the occurrence
+        let step_data = merge_structure(&assembly, MergeOptions::default());
+        let designators: Vec<String> = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "NEXT_ASSEMBLY_USAGE_OCCURRENCE")
+            .map(|e| e.get_strings().pop().unwrap())
+            .collect();
+
+        assert_eq!(designators, vec!["A1", "A2", "Nut"]);
+    }
+
+    #[test]
+    fn test_merge_instance_name() {
+        let mut root = Node::new("Root");
+        root.add_child(1).set_instance_name("BOLT-1");
+        root.add_child(1).set_instance_name("BOLT-2");
+        root.add_child(2);
+
+        let assembly: Assembly = serde_json::from_value(serde_json::json!({
+            "nodes": [root, Node::new("Bolt"), Node::new("Nut")]
+        }))
+        .unwrap();
+        assert_eq!(
+            assembly.nodes[0].get_children()[0].get_instance_name(),
+            Some("BOLT-1")
+        );
+
+        // the instance name is the id of the occurrence, the description keeps the part label
+        let step_data = merge_structure(&assembly, MergeOptions::default());
+        let occurrences: Vec<(String, String)> = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "NEXT_ASSEMBLY_USAGE_OCCURRENCE")
+            .map(|e| {
+                let strings = e.get_strings();
+                (strings[0].clone(), strings[2].clone())
+            })
+            .collect();
+
+        assert_eq!(
+            occurrences,
+            vec![
+                ("BOLT-1".to_owned(), "Bolt".to_owned()),
+                ("BOLT-2".to_owned(), "Bolt".to_owned()),
+                ("Nut".to_owned(), "Nut".to_owned()),
+            ]
+        );
+
+        // the edges refer to the part instead of the occurrence
+        let edges = step_data.occurrence_edges();
+        assert_eq!(
+            edges,
+            vec![
+                ("Root".to_owned(), "Bolt".to_owned()),
+                ("Root".to_owned(), "Bolt".to_owned()),
+                ("Root".to_owned(), "Nut".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_non_ascii_label() {
+        let mut nodes = vec![Node::new("Root"), Node::new("Überträger")];
+        nodes[0].add_child(1);
+        let assembly = Assembly { nodes, unit: None };
+
+        let (_, output) = merge_structure_output(&assembly, MergeOptions::default());
+        assert!(output.is_ascii());
+        assert!(output.contains("PRODUCT('\\X2\\00DC\\X0\\bertr\\X2\\00E4\\X0\\ger'"));
+
+        let step_data = StepData::from_str(&output).unwrap();
+        let labels = step_data
+            .get_entries()
+            .iter()
+            .filter(|e| e.keyword() == "PRODUCT")
+            .map(|e| e.decoded_strings().unwrap()[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Root", "Überträger"]);
+    }
 }
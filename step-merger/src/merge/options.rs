@@ -0,0 +1,180 @@
+use crate::step::{ApplicationProtocol, HeaderInfo, LineEnding, SectionSpacing, SiPrefix};
+
+use super::root_nodes::RootNodeKeywords;
+
+/// Defines how the transformation matrices of the assembly nodes are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransformSemantics {
+    /// The transform of a node is relative to its parent node.
+    #[default]
+    Relative,
+
+    /// The transform of a node is given in world coordinates.
+    Absolute,
+}
+
+/// Defines how strictly the entities of the merged STEP file are checked against the schema of
+/// the targeted application protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conformance {
+    /// All entities are accepted.
+    #[default]
+    Lenient,
+
+    /// Entities unknown to the targeted application protocol are rejected with an error.
+    Strict,
+}
+
+/// Defines how referenced step files that cannot be loaded are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnMissingReference {
+    /// The reference is skipped and the merge continues. The failures are reported in the merge
+    /// output.
+    #[default]
+    Skip,
+
+    /// The merge is aborted with the error of the first reference that cannot be loaded.
+    Fail,
+}
+
+/// The options for merging an assembly structure into a single monolithic STEP file.
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    /// Flag to indicate if external references should be loaded.
+    pub load_references: bool,
+
+    /// The application protocol the merged STEP file is targeting.
+    pub protocol: ApplicationProtocol,
+
+    /// Flag to indicate if a manifest mapping the node labels to their STEP ids should be
+    /// returned.
+    pub create_manifest: bool,
+
+    /// Flag to indicate if the generated AXIS2_PLACEMENT_3D entries are checked to reference a
+    /// point and two directions. Enabled by default in debug builds.
+    pub validate_placements: bool,
+
+    /// The SI prefix of the emitted length unit. The translations of the assembly, which are
+    /// given in meters, are scaled accordingly.
+    pub length_unit_prefix: SiPrefix,
+
+    /// The spacing between the sections of the merged STEP file.
+    pub section_spacing: SectionSpacing,
+
+    /// The line terminator of the merged STEP file.
+    pub line_ending: LineEnding,
+
+    /// The maximum width of the lines of an entry in the merged STEP file. If None, each entry is
+    /// written as a single line.
+    pub max_line_width: Option<usize>,
+
+    /// Defines how the transformation matrices of the assembly nodes are interpreted.
+    pub transform_semantics: TransformSemantics,
+
+    /// The metadata key, e.g., `revision`, whose value is written as the id of the
+    /// PRODUCT_DEFINITION_FORMATION of the node. If not set, the id is left empty.
+    pub revision_metadata_key: Option<String>,
+
+    /// Flag to indicate if the shape representations of all nodes reference the shared default
+    /// coordinate system instead of creating their own, identical placement. Enabled by default,
+    /// as the separate placements only add redundant entries for large assemblies.
+    ///
+    /// The transformations of the parent-child relations always map from the default coordinate
+    /// system. Both modes are geometrically equivalent, as every placement of a node is located
+    /// at the origin with the canonical axes. Only the shared mode makes the coupling explicit,
+    /// i.e., the node shapes are placed in the same coordinate system the transformations
+    /// originate from.
+    pub share_node_coordinate_system: bool,
+
+    /// Flag to indicate if all parent-child relations with an identity transform share a single
+    /// transformation referencing the default coordinate system instead of creating their own,
+    /// identical placement.
+    pub share_identity_transforms: bool,
+
+    /// Flag to indicate if referenced step files with identical content but different links are
+    /// only merged once. The nodes linking to such files then share the merged entries.
+    pub deduplicate_references: bool,
+
+    /// The keywords of the entities evaluated to find the root nodes of the referenced step
+    /// files.
+    pub root_node_keywords: RootNodeKeywords,
+
+    /// Flag to indicate if the entries of the merged STEP file are renumbered to gap-free ids
+    /// starting at 1. Requires buffering the merged STEP file in memory.
+    pub compact_ids: bool,
+
+    /// Flag to indicate if the entries of the merged STEP file are written in topological order,
+    /// i.e., referenced entries precede the entries referencing them unless the references form
+    /// cycles. The ids are kept. Requires buffering the merged STEP file in memory.
+    pub topological_order: bool,
+
+    /// Flag to indicate if the CARTESIAN_POINT and DIRECTION entries with identical definitions
+    /// are merged into a single entry across the whole merged STEP file, which reduces the file
+    /// size of large assemblies. Requires buffering the merged STEP file in memory.
+    pub merge_duplicate_geometry: bool,
+
+    /// Flag to indicate if the presentation entities, e.g., colors, styles and layers, of the
+    /// referenced step files are dropped, if only the geometry and the assembly structure are
+    /// needed. The entries referring to them are dropped as well, which requires buffering each
+    /// referenced step file in memory.
+    pub drop_presentation: bool,
+
+    /// Flag to indicate if the entries of each referenced step file are enclosed by comments
+    /// naming the link of the file, e.g., `/* from cube.stp */`, for tracing the origin of the
+    /// entries. Cannot be combined with the options reordering or renumbering the entries, i.e.,
+    /// `compact_ids`, `topological_order` and `merge_duplicate_geometry`.
+    pub provenance_comments: bool,
+
+    /// The maximum ratio between the largest and the smallest global uncertainty of the
+    /// referenced step files before a mismatch is reported, as mixed accuracies may cause
+    /// tolerance problems downstream. If None, the uncertainties are not checked.
+    pub max_uncertainty_ratio: Option<f64>,
+
+    /// The size of the id blocks reserved for the referenced step files. If set, the entries of
+    /// each referenced step file start in a new block, i.e., after the next multiple of the block
+    /// size. The occupied ranges are part of the manifest.
+    pub id_block_size: Option<u64>,
+
+    /// The creator information written into the FILE_NAME header entity of the merged file,
+    /// e.g., the author and the originating system.
+    pub header_info: HeaderInfo,
+
+    /// Defines if entities unknown to the targeted application protocol are rejected, e.g., to
+    /// catch misspelled entities or entities that other tools fail to import.
+    pub conformance: Conformance,
+
+    /// Defines if the merge is aborted if a referenced step file cannot be loaded. Only has an
+    /// effect if `load_references` is set, as no referenced step files are loaded otherwise.
+    pub on_missing_reference: OnMissingReference,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            load_references: true,
+            protocol: ApplicationProtocol::default(),
+            create_manifest: false,
+            validate_placements: cfg!(debug_assertions),
+            length_unit_prefix: SiPrefix::default(),
+            section_spacing: SectionSpacing::default(),
+            line_ending: LineEnding::default(),
+            max_line_width: None,
+            transform_semantics: TransformSemantics::default(),
+            revision_metadata_key: None,
+            share_node_coordinate_system: true,
+            share_identity_transforms: false,
+            deduplicate_references: false,
+            root_node_keywords: RootNodeKeywords::default(),
+            compact_ids: false,
+            topological_order: false,
+            merge_duplicate_geometry: false,
+            drop_presentation: false,
+            provenance_comments: false,
+            max_uncertainty_ratio: None,
+            id_block_size: None,
+            header_info: HeaderInfo::default(),
+            conformance: Conformance::default(),
+            on_missing_reference: OnMissingReference::default(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use crate::{step::StepHeader, Error};
+
+use super::utils::NodeStepIds;
+
+/// A problem detected while merging, which does not prevent the merge but may cause problems in
+/// downstream applications.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeDiagnostic {
+    /// The global uncertainties of the referenced STEP files, given in meters, differ by more than
+    /// the configured ratio. Holds the links and uncertainties of the files with the smallest
+    /// and the largest uncertainty.
+    UncertaintyMismatch((String, f64), (String, f64)),
+}
+
+impl Display for MergeDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeDiagnostic::UncertaintyMismatch((min_link, min), (max_link, max)) => write!(
+                f,
+                "Uncertainty of {} ({} m) differs from uncertainty of {} ({} m)",
+                min_link, min, max_link, max
+            ),
+        }
+    }
+}
+
+/// The output of merging an assembly structure into a single monolithic STEP file.
+#[derive(Debug, Clone, Default)]
+pub struct MergeOutput {
+    /// Maps the label of the assembly nodes to the STEP ids generated for them. Nodes sharing a
+    /// label are listed in the order of the assembly. Only set if the manifest has been requested
+    /// in the merge options.
+    pub manifest: Option<HashMap<String, Vec<NodeStepIds>>>,
+
+    /// Maps the link of each loaded STEP file to the range of ids occupied by its entries. Only
+    /// set if the manifest has been requested in the merge options.
+    pub file_id_ranges: Option<HashMap<String, Range<u64>>>,
+
+    /// The root products of the referenced STEP files together with the link of the STEP file
+    /// they originated from, in the order the files have been loaded. Only set if the manifest has
+    /// been requested in the merge options.
+    pub root_origins: Option<Vec<(NodeStepIds, String)>>,
+
+    /// Maps the link of each loaded STEP file to its header, e.g., to trace the originating
+    /// system and the authors of the merged data. Only set if the manifest has been requested in
+    /// the merge options.
+    pub file_headers: Option<HashMap<String, StepHeader>>,
+
+    /// The links of the referenced STEP files that could not be loaded together with the error.
+    /// The merge continues without these files, i.e., the caller has to decide whether such a
+    /// partial merge is acceptable.
+    pub failed_references: Vec<(String, Error)>,
+
+    /// The problems detected while merging, which are logged as warnings as well.
+    pub diagnostics: Vec<MergeDiagnostic>,
+
+    /// Flag to indicate if the assembly was trivial and the referenced STEP file has been copied
+    /// instead of being merged.
+    pub passthrough: bool,
+}
@@ -0,0 +1,108 @@
+use std::{collections::HashMap, io::Cursor};
+
+use rayon::prelude::*;
+
+use crate::{
+    step::{STEPReader, STEPReaderTrait, StepEntry, StepHeader},
+    Result,
+};
+
+/// A referenced step file, which has been parsed ahead of adding its entries to the merged step
+/// file.
+pub(super) struct ParsedStep {
+    /// The hash of the content of the step file for detecting identical files.
+    pub content_hash: u64,
+
+    /// The header of the step file.
+    pub header: StepHeader,
+
+    /// The entries of the step file in the order of the file.
+    pub entries: Vec<StepEntry>,
+}
+
+/// Parses the given contents of the referenced step files on the rayon thread pool. The contents
+/// that could not be read keep their error.
+///
+/// # Arguments
+/// * `contents` - The links of the step files together with their content.
+pub(super) fn parse_step_files(
+    contents: Vec<(String, Result<Vec<u8>>)>,
+) -> HashMap<String, Result<ParsedStep>> {
+    contents
+        .into_par_iter()
+        .map(|(link, content)| {
+            let parsed = content.and_then(|content| parse_step_file(&link, content));
+            (link, parsed)
+        })
+        .collect()
+}
+
+/// Parses the given content of a step file.
+///
+/// # Arguments
+/// * `link` - The link to the step file.
+/// * `content` - The content of the step file.
+fn parse_step_file(link: &str, content: Vec<u8>) -> Result<ParsedStep> {
+    let content_hash = super::content_hash(&content);
+
+    let parser = STEPReader::new(Cursor::new(content.as_slice()))?;
+    super::check_external_references(link, &parser)?;
+    let header = parser.header().clone();
+    let entries = parser.into_iter().collect::<Result<Vec<_>>>()?;
+
+    Ok(ParsedStep {
+        content_hash,
+        header,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_step_files() {
+        let contents = vec![
+            (
+                "cube.stp".to_owned(),
+                Ok(include_bytes!("../../../test_data/cube.stp").to_vec()),
+            ),
+            (
+                "copy.stp".to_owned(),
+                Ok(include_bytes!("../../../test_data/cube.stp").to_vec()),
+            ),
+            (
+                "missing.stp".to_owned(),
+                Err(Error::AppContextMissing(String::new())),
+            ),
+            ("invalid.stp".to_owned(), Ok(b"no step file".to_vec())),
+            (
+                "external.stp".to_owned(),
+                Ok(
+                    b"ISO-10303-21; HEADER; ENDSEC; REFERENCE; #10=<part.stp#body>; ENDSEC; \
+                     DATA; #1=FOO(#10); ENDSEC; END-ISO-10303-21;"
+                        .to_vec(),
+                ),
+            ),
+        ];
+
+        let mut parsed = parse_step_files(contents);
+        assert_eq!(parsed.len(), 5);
+
+        let cube = parsed.remove("cube.stp").unwrap().unwrap();
+        let copy = parsed.remove("copy.stp").unwrap().unwrap();
+        assert!(!cube.entries.is_empty());
+        assert_eq!(cube.entries.len(), copy.entries.len());
+        assert_eq!(cube.content_hash, copy.content_hash);
+
+        assert!(parsed.remove("missing.stp").unwrap().is_err());
+        assert!(parsed.remove("invalid.stp").unwrap().is_err());
+        assert!(matches!(
+            parsed.remove("external.stp").unwrap(),
+            Err(Error::ExternalReferences(link)) if link == "external.stp"
+        ));
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+/// The progress of merging an assembly structure, e.g., to show a progress bar. The totals are
+/// known from the start, while the counters increase as the merge proceeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeProgress {
+    /// The number of assembly nodes created so far.
+    pub nodes_created: usize,
+
+    /// The total number of assembly nodes.
+    pub num_nodes: usize,
+
+    /// The number of parent-child relations between the assembly nodes created so far.
+    pub relations_created: usize,
+
+    /// The total number of parent-child relations between the assembly nodes.
+    pub num_relations: usize,
+
+    /// The number of referenced step files processed so far, including the ones that failed to
+    /// load.
+    pub references_loaded: usize,
+
+    /// The total number of distinct referenced step files to be loaded.
+    pub num_references: usize,
+
+    /// The number of bytes of the merged step file written so far. If the merged step file is
+    /// buffered for a second pass, the bytes of the buffer are counted until the final output is
+    /// written.
+    pub bytes_written: u64,
+}
+
+impl Display for MergeProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} nodes, {}/{} relations, {}/{} references, {} bytes written",
+            self.nodes_created,
+            self.num_nodes,
+            self.relations_created,
+            self.num_relations,
+            self.references_loaded,
+            self.num_references,
+            self.bytes_written
+        )
+    }
+}
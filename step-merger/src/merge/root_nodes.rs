@@ -6,6 +6,60 @@ use crate::step::StepEntry;
 
 use super::utils::NodeStepIds;
 
+/// The keywords of the entities evaluated by `FindRootNodes`. Each role can be fulfilled by
+/// several entity types, e.g., to support files of tools using alternative occurrence entities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootNodeKeywords {
+    /// The keywords of the entities linking a product definition shape and a shape
+    /// representation, i.e., SHAPE_DEFINITION_REPRESENTATION by default.
+    pub shape_definition_representation: Vec<String>,
+
+    /// The keywords of the entities linking a product definition to its shape, i.e.,
+    /// PRODUCT_DEFINITION_SHAPE by default.
+    pub product_definition_shape: Vec<String>,
+
+    /// The keywords of the entities marking a product definition as child of an assembly, i.e.,
+    /// NEXT_ASSEMBLY_USAGE_OCCURRENCE by default.
+    pub assembly_occurrence: Vec<String>,
+}
+
+impl Default for RootNodeKeywords {
+    fn default() -> Self {
+        RootNodeKeywords {
+            shape_definition_representation: vec!["SHAPE_DEFINITION_REPRESENTATION".to_owned()],
+            product_definition_shape: vec!["PRODUCT_DEFINITION_SHAPE".to_owned()],
+            assembly_occurrence: vec!["NEXT_ASSEMBLY_USAGE_OCCURRENCE".to_owned()],
+        }
+    }
+}
+
+/// The role of an entity for finding the root nodes.
+enum EntityRole {
+    ShapeDefinitionRepresentation,
+    ProductDefinitionShape,
+    AssemblyOccurrence,
+}
+
+impl RootNodeKeywords {
+    /// Returns the role of the entity with the given keyword or None if it is not relevant.
+    ///
+    /// # Arguments
+    /// * `keyword` - The keyword of the entity.
+    fn role(&self, keyword: &str) -> Option<EntityRole> {
+        let contains = |keywords: &[String]| keywords.iter().any(|k| k == keyword);
+
+        if contains(&self.shape_definition_representation) {
+            Some(EntityRole::ShapeDefinitionRepresentation)
+        } else if contains(&self.product_definition_shape) {
+            Some(EntityRole::ProductDefinitionShape)
+        } else if contains(&self.assembly_occurrence) {
+            Some(EntityRole::AssemblyOccurrence)
+        } else {
+            None
+        }
+    }
+}
+
 /// The structure to find the root nodes in the step data entries.
 /// We have the following entities:
 /// * PRODUCT_DEFINITION
@@ -25,6 +79,7 @@ use super::utils::NodeStepIds;
 /// We then have to return the SHAPE_REPRESENTATION and PRODUCT_DEFINITION_SHAPE ids.
 #[derive(Default)]
 pub struct FindRootNodes {
+    keywords: RootNodeKeywords,
     shape_def_rep_to_shape_rep: HashMap<u64, u64>,
     prod_def_shape_to_shape_def_rep: HashMap<u64, u64>,
     prod_def_to_prod_def_shape: Vec<(u64, u64)>,
@@ -32,9 +87,15 @@ pub struct FindRootNodes {
 }
 
 impl FindRootNodes {
-    /// Creates a new instance.
-    pub fn new() -> Self {
-        FindRootNodes::default()
+    /// Creates a new instance evaluating the entities with the given keywords.
+    ///
+    /// # Arguments
+    /// * `keywords` - The keywords of the evaluated entities.
+    pub fn with_keywords(keywords: RootNodeKeywords) -> Self {
+        FindRootNodes {
+            keywords,
+            ..Default::default()
+        }
     }
 
     /// Adds the given entry to the internal data structure.
@@ -42,10 +103,23 @@ impl FindRootNodes {
     /// # Arguments
     /// * `entry` - The entry to be added.
     pub fn add_entry(&mut self, entry: &StepEntry) {
-        let keyword = Self::extract_keyword(entry.get_definition());
+        // the entities of interest may also be part of a complex instance
+        if let Some(partial_entries) = entry.partial_entries() {
+            for partial_entry in partial_entries.iter() {
+                self.add_simple_entry(partial_entry);
+            }
+        } else {
+            self.add_simple_entry(entry);
+        }
+    }
 
-        match keyword {
-            "SHAPE_DEFINITION_REPRESENTATION" => {
+    /// Adds the given entry, which is not a complex instance, to the internal data structure.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to be added.
+    fn add_simple_entry(&mut self, entry: &StepEntry) {
+        match self.keywords.role(entry.keyword()) {
+            Some(EntityRole::ShapeDefinitionRepresentation) => {
                 let shape_def_rep_id = entry.get_id();
                 let references = entry.get_references();
                 if references.len() != 2 {
@@ -71,7 +145,7 @@ impl FindRootNodes {
                 self.prod_def_shape_to_shape_def_rep
                     .insert(prod_def_shape_id, shape_def_rep_id);
             }
-            "PRODUCT_DEFINITION_SHAPE" => {
+            Some(EntityRole::ProductDefinitionShape) => {
                 let prod_def_shape_id = entry.get_id();
                 let references = entry.get_references();
                 if references.is_empty() {
@@ -93,7 +167,7 @@ impl FindRootNodes {
                 self.prod_def_to_prod_def_shape
                     .push((*prod_def_id, prod_def_shape_id));
             }
-            "NEXT_ASSEMBLY_USAGE_OCCURRENCE" => {
+            Some(EntityRole::AssemblyOccurrence) => {
                 let references = entry.get_references();
                 if references.len() != 2 {
                     error!(
@@ -112,7 +186,7 @@ impl FindRootNodes {
                 );
                 self.prod_def_assembly_occurrences.insert(prod_def_id);
             }
-            _ => {}
+            None => {}
         }
     }
 
@@ -151,20 +225,6 @@ impl FindRootNodes {
 
         result
     }
-
-    /// Extracts the keyword from the given definition
-    ///
-    /// # Arguments
-    /// * `definition` - The definition to extract the keyword from.
-    fn extract_keyword(definition: &str) -> &str {
-        let definition = definition.trim();
-
-        // find first character that does not belong to the keyword characters
-        let keyword_end = definition
-            .find(|c: char| !c.is_ascii_uppercase() && c != '_')
-            .unwrap_or(definition.len());
-        &definition[..keyword_end]
-    }
 }
 
 #[cfg(test)]
@@ -175,26 +235,13 @@ mod test {
 
     use super::*;
 
-    #[test]
-    fn test_extract_keyword() {
-        let s = "PRODUCT_DEFINITION_SHAPE('',#,#);";
-        let keyword = FindRootNodes::extract_keyword(s);
-
-        assert_eq!(keyword, "PRODUCT_DEFINITION_SHAPE");
-
-        let s = "  FOOBAR_BLUB( );  ";
-        let keyword = FindRootNodes::extract_keyword(s);
-
-        assert_eq!(keyword, "FOOBAR_BLUB");
-    }
-
     #[test]
     fn test_find_root_nodes1() {
         let source = include_str!("../../../test_data/minimal-structure.stp");
         let step_data = StepData::from_str(source).unwrap();
 
         let entries = step_data.get_entries();
-        let mut find_root_nodes = FindRootNodes::new();
+        let mut find_root_nodes = FindRootNodes::default();
         for entry in entries.iter() {
             find_root_nodes.add_entry(entry);
         }
@@ -216,7 +263,7 @@ mod test {
         let step_data = StepData::from_str(source).unwrap();
 
         let entries = step_data.get_entries();
-        let mut find_root_nodes = FindRootNodes::new();
+        let mut find_root_nodes = FindRootNodes::default();
         for entry in entries.iter() {
             find_root_nodes.add_entry(entry);
         }
@@ -228,4 +275,63 @@ mod test {
         assert_eq!(root_nodes[0].product_definition_id, 14);
         assert_eq!(root_nodes[0].shape_representation_id, 31);
     }
+
+    #[test]
+    fn test_find_root_nodes_complex_instances() {
+        let source = "ISO-10303-21; DATA;
+            #1=PRODUCT_DEFINITION('','',#10,#11);
+            #2=(PRODUCT_DEFINITION_SHAPE('',$,#1)FOO());
+            #3=(SHAPE_DEFINITION_REPRESENTATION(#2,#4)BAR('x'));
+            #4=SHAPE_REPRESENTATION('',(#12),#13);
+            ENDSEC;";
+        let step_data = StepData::from_str(source).unwrap();
+
+        let mut find_root_nodes = FindRootNodes::default();
+        for entry in step_data.get_entries().iter() {
+            find_root_nodes.add_entry(entry);
+        }
+
+        let root_nodes = find_root_nodes.get_root_nodes();
+
+        assert_eq!(root_nodes.len(), 1);
+        assert_eq!(root_nodes[0].product_definition_id, 1);
+        assert_eq!(root_nodes[0].shape_representation_id, 4);
+    }
+
+    #[test]
+    fn test_find_root_nodes_alternative_occurrence() {
+        let source = "ISO-10303-21; DATA;
+            #1=PRODUCT_DEFINITION('','',#10,#11);
+            #2=PRODUCT_DEFINITION_SHAPE('',$,#1);
+            #3=SHAPE_DEFINITION_REPRESENTATION(#2,#4);
+            #4=SHAPE_REPRESENTATION('',(#12),#13);
+            #5=PRODUCT_DEFINITION('','',#10,#11);
+            #6=PRODUCT_DEFINITION_SHAPE('',$,#5);
+            #7=SHAPE_DEFINITION_REPRESENTATION(#6,#8);
+            #8=SHAPE_REPRESENTATION('',(#12),#13);
+            #9=ASSEMBLY_COMPONENT_USAGE('','','',#1,#5,'');
+            ENDSEC;";
+        let step_data = StepData::from_str(source).unwrap();
+
+        let find = |find_root_nodes: &mut FindRootNodes| {
+            for entry in step_data.get_entries().iter() {
+                find_root_nodes.add_entry(entry);
+            }
+
+            find_root_nodes
+                .get_root_nodes()
+                .iter()
+                .map(|n| n.product_definition_id)
+                .collect::<Vec<_>>()
+        };
+
+        // the alternative occurrence is ignored by default
+        assert_eq!(find(&mut FindRootNodes::default()), vec![1, 5]);
+
+        let mut keywords = RootNodeKeywords::default();
+        keywords
+            .assembly_occurrence
+            .push("ASSEMBLY_COMPONENT_USAGE".to_owned());
+        assert_eq!(find(&mut FindRootNodes::with_keywords(keywords)), vec![1]);
+    }
 }
@@ -1,38 +1,81 @@
-use log::{debug, error, trace};
+use std::collections::HashMap;
 
-use crate::step::StepEntry;
+use log::error;
+
+use crate::{step::StepEntry, Error, Result};
 
 /// The ids being generated for a node while creating the step data.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeStepIds {
+    /// The id of the PRODUCT_DEFINITION entry.
     pub product_definition_id: u64,
+
+    /// The id of the SHAPE_REPRESENTATION entry.
     pub shape_representation_id: u64,
 }
 
-/// Extracts all ids for the items defined in MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION entry.
-///
-/// # Arguments
-/// * `entry` - The MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION entry.
-/// * `ids` - The list of ids where the extracted ids are added.
-pub fn get_ids_from_mechanical_part(entry: &StepEntry, ids: &mut Vec<u64>) {
-    let mut entry_ids = entry.get_references();
-    if entry_ids.is_empty() {
-        error!(
-            "No references found in MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION entry"
-        );
-        return;
-    } else {
-        entry_ids.pop();
+/// The kinds of entries that can be referenced by an AXIS2_PLACEMENT_3D entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlacementItem {
+    CartesianPoint,
+    Direction,
+}
+
+/// Self-check for the generated AXIS2_PLACEMENT_3D entries. Verifies that each placement
+/// references a CARTESIAN_POINT followed by two DIRECTION entries, which catches wrongly computed
+/// id offsets.
+#[derive(Default)]
+pub struct PlacementValidator {
+    items: HashMap<u64, PlacementItem>,
+}
+
+impl PlacementValidator {
+    /// Creates a new empty validator.
+    pub fn new() -> Self {
+        PlacementValidator::default()
     }
 
-    debug!(
-        "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION got id {} and has {} ids",
-        entry.get_id(),
-        entry_ids.len()
-    );
-    trace!("ids: {:?}", entry_ids);
+    /// Checks the given entry and records it if it can be referenced by a placement.
+    /// Returns an error if the entry is an AXIS2_PLACEMENT_3D with unexpected references.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to be checked.
+    pub fn check_entry(&mut self, entry: &StepEntry) -> Result<()> {
+        match entry.keyword() {
+            "CARTESIAN_POINT" => {
+                self.items
+                    .insert(entry.get_id(), PlacementItem::CartesianPoint);
+            }
+            "DIRECTION" => {
+                self.items.insert(entry.get_id(), PlacementItem::Direction);
+            }
+            "AXIS2_PLACEMENT_3D" => {
+                let references = entry.get_references();
+                let expected = [
+                    PlacementItem::CartesianPoint,
+                    PlacementItem::Direction,
+                    PlacementItem::Direction,
+                ];
+
+                if references.len() != expected.len()
+                    || references
+                        .iter()
+                        .zip(expected.iter())
+                        .any(|(id, item)| self.items.get(id) != Some(item))
+                {
+                    error!(
+                        "AXIS2_PLACEMENT_3D {} has invalid references {:?}",
+                        entry.get_id(),
+                        references
+                    );
+                    return Err(Error::InvalidPlacement(entry.get_id()));
+                }
+            }
+            _ => {}
+        }
 
-    ids.append(&mut entry_ids);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -40,13 +83,28 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_parse_mechanical_design_entry() {
-        let s = "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION('',(#24),#187);";
-        let entry = StepEntry::new(1, s);
-        let mut ids = Vec::new();
-
-        get_ids_from_mechanical_part(&entry, &mut ids);
+    fn test_placement_validator() {
+        let mut validator = PlacementValidator::new();
+        validator
+            .check_entry(&StepEntry::new(1, "CARTESIAN_POINT('',(0.,0.,0.))"))
+            .unwrap();
+        validator
+            .check_entry(&StepEntry::new(2, "DIRECTION('',(0.,0.,1.))"))
+            .unwrap();
+        validator
+            .check_entry(&StepEntry::new(3, "DIRECTION('',(1.,0.,0.))"))
+            .unwrap();
 
-        assert_eq!(ids, vec![24]);
+        validator
+            .check_entry(&StepEntry::new(4, "AXIS2_PLACEMENT_3D('',#1,#2,#3)"))
+            .unwrap();
+        assert!(matches!(
+            validator.check_entry(&StepEntry::new(5, "AXIS2_PLACEMENT_3D('',#2,#1,#3)")),
+            Err(Error::InvalidPlacement(5))
+        ));
+        assert!(matches!(
+            validator.check_entry(&StepEntry::new(6, "AXIS2_PLACEMENT_3D('',#1,#2,#4)")),
+            Err(Error::InvalidPlacement(6))
+        ));
     }
 }
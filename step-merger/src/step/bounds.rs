@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use super::StepEntry;
+
+/// The axis-aligned bounding box of the CARTESIAN_POINT entries of a STEP file, e.g., to quickly
+/// check the size and position of a model. The points are taken as they are, i.e., without
+/// applying the placements or the length unit of their representations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointBounds {
+    /// The minimum coordinates of the points.
+    pub min: [f64; 3],
+
+    /// The maximum coordinates of the points.
+    pub max: [f64; 3],
+
+    /// The number of points within the bounds.
+    pub num_points: usize,
+}
+
+impl Default for PointBounds {
+    fn default() -> Self {
+        PointBounds {
+            min: [f64::INFINITY; 3],
+            max: [f64::NEG_INFINITY; 3],
+            num_points: 0,
+        }
+    }
+}
+
+impl PointBounds {
+    /// Computes the bounds of the CARTESIAN_POINT entries in the given entries, e.g., the entries
+    /// streamed by a `STEPReader`. Returns None if there are no three-dimensional points.
+    ///
+    /// # Arguments
+    /// * `entries` - The entries to scan for points.
+    pub fn from_entries<'a, I>(entries: I) -> Option<PointBounds>
+    where
+        I: IntoIterator<Item = &'a StepEntry>,
+    {
+        let mut bounds = PointBounds::default();
+        for entry in entries {
+            bounds.add_entry(entry);
+        }
+
+        bounds.is_valid().then_some(bounds)
+    }
+
+    /// Extends the bounds by the given entry if it is a three-dimensional CARTESIAN_POINT.
+    /// Returns true if the entry has been added.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to be added.
+    pub fn add_entry(&mut self, entry: &StepEntry) -> bool {
+        if entry.keyword() != "CARTESIAN_POINT" {
+            return false;
+        }
+
+        let coordinates = entry.get_numbers();
+        if coordinates.len() != 3 {
+            return false;
+        }
+
+        for (axis, coordinate) in coordinates.into_iter().enumerate() {
+            self.min[axis] = self.min[axis].min(coordinate);
+            self.max[axis] = self.max[axis].max(coordinate);
+        }
+
+        self.num_points += 1;
+
+        true
+    }
+
+    /// Returns true if the bounds contain at least one point.
+    pub fn is_valid(&self) -> bool {
+        self.num_points > 0
+    }
+
+    /// Returns the center of the bounds.
+    pub fn center(&self) -> [f64; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) / 2.0)
+    }
+
+    /// Returns the extent of the bounds along each axis.
+    pub fn size(&self) -> [f64; 3] {
+        [0, 1, 2].map(|axis| self.max[axis] - self.min[axis])
+    }
+}
+
+impl Display for PointBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} points within {:?} - {:?}, size={:?}, center={:?}",
+            self.num_points,
+            self.min,
+            self.max,
+            self.size(),
+            self.center()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point_bounds() {
+        let entries = [
+            StepEntry::new(1, "CARTESIAN_POINT('',(1.E-3,-2.,0.5))"),
+            StepEntry::new(2, "CARTESIAN_POINT('#3',(-4.,2.5E+1,0.))"),
+            StepEntry::new(3, "CARTESIAN_POINT('',(7.,8.))"),
+            StepEntry::new(4, "DIRECTION('',(100.,0.,0.))"),
+        ];
+
+        let bounds = PointBounds::from_entries(entries.iter()).unwrap();
+        assert_eq!(bounds.num_points, 2);
+        assert_eq!(bounds.min, [-4.0, -2.0, 0.0]);
+        assert_eq!(bounds.max, [1e-3, 25.0, 0.5]);
+        assert_eq!(bounds.size(), [4.001, 27.0, 0.5]);
+        assert_eq!(bounds.center(), [-1.9995, 11.5, 0.25]);
+
+        assert!(PointBounds::from_entries(entries[2..].iter()).is_none());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{Error, Result};
+
+/// Encodes the given string as content of a STEP string literal. Quotes and backslashes are
+/// escaped and all non-ASCII characters are encoded with the `\X2\...\X0\` control directive or
+/// with `\X4\...\X0\` for characters outside the basic multilingual plane.
+///
+/// # Arguments
+/// * `s` - The string to encode.
+pub fn encode_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    // the currently open control directive, i.e., 2 for \X2\ and 4 for \X4\
+    let mut directive = 0;
+
+    for c in s.chars() {
+        let required_directive = if c.is_ascii() {
+            0
+        } else if c.len_utf16() == 1 {
+            2
+        } else {
+            4
+        };
+
+        if required_directive != directive {
+            if directive != 0 {
+                result.push_str("\\X0\\");
+            }
+
+            if required_directive != 0 {
+                result.push_str(&format!("\\X{}\\", required_directive));
+            }
+
+            directive = required_directive;
+        }
+
+        match c {
+            '\'' => result.push_str("''"),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_ascii() => result.push(c),
+            c if directive == 2 => result.push_str(&format!("{:04X}", c as u32)),
+            c => result.push_str(&format!("{:08X}", c as u32)),
+        }
+    }
+
+    if directive != 0 {
+        result.push_str("\\X0\\");
+    }
+
+    result
+}
+
+/// Decodes the content of a STEP string literal, i.e., the inverse of `encode_string`. Besides
+/// the `\X2\` and `\X4\` control directives, the single character encodings `\X\hh` and `\S\c`
+/// of ISO 8859-1 are supported.
+///
+/// # Arguments
+/// * `s` - The encoded content of the string literal.
+pub fn decode_string(s: &str) -> Result<String> {
+    let invalid = || Error::InvalidStringEncoding(s.to_owned());
+
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("''") {
+            result.push('\'');
+            rest = &rest[2..];
+        } else if rest.starts_with("\\\\") {
+            result.push('\\');
+            rest = &rest[2..];
+        } else if let Some(directive) = rest
+            .strip_prefix("\\X2\\")
+            .map(|r| (r, 4))
+            .or_else(|| rest.strip_prefix("\\X4\\").map(|r| (r, 8)))
+        {
+            let (encoded, num_digits) = directive;
+            let end = encoded.find("\\X0\\").ok_or_else(invalid)?;
+            let hex = &encoded[..end];
+            if hex.is_empty() || hex.len() % num_digits != 0 || !hex.is_ascii() {
+                return Err(invalid());
+            }
+
+            for i in (0..hex.len()).step_by(num_digits) {
+                let code =
+                    u32::from_str_radix(&hex[i..i + num_digits], 16).map_err(|_| invalid())?;
+                result.push(char::from_u32(code).ok_or_else(invalid)?);
+            }
+
+            rest = &encoded[end + 4..];
+        } else if let Some(encoded) = rest.strip_prefix("\\X\\") {
+            // a single ISO 8859-1 character given by two hex digits
+            let hex = encoded
+                .get(..2)
+                .filter(|h| h.is_ascii())
+                .ok_or_else(invalid)?;
+            let code = u8::from_str_radix(hex, 16).map_err(|_| invalid())?;
+            result.push(code as char);
+            rest = &encoded[2..];
+        } else if let Some(encoded) = rest.strip_prefix("\\S\\") {
+            // a single ISO 8859-1 character given by its code minus 128
+            let c = encoded
+                .chars()
+                .next()
+                .filter(char::is_ascii)
+                .ok_or_else(invalid)?;
+            result.push((c as u8 + 128) as char);
+            rest = &encoded[1..];
+        } else {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_string() {
+        assert_eq!(encode_string("Cube"), "Cube");
+        assert_eq!(encode_string("It's"), "It''s");
+        assert_eq!(encode_string("a\\b"), "a\\\\b");
+        assert_eq!(encode_string("Über"), "\\X2\\00DC\\X0\\ber");
+        assert_eq!(encode_string("Grüße"), "Gr\\X2\\00FC00DF\\X0\\e");
+        assert_eq!(encode_string("🙂"), "\\X4\\0001F642\\X0\\");
+    }
+
+    #[test]
+    fn test_decode_string() {
+        for s in ["Cube", "It's", "a\\b", "Über", "Grüße", "x🙂ÿ", ""] {
+            assert_eq!(decode_string(&encode_string(s)).unwrap(), s);
+        }
+
+        assert_eq!(decode_string("Caf\\X\\E9").unwrap(), "Café");
+        assert_eq!(decode_string("Caf\\S\\i").unwrap(), "Café");
+        assert!(decode_string("\\X\\E").is_err());
+        assert!(decode_string("\\X\\ZZ").is_err());
+        assert!(decode_string("\\X2\\00D\\X0\\").is_err());
+        assert!(decode_string("\\X2\\00DC").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use super::StepValue;
+
+/// A builder for the definition of an entity instance, e.g., `PRODUCT('A','A','',(#2))`. The
+/// parameters are serialized in the syntax of the STEP exchange structure, i.e., strings are
+/// encoded and reals always contain a decimal point.
+#[derive(Debug, Clone)]
+pub struct EntryBuilder {
+    keyword: String,
+    parameters: Vec<StepValue>,
+}
+
+impl EntryBuilder {
+    /// Creates a new builder for an entity instance without any parameters.
+    ///
+    /// # Arguments
+    /// * `keyword` - The keyword of the entity, e.g., `PRODUCT`.
+    pub fn new(keyword: &str) -> Self {
+        EntryBuilder {
+            keyword: keyword.to_owned(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Appends the given parameter.
+    ///
+    /// # Arguments
+    /// * `value` - The value of the parameter.
+    pub fn param(&mut self, value: StepValue) -> &mut Self {
+        self.parameters.push(value);
+        self
+    }
+
+    /// Appends a string parameter, which is encoded when building the definition.
+    ///
+    /// # Arguments
+    /// * `value` - The unencoded string.
+    pub fn string(&mut self, value: &str) -> &mut Self {
+        self.param(StepValue::String(value.to_owned()))
+    }
+
+    /// Appends a reference to the entry with the given id.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the referenced entry.
+    pub fn reference(&mut self, id: u64) -> &mut Self {
+        self.param(StepValue::Reference(id))
+    }
+
+    /// Appends a list of references to the entries with the given ids.
+    ///
+    /// # Arguments
+    /// * `ids` - The ids of the referenced entries.
+    pub fn references(&mut self, ids: &[u64]) -> &mut Self {
+        self.param(StepValue::List(
+            ids.iter().map(|id| StepValue::Reference(*id)).collect(),
+        ))
+    }
+
+    /// Appends a real parameter. The value must be finite.
+    ///
+    /// # Arguments
+    /// * `value` - The value of the parameter.
+    pub fn real(&mut self, value: f64) -> &mut Self {
+        self.param(StepValue::Real(value))
+    }
+
+    /// Appends an enumeration parameter, e.g., `T` for `.T.`.
+    ///
+    /// # Arguments
+    /// * `value` - The enumeration value without the enclosing dots.
+    pub fn enumeration(&mut self, value: &str) -> &mut Self {
+        self.param(StepValue::Enum(value.to_owned()))
+    }
+
+    /// Appends an unset optional parameter, i.e., `$`.
+    pub fn unset(&mut self) -> &mut Self {
+        self.param(StepValue::Unset)
+    }
+
+    /// Returns the serialized definition of the entity instance.
+    pub fn build(&self) -> String {
+        format!(
+            "{}{}",
+            self.keyword,
+            StepValue::List(self.parameters.clone())
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_entry_builder() {
+        assert_eq!(
+            EntryBuilder::new("PRODUCT")
+                .string("It's")
+                .string("Über")
+                .string("")
+                .references(&[2])
+                .build(),
+            "PRODUCT('It''s','\\X2\\00DC\\X0\\ber','',(#2))"
+        );
+
+        assert_eq!(
+            EntryBuilder::new("AXIS2_PLACEMENT_3D")
+                .string("")
+                .reference(1)
+                .reference(2)
+                .reference(3)
+                .build(),
+            "AXIS2_PLACEMENT_3D('',#1,#2,#3)"
+        );
+
+        assert_eq!(
+            EntryBuilder::new("CARTESIAN_POINT")
+                .string("")
+                .param(StepValue::List(vec![
+                    StepValue::Real(0.0),
+                    StepValue::Real(-1.5),
+                    StepValue::Real(100.0),
+                ]))
+                .build(),
+            "CARTESIAN_POINT('',(0.,-1.5,100.))"
+        );
+
+        assert_eq!(
+            EntryBuilder::new("UNCERTAINTY_MEASURE_WITH_UNIT")
+                .param(StepValue::Typed(
+                    "LENGTH_MEASURE".to_owned(),
+                    Box::new(StepValue::Real(0.001))
+                ))
+                .reference(7)
+                .string("distance_accuracy_value")
+                .unset()
+                .build(),
+            "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(0.001),#7,'distance_accuracy_value',$)"
+        );
+
+        assert_eq!(
+            EntryBuilder::new("SI_UNIT")
+                .param(StepValue::Derived)
+                .unset()
+                .enumeration("METRE")
+                .build(),
+            "SI_UNIT(*,$,.METRE.)"
+        );
+
+        assert_eq!(EntryBuilder::new("FOO").build(), "FOO()");
+    }
+}
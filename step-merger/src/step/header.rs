@@ -0,0 +1,316 @@
+use std::fmt::Display;
+
+use log::warn;
+
+use super::{extract_keyword, StepEntry};
+
+/// The prefixes of the schema names of the supported application protocols.
+const KNOWN_SCHEMA_PREFIXES: &[&str] = &[
+    "AP2",
+    "AUTOMOTIVE_DESIGN",
+    "CONFIG_CONTROL_DESIGN",
+    "CONFIGURATION_CONTROL_3D_DESIGN",
+];
+
+/// A problem with the schema strings of the FILE_SCHEMA header entity, which may indicate that
+/// the file is not compatible with the merged output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDiagnostic {
+    /// The header does not declare any schema.
+    Missing,
+
+    /// The schema name does not start with the name of a known application protocol.
+    UnknownSchema(String),
+
+    /// The schema string has no well-formed object identifier block, e.g., `{ 1 0 10303 214 }`.
+    MissingObjectIdentifier(String),
+}
+
+impl Display for SchemaDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaDiagnostic::Missing => write!(f, "No schema declared in FILE_SCHEMA"),
+            SchemaDiagnostic::UnknownSchema(schema) => write!(f, "Unknown schema '{}'", schema),
+            SchemaDiagnostic::MissingObjectIdentifier(schema) => {
+                write!(f, "Schema '{}' has no object identifier", schema)
+            }
+        }
+    }
+}
+
+/// The content of the HEADER section of a STEP file. The strings are decoded, i.e., they contain
+/// the actual characters instead of their STEP encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StepHeader {
+    /// The description strings of FILE_DESCRIPTION.
+    pub description: Vec<String>,
+
+    /// The implementation level of FILE_DESCRIPTION, e.g., `2;1`.
+    pub implementation_level: String,
+
+    /// The file name of FILE_NAME.
+    pub name: String,
+
+    /// The time stamp of FILE_NAME.
+    pub time_stamp: String,
+
+    /// The authors of FILE_NAME.
+    pub author: Vec<String>,
+
+    /// The organizations of FILE_NAME.
+    pub organization: Vec<String>,
+
+    /// The preprocessor version of FILE_NAME.
+    pub preprocessor_version: String,
+
+    /// The originating system of FILE_NAME.
+    pub originating_system: String,
+
+    /// The authorization of FILE_NAME.
+    pub authorization: String,
+
+    /// The schema identifiers of FILE_SCHEMA.
+    pub schemas: Vec<String>,
+
+    /// The text of the comments preceding the `ISO-10303-21;` line, excluding the enclosing
+    /// `/*` and `*/`.
+    pub leading_comments: Vec<String>,
+}
+
+impl StepHeader {
+    /// Creates the header from the given definitions of the header entities, e.g.,
+    /// `FILE_NAME('a.stp','2024-05-08',(''),(''),'','','')`. Unknown entities are ignored.
+    ///
+    /// # Arguments
+    /// * `definitions` - The definitions of the header entities.
+    pub fn from_definitions<S: AsRef<str>>(definitions: &[S]) -> StepHeader {
+        let mut header = StepHeader::default();
+
+        for definition in definitions.iter() {
+            let definition = definition.as_ref().trim();
+            let parameters = split_parameters(definition);
+            let list = |index: usize| parameters.get(index).map_or(Vec::new(), |p| strings(p));
+            let single = |index: usize| list(index).into_iter().next().unwrap_or_default();
+
+            match extract_keyword(definition) {
+                "FILE_DESCRIPTION" => {
+                    header.description = list(0);
+                    header.implementation_level = single(1);
+                }
+                "FILE_NAME" => {
+                    header.name = single(0);
+                    header.time_stamp = single(1);
+                    header.author = list(2);
+                    header.organization = list(3);
+                    header.preprocessor_version = single(4);
+                    header.originating_system = single(5);
+                    header.authorization = single(6);
+                }
+                "FILE_SCHEMA" => {
+                    // the schemas of duplicate entities are combined instead of being dropped
+                    if !header.schemas.is_empty() {
+                        warn!("Duplicate FILE_SCHEMA entity in header");
+                    }
+
+                    header.schemas.extend(list(0));
+                }
+                _ => {}
+            }
+        }
+
+        header
+    }
+
+    /// Checks the schema strings of FILE_SCHEMA and returns the detected problems. Each schema
+    /// is expected to consist of the name of an application protocol followed by its object
+    /// identifier, e.g., `AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }`.
+    pub fn schema_diagnostics(&self) -> Vec<SchemaDiagnostic> {
+        if self.schemas.iter().all(|s| s.trim().is_empty()) {
+            return vec![SchemaDiagnostic::Missing];
+        }
+
+        let mut result = Vec::new();
+        for schema in self.schemas.iter().filter(|s| !s.trim().is_empty()) {
+            let (name, identifier) = match schema.split_once('{') {
+                Some((name, identifier)) => (name.trim(), Some(identifier)),
+                None => (schema.trim(), None),
+            };
+
+            let name = name.to_ascii_uppercase();
+            if !KNOWN_SCHEMA_PREFIXES.iter().any(|p| name.starts_with(p)) {
+                result.push(SchemaDiagnostic::UnknownSchema(schema.clone()));
+            }
+
+            if !identifier.is_some_and(is_object_identifier) {
+                result.push(SchemaDiagnostic::MissingObjectIdentifier(schema.clone()));
+            }
+        }
+
+        result
+    }
+}
+
+/// Checks if the given text following the opening brace is an object identifier, i.e.,
+/// a non-empty list of numbers or named numbers such as `iso(1)`, terminated by a closing brace.
+///
+/// # Arguments
+/// * `text` - The text following the opening brace.
+fn is_object_identifier(text: &str) -> bool {
+    let Some(components) = text.trim_end().strip_suffix('}') else {
+        return false;
+    };
+
+    let mut components = components.split_whitespace().peekable();
+    components.peek().is_some()
+        && components.all(|c| {
+            c.chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '(' || ch == ')' || ch == '_')
+        })
+}
+
+/// Returns the decoded string literals of the given parameter. Falls back to the unescaped
+/// string literals if they are not properly encoded.
+///
+/// # Arguments
+/// * `parameter` - The parameter, e.g., a string or a list of strings.
+fn strings(parameter: &str) -> Vec<String> {
+    let entry = StepEntry::new(0, parameter);
+    entry
+        .decoded_strings()
+        .unwrap_or_else(|_| entry.get_strings())
+}
+
+/// Splits the top-level parameters of the given entity definition, e.g., `FOO('a',(1,2),$)`
+/// results in `'a'`, `(1,2)` and `$`.
+///
+/// # Arguments
+/// * `definition` - The entity definition.
+fn split_parameters(definition: &str) -> Vec<&str> {
+    let start = match definition.find('(') {
+        Some(start) => start + 1,
+        None => return Vec::new(),
+    };
+
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut inside_string = false;
+    let mut parameter_start = start;
+
+    for (i, c) in definition.char_indices().skip_while(|(i, _)| *i < start) {
+        if inside_string {
+            // escaped quotes are handled as two consecutive strings
+            if c == '\'' {
+                inside_string = false;
+            }
+
+            continue;
+        }
+
+        match c {
+            '\'' => inside_string = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                result.push(definition[parameter_start..i].trim());
+                break;
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(definition[parameter_start..i].trim());
+                parameter_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_parameters() {
+        assert_eq!(
+            split_parameters("FOO('a,b',(1,2), $ ,'it''s')"),
+            vec!["'a,b'", "(1,2)", "$", "'it''s'"]
+        );
+        assert!(split_parameters("FOO").is_empty());
+    }
+
+    #[test]
+    fn test_from_definitions() {
+        let header = StepHeader::from_definitions(&[
+            "FILE_DESCRIPTION(('A cube','with sphere'),'2;1')",
+            "FILE_NAME('cube.stp','2024-05-08T18:23:30',('Jane','John'),('ACME'),'pre','sys','')",
+            "FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'))",
+        ]);
+
+        assert_eq!(header.description, vec!["A cube", "with sphere"]);
+        assert_eq!(header.implementation_level, "2;1");
+        assert_eq!(header.name, "cube.stp");
+        assert_eq!(header.time_stamp, "2024-05-08T18:23:30");
+        assert_eq!(header.author, vec!["Jane", "John"]);
+        assert_eq!(header.organization, vec!["ACME"]);
+        assert_eq!(header.preprocessor_version, "pre");
+        assert_eq!(header.originating_system, "sys");
+        assert_eq!(header.authorization, "");
+        assert_eq!(
+            header.schemas,
+            vec!["AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }"]
+        );
+        assert!(header.schema_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_schema_diagnostics() {
+        let diagnostics =
+            |schema: &str| StepHeader::from_definitions(&[schema.to_owned()]).schema_diagnostics();
+
+        assert!(
+            diagnostics("FILE_SCHEMA(('CONFIG_CONTROL_DESIGN {1 0 10303 203 1 1}'))").is_empty()
+        );
+        assert_eq!(
+            diagnostics("FILE_SCHEMA(())"),
+            vec![SchemaDiagnostic::Missing]
+        );
+        assert_eq!(
+            diagnostics("FILE_SCHEMA((''))"),
+            vec![SchemaDiagnostic::Missing]
+        );
+        assert_eq!(
+            diagnostics("FILE_SCHEMA(('AP242_MANAGED_MODEL_BASED_3D_ENGINEERING'))"),
+            vec![SchemaDiagnostic::MissingObjectIdentifier(
+                "AP242_MANAGED_MODEL_BASED_3D_ENGINEERING".to_owned()
+            )]
+        );
+        assert_eq!(
+            diagnostics("FILE_SCHEMA(('FOO { 1 0 10303 }'))"),
+            vec![SchemaDiagnostic::UnknownSchema(
+                "FOO { 1 0 10303 }".to_owned()
+            )]
+        );
+        assert_eq!(
+            diagnostics("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214'))"),
+            vec![SchemaDiagnostic::MissingObjectIdentifier(
+                "AUTOMOTIVE_DESIGN { 1 0 10303 214".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_file_schema() {
+        let header = StepHeader::from_definitions(&[
+            "FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'))",
+            "FILE_SCHEMA(('CONFIG_CONTROL_DESIGN'))",
+        ]);
+
+        assert_eq!(header.schemas.len(), 2);
+        assert_eq!(
+            header.schema_diagnostics(),
+            vec![SchemaDiagnostic::MissingObjectIdentifier(
+                "CONFIG_CONTROL_DESIGN".to_owned()
+            )]
+        );
+    }
+}
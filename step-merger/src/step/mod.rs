@@ -1,12 +1,57 @@
+mod bounds;
+mod encoding;
+mod entry_builder;
+mod header;
+mod model;
+mod presentation;
+mod protocol;
 mod reader;
+mod units;
+mod value;
 mod writer;
 
-use std::{fs::File, io::Cursor, ops::Range, path::Path, str::FromStr, sync::Arc};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+    fs::File,
+    io::{Cursor, Read},
+    ops::Range,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
 
 use crate::{Error, Result};
 
+pub use bounds::PointBounds;
+pub use encoding::{decode_string, encode_string};
+pub use entry_builder::EntryBuilder;
+pub use header::{SchemaDiagnostic, StepHeader};
+pub use model::StepModel;
+pub(crate) use presentation::PresentationFilter;
+pub use protocol::ApplicationProtocol;
 pub use reader::*;
-pub use writer::StepWriter;
+pub use units::SiPrefix;
+pub(crate) use units::{length_unit, plane_angle_unit, si_length_unit_factor, solid_angle_unit};
+pub use value::StepValue;
+pub use writer::{HeaderInfo, LineEnding, SectionSpacing, StepWriter, WriterFormat};
+
+/// The keywords of the entries that are top-level by design, i.e., they are not referenced by any
+/// other entry but are still required for a meaningful STEP file.
+const ROOT_KEYWORDS: &[&str] = &[
+    "APPLICATION_CONTEXT",
+    "APPLICATION_PROTOCOL_DEFINITION",
+    "APPLIED_ORGANIZATION_ASSIGNMENT",
+    "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION",
+    "DRAUGHTING_MODEL",
+    "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION",
+    "PRESENTATION_LAYER_ASSIGNMENT",
+    "PRODUCT_RELATED_PRODUCT_CATEGORY",
+    "PROPERTY_DEFINITION_REPRESENTATION",
+    "SHAPE_DEFINITION_REPRESENTATION",
+    "SHAPE_REPRESENTATION_RELATIONSHIP",
+    "STYLED_ITEM",
+];
 
 /// A single entry in the STEP file.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -49,11 +94,61 @@ impl StepEntry {
         &self.definition
     }
 
-    /// Updates the references in the step data using the given function.
+    /// Returns the keyword, i.e., the entity type, of the entry, e.g., `PRODUCT_DEFINITION`.
+    /// Returns an empty string for complex instances whose definition starts with `(`, see
+    /// `keywords` for their entity types.
+    pub fn keyword(&self) -> &str {
+        extract_keyword(&self.definition)
+    }
+
+    /// Returns the keywords of all entity types of the entry. For complex instances, e.g.,
+    /// `(NAMED_UNIT(*)SI_UNIT($,.METRE.)LENGTH_UNIT())`, these are the keywords of the partial
+    /// instances. Otherwise, it is only the keyword of the entry.
+    pub fn keywords(&self) -> Vec<&str> {
+        match split_complex_instance(&self.definition) {
+            Some(partials) => partials.into_iter().map(extract_keyword).collect(),
+            None => vec![self.keyword()],
+        }
+    }
+
+    /// Returns the partial instances of a complex instance as entries with the id of this entry.
+    /// Returns None if the entry is not a complex instance.
+    pub(crate) fn partial_entries(&self) -> Option<Vec<StepEntry>> {
+        split_complex_instance(&self.definition).map(|partials| {
+            partials
+                .into_iter()
+                .map(|partial| StepEntry::new(self.id, partial))
+                .collect()
+        })
+    }
+
+    /// Updates the references in the step data using the given function. Invalid references,
+    /// e.g., numbers exceeding the u64 range, are kept unchanged.
     ///
     /// # Arguments
     /// * `f` - The function to update the references. Must be a strictly monotonic function.
     pub fn update_references(&self, f: impl Fn(u64) -> u64) -> Self {
+        // the non-strict update never fails
+        self.update_references_impl(f, false)
+            .unwrap_or_else(|_| self.clone())
+    }
+
+    /// Updates the references in the step data using the given function. Returns an error if the
+    /// definition contains an invalid reference, e.g., a number exceeding the u64 range.
+    ///
+    /// # Arguments
+    /// * `f` - The function to update the references. Must be a strictly monotonic function.
+    pub fn try_update_references(&self, f: impl Fn(u64) -> u64) -> Result<Self> {
+        self.update_references_impl(f, true)
+    }
+
+    /// Updates the references using the given function.
+    ///
+    /// # Arguments
+    /// * `f` - The function to update the references.
+    /// * `strict` - If true, invalid references result in an error. Otherwise, they are kept
+    ///   unchanged.
+    fn update_references_impl(&self, f: impl Fn(u64) -> u64, strict: bool) -> Result<Self> {
         // the new id
         let id = f(self.id);
 
@@ -61,7 +156,8 @@ impl StepEntry {
         let mut new_definition = String::new();
         let mut mode = Mode::Definition;
         let mut buffer = String::new();
-        for c in self.definition.chars() {
+        let mut chars = self.definition.chars().peekable();
+        while let Some(c) = chars.next() {
             match mode {
                 Mode::Definition => {
                     if c == '#' {
@@ -77,11 +173,8 @@ impl StepEntry {
                     if c.is_ascii_digit() {
                         buffer.push(c);
                     } else {
-                        let id = buffer.parse::<u64>().unwrap();
+                        Self::push_updated_reference(&mut new_definition, &buffer, &f, strict)?;
                         buffer.clear();
-                        let new_id = f(id);
-                        new_definition.push('#');
-                        new_definition.push_str(&new_id.to_string());
 
                         if c == '\'' {
                             mode = Mode::String;
@@ -95,67 +188,368 @@ impl StepEntry {
                     }
                 }
                 Mode::String => {
+                    new_definition.push(c);
+
                     if c == '\'' {
-                        mode = Mode::Definition;
+                        // two consecutive quotes are an escaped quote inside the string
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                            new_definition.push('\'');
+                        } else {
+                            mode = Mode::Definition;
+                        }
                     }
-
-                    new_definition.push(c);
                 }
             }
         }
 
-        Self {
+        // flush the reference at the end of the definition
+        if mode == Mode::Reference {
+            Self::push_updated_reference(&mut new_definition, &buffer, &f, strict)?;
+        }
+
+        Ok(Self {
             id,
             definition: new_definition,
+        })
+    }
+
+    /// Pushes the updated reference for the given digits to the definition.
+    ///
+    /// # Arguments
+    /// * `definition` - The definition to push the reference to.
+    /// * `digits` - The digits of the reference without the leading `#`.
+    /// * `f` - The function to update the reference.
+    /// * `strict` - If true, an invalid reference results in an error. Otherwise, it is pushed
+    ///   unchanged.
+    fn push_updated_reference(
+        definition: &mut String,
+        digits: &str,
+        f: impl Fn(u64) -> u64,
+        strict: bool,
+    ) -> Result<()> {
+        definition.push('#');
+        match parse_reference(digits) {
+            Ok(id) => definition.push_str(&f(id).to_string()),
+            Err(err) if strict => return Err(err),
+            Err(_) => definition.push_str(digits),
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the definition is well-formed, i.e., all parentheses outside of strings are
+    /// balanced and all strings are properly terminated. Quotes inside strings must be escaped
+    /// as `''`.
+    pub fn is_well_formed(&self) -> bool {
+        let mut depth = 0usize;
+        let mut inside_string = false;
+        let mut chars = self.definition.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if inside_string {
+                if c == '\'' {
+                    // two consecutive quotes are an escaped quote inside the string
+                    if chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        inside_string = false;
+                    }
+                }
+            } else {
+                match c {
+                    '\'' => inside_string = true,
+                    '(' => depth += 1,
+                    ')' => {
+                        if depth == 0 {
+                            return false;
+                        }
+
+                        depth -= 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        depth == 0 && !inside_string
+    }
+
+    /// Returns the unescaped content of all string literals in the definition in the order they
+    /// appear. Control directives such as `\X2\...\X0\` are kept, see `decoded_strings`.
+    pub fn get_strings(&self) -> Vec<String> {
+        self.string_literals()
+            .into_iter()
+            .map(|s| s.replace("''", "'"))
+            .collect()
+    }
+
+    /// Returns the decoded content of all string literals in the definition in the order they
+    /// appear, i.e., non-ASCII characters encoded as control directives are restored. Returns an
+    /// error if a string literal is not properly encoded.
+    pub fn decoded_strings(&self) -> Result<Vec<String>> {
+        self.string_literals()
+            .into_iter()
+            .map(decode_string)
+            .collect()
+    }
+
+    /// Returns the raw content of all string literals in the definition in the order they
+    /// appear, i.e., without the enclosing quotes but with the escape sequences.
+    fn string_literals(&self) -> Vec<&str> {
+        let mut result = Vec::new();
+        let mut start: Option<usize> = None;
+        let mut chars = self.definition.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            if c != '\'' {
+                continue;
+            }
+
+            match start {
+                // two consecutive quotes are an escaped quote inside the string
+                Some(_) if chars.peek().is_some_and(|(_, c)| *c == '\'') => {
+                    chars.next();
+                }
+                Some(s) => {
+                    result.push(&self.definition[s..pos]);
+                    start = None;
+                }
+                None => start = Some(pos + 1),
+            }
         }
+
+        result
     }
 
-    /// Returns a list of all references in the definition excluding the own id.
+    /// Returns the values of all numeric parameters in the definition in the order they appear,
+    /// e.g., the coordinates of a CARTESIAN_POINT. Numbers inside strings, references and
+    /// enumerations are skipped.
+    pub fn get_numbers(&self) -> Vec<f64> {
+        // all relevant characters are ASCII, thus the bytes can be scanned directly
+        let definition = self.definition.as_bytes();
+        let mut result = Vec::new();
+        let mut inside_string = false;
+        let mut previous = 0u8;
+        let mut pos = 0;
+
+        while pos < definition.len() {
+            let c = definition[pos];
+            if c == b'\'' {
+                // escaped quotes are handled as two consecutive strings
+                inside_string = !inside_string;
+            } else if !inside_string
+                && matches!(previous, b'(' | b',')
+                && matches!(c, b'0'..=b'9' | b'+' | b'-' | b'.')
+            {
+                // a number can only start a parameter, which excludes digits of keywords
+                let start = pos;
+                while definition
+                    .get(pos)
+                    .is_some_and(|b| matches!(b, b'0'..=b'9' | b'+' | b'-' | b'.' | b'E' | b'e'))
+                {
+                    pos += 1;
+                }
+
+                if let Ok(number) = parse_number(&self.definition[start..pos]) {
+                    result.push(number);
+                }
+
+                previous = definition[pos - 1];
+                continue;
+            }
+
+            if !c.is_ascii_whitespace() {
+                previous = c;
+            }
+
+            pos += 1;
+        }
+
+        result
+    }
+
+    /// Returns a list of all references in the definition excluding the own id. Invalid
+    /// references, e.g., numbers exceeding the u64 range, are skipped.
     pub fn get_references(&self) -> Vec<u64> {
-        // update the references in the definition
-        let mut mode = Mode::Definition;
-        let mut buffer = String::new();
+        // the non-strict collection never fails
+        self.get_references_impl(false).unwrap_or_default()
+    }
+
+    /// Returns a list of all references in the definition excluding the own id. Returns an error
+    /// if the definition contains an invalid reference, e.g., a number exceeding the u64 range.
+    pub fn try_get_references(&self) -> Result<Vec<u64>> {
+        self.get_references_impl(true)
+    }
+
+    /// Collects the references in the definition.
+    ///
+    /// # Arguments
+    /// * `strict` - If true, invalid references result in an error. Otherwise, they are skipped.
+    fn get_references_impl(&self, strict: bool) -> Result<Vec<u64>> {
+        // all relevant characters are ASCII, thus the bytes can be scanned directly
+        let definition = self.definition.as_bytes();
         let mut result = Vec::new();
+        let mut pos = 0;
 
-        for c in self.definition.chars() {
-            match mode {
-                Mode::Definition => {
-                    if c == '#' {
-                        mode = Mode::Reference;
-                    } else if c == '\'' {
-                        mode = Mode::String;
+        while pos < definition.len() {
+            match definition[pos] {
+                b'#' => {
+                    // the digits are accumulated in place without copying them
+                    let start = pos + 1;
+                    let mut end = start;
+                    let mut id = Some(0u64);
+                    while let Some(digit) = definition.get(end).filter(|b| b.is_ascii_digit()) {
+                        id = id
+                            .and_then(|id| id.checked_mul(10))
+                            .and_then(|id| id.checked_add((digit - b'0') as u64));
+                        end += 1;
                     }
-                }
-                Mode::Reference => {
-                    if c.is_ascii_digit() {
-                        buffer.push(c);
-                    } else {
-                        let id = buffer.parse::<u64>().unwrap();
-                        buffer.clear();
-                        result.push(id);
 
-                        if c == '\'' {
-                            mode = Mode::String;
-                        } else if c == '#' {
-                            mode = Mode::Reference;
-                        } else {
-                            mode = Mode::Definition;
+                    match id.filter(|_| end > start) {
+                        Some(id) => result.push(id),
+                        None if strict => {
+                            let digits = &self.definition[start..end];
+                            return Err(Error::InvalidNumber(format!("#{}", digits)));
                         }
+                        None => {}
                     }
+
+                    pos = end;
                 }
-                Mode::String => {
-                    if c == '\'' {
-                        mode = Mode::Definition;
+                b'\'' => {
+                    // skip the string, two consecutive quotes are an escaped quote inside it
+                    pos += 1;
+                    loop {
+                        match definition[pos..].iter().position(|b| *b == b'\'') {
+                            Some(len) if definition.get(pos + len + 1) == Some(&b'\'') => {
+                                pos += len + 2;
+                            }
+                            Some(len) => {
+                                pos += len + 1;
+                                break;
+                            }
+                            None => {
+                                pos = definition.len();
+                                break;
+                            }
+                        }
                     }
                 }
+                _ => pos += 1,
             }
         }
 
-        result
+        Ok(result)
     }
 }
 
+/// Parses the digits of a reference, i.e., the part after the `#`.
+///
+/// # Arguments
+/// * `digits` - The digits of the reference.
+fn parse_reference(digits: &str) -> Result<u64> {
+    digits
+        .parse::<u64>()
+        .map_err(|_| Error::InvalidNumber(format!("#{}", digits)))
+}
+
+/// Parses the given STEP integer or real, e.g., `42`, `-0.`, `1.745329251994E-02` or `1E10`. A
+/// missing integer part as in `.5` is accepted as well, although not allowed by the standard.
+///
+/// # Arguments
+/// * `s` - The number to parse.
+pub fn parse_number(s: &str) -> Result<f64> {
+    let invalid = || Error::InvalidNumber(s.to_owned());
+
+    let (mantissa, exponent) = match s.find(['E', 'e']) {
+        Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+        None => (s, None),
+    };
+
+    // the mantissa consists of an optional sign and digits with at most one decimal point
+    let mantissa_digits = mantissa.strip_prefix(['+', '-']).unwrap_or(mantissa);
+    let (integer, fraction) = mantissa_digits
+        .split_once('.')
+        .unwrap_or((mantissa_digits, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if integer.len() + fraction.len() == 0 || !all_digits(integer) || !all_digits(fraction) {
+        return Err(invalid());
+    }
+
+    // the exponent consists of an optional sign and at least one digit
+    if let Some(exponent) = exponent {
+        let exponent_digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if exponent_digits.is_empty() || !all_digits(exponent_digits) {
+            return Err(invalid());
+        }
+    }
+
+    s.parse::<f64>().map_err(|_| invalid())
+}
+
+/// Extracts the keyword from the given definition
+///
+/// # Arguments
+/// * `definition` - The definition to extract the keyword from.
+pub(crate) fn extract_keyword(definition: &str) -> &str {
+    let definition = definition.trim();
+
+    // find first character that does not belong to the keyword characters
+    let keyword_end = definition
+        .find(|c: char| !c.is_ascii_uppercase() && !c.is_ascii_digit() && c != '_')
+        .unwrap_or(definition.len());
+    &definition[..keyword_end]
+}
+
+/// Splits the definition of a complex instance, e.g., `(NAMED_UNIT(*)SI_UNIT($,.METRE.))`, into
+/// the definitions of its partial instances. Returns None if the definition is not a complex
+/// instance.
+///
+/// # Arguments
+/// * `definition` - The definition to split.
+fn split_complex_instance(definition: &str) -> Option<Vec<&str>> {
+    let inner = definition.trim().strip_prefix('(')?.strip_suffix(')')?;
+
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut inside_string = false;
+    let mut start = None;
+
+    for (i, c) in inner.char_indices() {
+        if inside_string {
+            // escaped quotes are handled as two consecutive strings
+            if c == '\'' {
+                inside_string = false;
+            }
+
+            continue;
+        }
+
+        match c {
+            '\'' => inside_string = true,
+            '(' => depth += 1,
+            ')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    result.push(inner[start?..=i].trim());
+                    start = None;
+                }
+            }
+            c if depth == 0 && start.is_none() && !c.is_whitespace() => start = Some(i),
+            _ => {}
+        }
+    }
+
+    Some(result)
+}
+
 /// The data of a STEP file.
+///
+/// The definitions of entries read from a file are in canonical form: whitespace and comments
+/// outside of string literals are removed, e.g., `FOO( 'a  b' ,\n #2 )` is read as
+/// `FOO('a  b',#2)`, while string literals are preserved verbatim. Thus, writing and reading the
+/// step data again results in the same entries, see `reparse_matches`.
 #[derive(Default)]
 pub struct StepData {
     /// The entries in the STEP file.
@@ -163,6 +557,12 @@ pub struct StepData {
 
     /// The range of the ids in the STEP file.
     id_range: Range<u64>,
+
+    /// Maps the id of an entry to its index in the entries list.
+    index: HashMap<u64, usize>,
+
+    /// The content of the HEADER section.
+    header: StepHeader,
 }
 
 impl StepData {
@@ -171,6 +571,8 @@ impl StepData {
         StepData {
             entries: Vec::new(),
             id_range: 0..0,
+            index: HashMap::new(),
+            header: StepHeader::default(),
         }
     }
 
@@ -179,23 +581,66 @@ impl StepData {
     /// # Arguments
     /// * `path` - The path to the STEP file.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<StepData> {
-        let filename_str: String = path.as_ref().to_string_lossy().to_string();
+        StepData::from_step_reader(StepData::stream_from_file(path)?)
+    }
 
-        let step_reader = STEPReader::new(
-            File::open(path).map_err(|e| Error::FailedOpenFile(Arc::new(e), filename_str))?,
-        )?;
+    /// Reads the step data from the given reader and fails once more than the given number of
+    /// entries have been read, e.g., to guard against untrusted files.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to parse the STEP data from.
+    /// * `max_entries` - The maximum number of entries to read.
+    pub fn from_reader_with_max_entries<R: Read>(
+        reader: R,
+        max_entries: usize,
+    ) -> Result<StepData> {
+        let mut step_reader = STEPReader::new(reader)?;
+        step_reader.set_max_entries(Some(max_entries));
+
+        StepData::from_step_reader(step_reader)
+    }
+
+    /// Collects the header and the entries of the given reader into validated step data.
+    ///
+    /// # Arguments
+    /// * `step_reader` - The reader streaming the entries.
+    fn from_step_reader<R: Read>(step_reader: STEPReader<R>) -> Result<StepData> {
+        let mut step_data = StepData::new();
+        step_data.set_header(step_reader.header().clone());
 
         let mut entries = Vec::new();
         for entry in step_reader {
             entries.push(entry?);
         }
 
-        let mut step_data = StepData::new();
         step_data.set_entries(entries);
+        step_data.validate()?;
 
         Ok(step_data)
     }
 
+    /// Opens the given file and returns a reader streaming its entries without collecting them,
+    /// e.g., to scan large files with a small memory footprint. The header is available from the
+    /// reader as well.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the STEP file.
+    pub fn stream_from_file<P: AsRef<Path>>(path: P) -> Result<STEPReader<File>> {
+        let filename_str: String = path.as_ref().to_string_lossy().to_string();
+
+        STEPReader::new(
+            File::open(path).map_err(|e| Error::FailedOpenFile(Arc::new(e), filename_str))?,
+        )
+    }
+
+    /// Returns a reader streaming the entries of the given STEP data without collecting them.
+    ///
+    /// # Arguments
+    /// * `s` - The STEP data.
+    pub fn stream_from_str(s: &str) -> Result<STEPReader<Cursor<&[u8]>>> {
+        STEPReader::new(Cursor::new(s.as_bytes()))
+    }
+
     /// Writes the step data to the given file.
     ///
     /// # Arguments
@@ -223,6 +668,7 @@ impl StepData {
             self.id_range.end = self.id_range.end.max(id + 1);
         }
 
+        self.index.insert(id, self.entries.len());
         self.entries.push(entry);
     }
 
@@ -232,6 +678,12 @@ impl StepData {
     /// * `entries` - The entries to be set.
     pub fn set_entries(&mut self, entries: Vec<StepEntry>) {
         self.entries = entries;
+        self.index = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.get_id(), i))
+            .collect();
 
         if let Some(first_entry) = self.entries.first() {
             let first_id = first_entry.get_id();
@@ -248,33 +700,435 @@ impl StepData {
         }
     }
 
+    /// Checks that no entry id appears twice.
+    pub fn validate(&self) -> Result<()> {
+        // the index only holds one index per id
+        if self.index.len() == self.entries.len() {
+            return Ok(());
+        }
+
+        let mut ids = HashSet::with_capacity(self.entries.len());
+        for entry in self.entries.iter() {
+            if !ids.insert(entry.get_id()) {
+                return Err(Error::DuplicateId(entry.get_id()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that all references point to existing entries.
+    pub fn validate_references(&self) -> Result<()> {
+        for entry in self.entries.iter() {
+            for reference in entry.try_get_references()? {
+                if !self.index.contains_key(&reference) {
+                    return Err(Error::UnresolvedReference(entry.get_id(), reference));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns all references that point to an id without an entry as pairs of the id of the
+    /// referencing entry and the missing id, in the order of the entries.
+    pub fn dangling_references(&self) -> Vec<(u64, u64)> {
+        self.entries
+            .iter()
+            .flat_map(|e| e.get_references().into_iter().map(|r| (e.get_id(), r)))
+            .filter(|(_, reference)| !self.index.contains_key(reference))
+            .collect()
+    }
+
     /// Returns the entries in the STEP file.
     pub fn get_entries(&self) -> &[StepEntry] {
         &self.entries
     }
 
+    /// Returns the entry with the given id or None if there is no such entry.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the entry.
+    pub fn get_entry_by_id(&self, id: u64) -> Option<&StepEntry> {
+        self.index.get(&id).map(|i| &self.entries[*i])
+    }
+
+    /// Returns the axis-aligned bounding box of the CARTESIAN_POINT entries or None if there are
+    /// no three-dimensional points.
+    pub fn point_bounds(&self) -> Option<PointBounds> {
+        PointBounds::from_entries(self.entries.iter())
+    }
+
+    /// Checks if writing the step data and reading it again results in the same entries, i.e.,
+    /// the same ids and definitions in the same order. This holds for step data that has been
+    /// read from a file, as the definitions are in canonical form.
+    pub fn reparse_matches(&self) -> Result<bool> {
+        let mut buffer = Vec::new();
+        writer::write_step(&mut buffer, self, "")?;
+        let reparsed = StepData::from_str(std::str::from_utf8(&buffer)?)?;
+
+        Ok(reparsed.entries == self.entries)
+    }
+
+    /// Returns the number of entries per entity type, e.g., to inspect the composition of a file.
+    /// The partial instances of complex instances are counted for each of their entity types.
+    pub fn entity_histogram(&self) -> BTreeMap<String, usize> {
+        let mut histogram = BTreeMap::new();
+        for keyword in self.entries.iter().flat_map(|e| e.keywords()) {
+            *histogram.entry(keyword.to_owned()).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    /// Returns the entries referenced by the entry with the given id as pairs of their id and
+    /// keyword, e.g., to see the role of an entry. Every referenced id is listed once in the order
+    /// of its first reference. Unresolved references are skipped and an unknown id results in an
+    /// empty list.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the referencing entry.
+    pub fn reference_keywords(&self, id: u64) -> Vec<(u64, String)> {
+        let Some(entry) = self.get_entry_by_id(id) else {
+            return Vec::new();
+        };
+
+        let mut visited = HashSet::new();
+        entry
+            .get_references()
+            .into_iter()
+            .filter(|reference| visited.insert(*reference))
+            .filter_map(|reference| {
+                self.get_entry_by_id(reference)
+                    .map(|e| (reference, e.keyword().to_owned()))
+            })
+            .collect()
+    }
+
+    /// Updates the ids and references of all entries using the given function.
+    ///
+    /// # Arguments
+    /// * `f` - The function to update the ids and references. Must be a strictly monotonic
+    ///   function.
+    pub fn update_references(&mut self, f: impl Fn(u64) -> u64) {
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| e.update_references(&f))
+            .collect();
+
+        self.set_entries(entries);
+    }
+
+    /// Renumbers the entries to gap-free ids starting at 1 while keeping their order and updates
+    /// all references accordingly. Returns the mapping from the old to the new ids. References
+    /// to ids without an entry are left unchanged, see `validate_references`.
+    pub fn compact_ids(&mut self) -> HashMap<u64, u64> {
+        let mut ids: Vec<u64> = self.entries.iter().map(|e| e.get_id()).collect();
+        ids.sort_unstable();
+
+        // the rank of an id is a strictly monotonic function on the existing ids
+        self.update_references(|id| match ids.binary_search(&id) {
+            Ok(index) => index as u64 + 1,
+            Err(_) => id,
+        });
+
+        ids.iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index as u64 + 1))
+            .collect()
+    }
+
+    /// Keeps only the entries for which the predicate returns true, e.g., to strip presentation
+    /// data such as STYLED_ITEM entries. Returns the references of the kept entries that point to
+    /// removed entries as pairs of the id of the referencing entry and the removed id. References
+    /// that have been dangling before are not reported.
+    ///
+    /// # Arguments
+    /// * `predicate` - The predicate deciding if an entry is kept.
+    pub fn retain_entities(&mut self, predicate: impl Fn(&StepEntry) -> bool) -> Vec<(u64, u64)> {
+        let (kept, removed): (Vec<StepEntry>, Vec<StepEntry>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|e| predicate(e));
+        self.set_entries(kept);
+
+        let removed: HashSet<u64> = removed.iter().map(|e| e.get_id()).collect();
+        self.dangling_references()
+            .into_iter()
+            .filter(|(_, reference)| removed.contains(reference))
+            .collect()
+    }
+
+    /// Merges the entries of the given entity types that have identical definitions, e.g., the
+    /// many `DIRECTION('',(0.,0.,1.))` entries of a merged file. The first entry of each
+    /// definition is kept and all references to its duplicates are redirected to it. Returns the
+    /// number of removed duplicates.
+    ///
+    /// # Arguments
+    /// * `keywords` - The keywords of the entity types to be merged, e.g., `CARTESIAN_POINT`.
+    pub fn merge_duplicates(&mut self, keywords: &[&str]) -> usize {
+        let mut redirects: HashMap<u64, u64> = HashMap::new();
+        let mut canonical: HashMap<&str, u64> = HashMap::new();
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| keywords.contains(&e.keyword()))
+        {
+            let id = *canonical
+                .entry(entry.get_definition())
+                .or_insert(entry.get_id());
+            if id != entry.get_id() {
+                redirects.insert(entry.get_id(), id);
+            }
+        }
+
+        if redirects.is_empty() {
+            return 0;
+        }
+
+        let entries = self
+            .entries
+            .iter()
+            .filter(|e| !redirects.contains_key(&e.get_id()))
+            .map(|e| e.update_references(|id| redirects.get(&id).copied().unwrap_or(id)))
+            .collect();
+        self.set_entries(entries);
+
+        redirects.len()
+    }
+
+    /// Appends the entries of the other step data. The ids of the appended entries are shifted
+    /// past the largest id of this step data and their references are updated accordingly.
+    /// Returns the mapping from the ids of the other step data to the new ids.
+    ///
+    /// # Arguments
+    /// * `other` - The step data whose entries are appended.
+    /// * `collapse_app_context` - If true and both step data have an APPLICATION_CONTEXT, the
+    ///   references to the APPLICATION_CONTEXT of the other step data are redirected to the one
+    ///   of this step data. Its APPLICATION_CONTEXT and APPLICATION_PROTOCOL_DEFINITION entries
+    ///   are then dropped.
+    pub fn append(&mut self, other: &StepData, collapse_app_context: bool) -> HashMap<u64, u64> {
+        let app_context_id = |step: &StepData| {
+            step.entries
+                .iter()
+                .find(|e| e.keyword() == "APPLICATION_CONTEXT")
+                .map(|e| e.get_id())
+        };
+
+        let app_contexts = match (app_context_id(self), app_context_id(other)) {
+            (Some(own), Some(other)) if collapse_app_context => Some((own, other)),
+            _ => None,
+        };
+
+        let id_offset = self.id_range.end.saturating_sub(1);
+        let update_id = |id: u64| match app_contexts {
+            Some((own, other)) if id == other => own,
+            _ => id + id_offset,
+        };
+
+        let mut id_map = HashMap::with_capacity(other.entries.len());
+        for entry in other.entries.iter() {
+            let dropped = app_contexts.is_some()
+                && matches!(
+                    entry.keyword(),
+                    "APPLICATION_CONTEXT" | "APPLICATION_PROTOCOL_DEFINITION"
+                );
+            if !dropped {
+                self.add_entry(entry.update_references(update_id));
+            }
+
+            if !dropped || entry.keyword() == "APPLICATION_CONTEXT" {
+                id_map.insert(entry.get_id(), update_id(entry.get_id()));
+            }
+        }
+
+        id_map
+    }
+
+    /// Returns the content of the HEADER section.
+    pub fn get_header(&self) -> &StepHeader {
+        &self.header
+    }
+
+    /// Sets the content of the HEADER section.
+    ///
+    /// # Arguments
+    /// * `header` - The header to be set.
+    pub fn set_header(&mut self, header: StepHeader) {
+        self.header = header;
+    }
+
     /// Returns the range of the ids in the STEP file.
     pub fn get_id_range(&self) -> Range<u64> {
         self.id_range.clone()
     }
-}
 
-impl FromStr for StepData {
-    type Err = Error;
+    /// Returns the assembly edges as pairs of parent and child labels. The edges are extracted
+    /// from the NEXT_ASSEMBLY_USAGE_OCCURRENCE entries. The labels of the parent and the child are
+    /// resolved via the related PRODUCT_DEFINITION and PRODUCT entries, as the strings of the
+    /// occurrence may name the instance instead of the part, e.g., `BOLT-1`. Occurrences whose
+    /// parent or child cannot be resolved are skipped.
+    pub fn occurrence_edges(&self) -> Vec<(String, String)> {
+        // follows the reference at the given position if the entry has the expected keyword
+        let follow = |id: u64, keyword: &str, position: usize| -> Option<u64> {
+            let entry = self.get_entry_by_id(id)?;
+            if !entry.keyword().starts_with(keyword) {
+                return None;
+            }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let reader = Cursor::new(s.as_bytes());
-        let parsed_step = STEPReader::new(reader)?;
+            entry.get_references().get(position).copied()
+        };
 
-        let mut entries = Vec::new();
-        for entry in parsed_step {
-            entries.push(entry?);
+        // resolves the label of the PRODUCT of the given PRODUCT_DEFINITION
+        let product_label = |product_definition: u64| -> Option<String> {
+            follow(product_definition, "PRODUCT_DEFINITION", 0)
+                .and_then(|formation| follow(formation, "PRODUCT_DEFINITION_FORMATION", 0))
+                .and_then(|product| self.get_entry_by_id(product))
+                .filter(|product| product.keyword() == "PRODUCT")
+                .and_then(|product| product.get_strings().into_iter().next())
+        };
+
+        let mut result = Vec::new();
+        for entry in self.entries.iter() {
+            if entry.keyword() != "NEXT_ASSEMBLY_USAGE_OCCURRENCE" {
+                continue;
+            }
+
+            let references = entry.get_references();
+            let (Some(parent), Some(child)) = (references.first(), references.get(1)) else {
+                continue;
+            };
+
+            if let (Some(parent_label), Some(child_label)) =
+                (product_label(*parent), product_label(*child))
+            {
+                result.push((parent_label, child_label));
+            }
         }
 
-        let mut step_data = StepData::new();
-        step_data.set_entries(entries);
+        result
+    }
 
-        Ok(step_data)
+    /// Returns the ids of the entries in an order where each entry follows the entries it
+    /// references, while keeping the original order as far as possible. The returned flag is
+    /// false if the references form cycles, which is legal in STEP. In that case, a single entry
+    /// of each cycle is placed before the entries it references.
+    pub fn topological_order(&self) -> (Vec<u64>, bool) {
+        let num_entries = self.entries.len();
+        let mut num_pending = vec![0usize; num_entries];
+        let mut references: Vec<Vec<usize>> = vec![Vec::new(); num_entries];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); num_entries];
+        for (index, entry) in self.entries.iter().enumerate() {
+            let mut entry_references = entry.get_references();
+            entry_references.sort_unstable();
+            entry_references.dedup();
+
+            // references to missing entries or to the entry itself do not constrain the order
+            for reference in entry_references
+                .into_iter()
+                .filter(|r| *r != entry.get_id())
+            {
+                if let Some(reference_index) = self.index.get(&reference) {
+                    num_pending[index] += 1;
+                    references[index].push(*reference_index);
+                    dependents[*reference_index].push(index);
+                }
+            }
+        }
+
+        // the ready entries are emitted in their original order
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..num_entries)
+            .filter(|index| num_pending[*index] == 0)
+            .map(Reverse)
+            .collect();
+        let mut placed = vec![false; num_entries];
+        let mut next_unplaced = 0;
+        let mut acyclic = true;
+        let mut result = Vec::with_capacity(num_entries);
+
+        while result.len() < num_entries {
+            let index = match ready.pop() {
+                Some(Reverse(index)) if placed[index] => continue,
+                Some(Reverse(index)) => index,
+                None => {
+                    // all remaining entries are on or behind a cycle, thus the pending references
+                    // are followed until an entry repeats, which is on a cycle and placed first
+                    acyclic = false;
+                    while placed[next_unplaced] {
+                        next_unplaced += 1;
+                    }
+
+                    let mut index = next_unplaced;
+                    let mut visited = HashSet::new();
+                    while visited.insert(index) {
+                        index = references[index]
+                            .iter()
+                            .copied()
+                            .find(|r| !placed[*r])
+                            .unwrap_or(index);
+                    }
+
+                    index
+                }
+            };
+
+            placed[index] = true;
+            result.push(self.entries[index].get_id());
+            for dependent in dependents[index].iter() {
+                num_pending[*dependent] -= 1;
+                if num_pending[*dependent] == 0 && !placed[*dependent] {
+                    ready.push(Reverse(*dependent));
+                }
+            }
+        }
+
+        (result, acyclic)
+    }
+
+    /// Reorders the entries according to `topological_order`, i.e., referenced entries precede
+    /// the entries referencing them. Returns false if the references form cycles.
+    pub fn sort_topologically(&mut self) -> bool {
+        let (order, acyclic) = self.topological_order();
+
+        let mut entries: Vec<Option<StepEntry>> = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let sorted = order
+            .iter()
+            .filter_map(|id| entries[self.index[id]].take())
+            .collect();
+        self.set_entries(sorted);
+
+        acyclic
+    }
+
+    /// Returns the ids of the entries that are never referenced by any other entry and that are
+    /// not top-level entries by design, e.g., the application protocol definition or the
+    /// presentation entries. Such entries do not contribute to the model and only bloat the file.
+    pub fn dead_entries(&self) -> Vec<u64> {
+        let mut presentation = PresentationFilter::new();
+        for entry in self.entries.iter() {
+            presentation.add_entry(entry);
+        }
+
+        let mut excluded = presentation.into_dropped_ids();
+        excluded.extend(self.entries.iter().flat_map(|e| e.get_references()));
+
+        self.entries
+            .iter()
+            .filter(|e| !excluded.contains(&e.get_id()))
+            .filter(|e| !ROOT_KEYWORDS.contains(&e.keyword()))
+            .map(|e| e.get_id())
+            .collect()
+    }
+}
+
+impl FromStr for StepData {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        StepData::from_step_reader(StepData::stream_from_str(s)?)
     }
 }
 
@@ -282,6 +1136,242 @@ impl FromStr for StepData {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_from_reader_with_max_entries() {
+        let cube = include_bytes!("../../../test_data/cube.stp").as_slice();
+        let num_entries = StepData::from_reader_with_max_entries(cube, usize::MAX)
+            .unwrap()
+            .get_entries()
+            .len();
+
+        assert!(StepData::from_reader_with_max_entries(cube, num_entries).is_ok());
+        assert!(matches!(
+            StepData::from_reader_with_max_entries(cube, num_entries - 1),
+            Err(Error::MaxEntriesExceeded(max_entries)) if max_entries == num_entries - 1
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let step = StepData::from_str("ISO-10303-21; DATA; #1=FOO(#2); #2=BAR(); ENDSEC;").unwrap();
+        assert!(step.validate().is_ok());
+        assert!(step.validate_references().is_ok());
+
+        let result =
+            StepData::from_str("ISO-10303-21; DATA; #1=FOO(); #2=BAR(); #1=BAZ(); ENDSEC;");
+        assert!(matches!(result, Err(Error::DuplicateId(1))));
+
+        let step =
+            StepData::from_str("ISO-10303-21; DATA; #1=FOO(#2,#3); #2=BAR(); ENDSEC;").unwrap();
+        assert!(matches!(
+            step.validate_references(),
+            Err(Error::UnresolvedReference(1, 3))
+        ));
+    }
+
+    #[test]
+    fn test_extract_keyword() {
+        let s = "PRODUCT_DEFINITION_SHAPE('',#,#);";
+        let keyword = extract_keyword(s);
+
+        assert_eq!(keyword, "PRODUCT_DEFINITION_SHAPE");
+
+        let s = "  FOOBAR_BLUB( );  ";
+        let keyword = extract_keyword(s);
+
+        assert_eq!(keyword, "FOOBAR_BLUB");
+
+        let s = "AXIS2_PLACEMENT_3D('',#1,#2,#3)";
+        let keyword = extract_keyword(s);
+
+        assert_eq!(keyword, "AXIS2_PLACEMENT_3D");
+    }
+
+    #[test]
+    fn test_empty_data_section() {
+        let step = StepData::from_str("ISO-10303-21; DATA; ENDSEC; END-ISO-10303-21;").unwrap();
+        assert!(step.get_entries().is_empty());
+        assert_eq!(step.get_id_range(), 0..0);
+        assert!(step.validate_references().is_ok());
+        assert_eq!(step.topological_order(), (Vec::new(), true));
+    }
+
+    #[test]
+    fn test_append() {
+        let source = "ISO-10303-21; DATA; #1=APPLICATION_CONTEXT('');
+            #2=APPLICATION_PROTOCOL_DEFINITION('','',2000,#1); #3=PRODUCT_CONTEXT('',#1,'');
+            ENDSEC;";
+
+        // the ids are shifted past the largest id
+        let mut step = StepData::from_str(source).unwrap();
+        let id_map = step.append(&StepData::from_str(source).unwrap(), false);
+        assert_eq!(id_map, HashMap::from([(1, 4), (2, 5), (3, 6)]));
+        assert_eq!(step.get_entries().len(), 6);
+        assert_eq!(
+            step.get_entry_by_id(6).unwrap().get_definition(),
+            "PRODUCT_CONTEXT('',#4,'')"
+        );
+        assert_eq!(step.get_id_range(), 1..7);
+
+        // the shared application context is only kept once
+        let mut step = StepData::from_str(source).unwrap();
+        let id_map = step.append(&StepData::from_str(source).unwrap(), true);
+        assert_eq!(id_map, HashMap::from([(1, 1), (3, 6)]));
+        assert_eq!(step.get_entries().len(), 4);
+        assert_eq!(
+            step.get_entry_by_id(6).unwrap().get_definition(),
+            "PRODUCT_CONTEXT('',#1,'')"
+        );
+        assert!(step.validate().is_ok());
+        assert!(step.validate_references().is_ok());
+
+        // appending to an empty step data keeps the ids
+        let mut step = StepData::new();
+        let id_map = step.append(&StepData::from_str(source).unwrap(), true);
+        assert_eq!(id_map, HashMap::from([(1, 1), (2, 2), (3, 3)]));
+    }
+
+    #[test]
+    fn test_stream_from_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_data/cube.stp");
+        let step_data = StepData::from_file(path).unwrap();
+
+        let reader = StepData::stream_from_file(path).unwrap();
+        assert_eq!(reader.header(), step_data.get_header());
+
+        let mut num_entries = 0;
+        for (entry, expected) in reader.zip(step_data.get_entries()) {
+            assert_eq!(&entry.unwrap(), expected);
+            num_entries += 1;
+        }
+        assert_eq!(num_entries, step_data.get_entries().len());
+
+        assert!(matches!(
+            StepData::stream_from_file("does-not-exist.stp"),
+            Err(Error::FailedOpenFile(..))
+        ));
+
+        let reader = StepData::stream_from_str("ISO-10303-21; DATA; #1=FOO(); ENDSEC;").unwrap();
+        assert_eq!(
+            reader.map(|e| e.unwrap().get_id()).collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_compact_ids() {
+        let mut step = StepData::from_str(
+            "ISO-10303-21; DATA; #10=FOO(#30,'#20'); #30=BAR(); #25=BAZ(#10); ENDSEC;",
+        )
+        .unwrap();
+
+        let id_map = step.compact_ids();
+        assert_eq!(id_map, HashMap::from([(10, 1), (25, 2), (30, 3)]));
+        assert_eq!(
+            step.get_entries(),
+            &[
+                StepEntry::new(1, "FOO(#3,'#20')"),
+                StepEntry::new(3, "BAR()"),
+                StepEntry::new(2, "BAZ(#1)"),
+            ]
+        );
+        assert_eq!(step.get_id_range(), 1..4);
+        assert!(step.validate_references().is_ok());
+    }
+
+    #[test]
+    fn test_compact_ids_dangling_reference() {
+        let mut step =
+            StepData::from_str("ISO-10303-21; DATA; #10=FOO(#30,#20); #30=BAR(); ENDSEC;").unwrap();
+
+        // the dangling reference is kept instead of being redirected to an unrelated entry
+        let id_map = step.compact_ids();
+        assert_eq!(id_map, HashMap::from([(10, 1), (30, 2)]));
+        assert_eq!(
+            step.get_entries(),
+            &[StepEntry::new(1, "FOO(#2,#20)"), StepEntry::new(2, "BAR()"),]
+        );
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let mut step = StepData::from_str(
+            "ISO-10303-21; DATA; #1=FOO(#3,#2,#3); #2=BAR(#3); #3=BAZ(); #4=QUX(#1,#9); ENDSEC;",
+        )
+        .unwrap();
+        assert_eq!(step.topological_order(), (vec![3, 2, 1, 4], true));
+
+        assert!(step.sort_topologically());
+        let ids = step
+            .get_entries()
+            .iter()
+            .map(|e| e.get_id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 2, 1, 4]);
+        assert_eq!(
+            step.get_entry_by_id(1).unwrap().get_definition(),
+            "FOO(#3,#2,#3)"
+        );
+
+        // the entries are kept in their original order if there are no references
+        let step = StepData::from_str("ISO-10303-21; DATA; #5=FOO(); #2=BAR(); ENDSEC;").unwrap();
+        assert_eq!(step.topological_order(), (vec![5, 2], true));
+    }
+
+    #[test]
+    fn test_topological_order_cyclic() {
+        // #1 and #2 reference each other and #4 depends on the cycle
+        let step = StepData::from_str(
+            "ISO-10303-21; DATA; #4=QUX(#2); #1=FOO(#2,#3); #2=BAR(#1); #3=BAZ(#3); ENDSEC;",
+        )
+        .unwrap();
+
+        let (order, acyclic) = step.topological_order();
+        assert!(!acyclic);
+        assert_eq!(order, vec![3, 2, 4, 1]);
+    }
+
+    #[test]
+    fn test_keywords() {
+        let entry = StepEntry::new(5, "(NAMED_UNIT(*)SI_UNIT($,.METRE.)LENGTH_UNIT())");
+        assert_eq!(
+            entry.keywords(),
+            vec!["NAMED_UNIT", "SI_UNIT", "LENGTH_UNIT"]
+        );
+
+        let entry = StepEntry::new(
+            5,
+            " ( GEOMETRIC_REPRESENTATION_CONTEXT(3)\n REPRESENTATION_CONTEXT('(a)','') ) ",
+        );
+        assert_eq!(
+            entry.keywords(),
+            vec!["GEOMETRIC_REPRESENTATION_CONTEXT", "REPRESENTATION_CONTEXT"]
+        );
+        assert_eq!(
+            entry.partial_entries().unwrap()[1].get_definition(),
+            "REPRESENTATION_CONTEXT('(a)','')"
+        );
+
+        let entry = StepEntry::new(1, "PRODUCT('a','b','',(#2))");
+        assert_eq!(entry.keywords(), vec!["PRODUCT"]);
+        assert!(entry.partial_entries().is_none());
+    }
+
+    #[test]
+    fn test_keyword() {
+        let entry = StepEntry::new(1, "PRODUCT_DEFINITION('','',#2,#3)");
+        assert_eq!(entry.keyword(), "PRODUCT_DEFINITION");
+
+        let entry = StepEntry::new(1, " \n CARTESIAN_POINT ('',(0.,0.,0.))");
+        assert_eq!(entry.keyword(), "CARTESIAN_POINT");
+
+        let entry = StepEntry::new(
+            1,
+            "(GEOMETRIC_REPRESENTATION_CONTEXT(3)REPRESENTATION_CONTEXT('',''))",
+        );
+        assert_eq!(entry.keyword(), "");
+    }
+
     #[test]
     fn test_ranges() {
         let mut step_data = StepData::new();
@@ -301,6 +1391,35 @@ mod test {
         assert_eq!(step_data.get_id_range(), 1..5);
     }
 
+    #[test]
+    fn test_get_entry_by_id() {
+        let mut step_data = StepData::new();
+        step_data.add_entry(StepEntry::new(3, "FOO(#5)"));
+        step_data.add_entry(StepEntry::new(5, "BAR()"));
+
+        assert_eq!(
+            step_data.get_entry_by_id(3).unwrap().get_definition(),
+            "FOO(#5)"
+        );
+        assert_eq!(
+            step_data.get_entry_by_id(5).unwrap().get_definition(),
+            "BAR()"
+        );
+        assert!(step_data.get_entry_by_id(4).is_none());
+
+        step_data.update_references(|id| id + 10);
+        assert!(step_data.get_entry_by_id(3).is_none());
+        assert_eq!(
+            step_data.get_entry_by_id(13).unwrap().get_definition(),
+            "FOO(#15)"
+        );
+        assert_eq!(
+            step_data.get_entry_by_id(15).unwrap().get_definition(),
+            "BAR()"
+        );
+        assert_eq!(step_data.get_id_range(), 13..16);
+    }
+
     #[test]
     fn test_update_reference_simple() {
         let f = |id| id + 1;
@@ -326,6 +1445,329 @@ mod test {
         assert_eq!(entry.get_definition(), "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#10531))GLOBAL_UNIT_ASSIGNED_CONTEXT((#1008,#1009,#1007))REPRESENTATION_CONTEXT('',''));");
     }
 
+    #[test]
+    fn test_update_reference_escaped_quotes() {
+        let f = |id| id + 10;
+
+        let entry = StepEntry::new(1, "PRODUCT('it''s a part #1','',#5)");
+        let entry = entry.update_references(f);
+        assert_eq!(entry.get_definition(), "PRODUCT('it''s a part #1','',#15)");
+
+        let entry = StepEntry::new(1, "FOO('''#2''','#3'#4,'')");
+        let entry = entry.update_references(f);
+        assert_eq!(entry.get_definition(), "FOO('''#2''','#3'#14,'')");
+    }
+
+    #[test]
+    fn test_is_well_formed() {
+        let entry = StepEntry::new(1, "PRODUCT('A','B','',(#2))");
+        assert!(entry.is_well_formed());
+
+        let entry = StepEntry::new(1, "PRODUCT('(A','B)','',(#2))");
+        assert!(entry.is_well_formed());
+
+        let entry = StepEntry::new(1, "PRODUCT('It''s','B','',(#2))");
+        assert!(entry.is_well_formed());
+
+        let entry = StepEntry::new(1, "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))");
+        assert!(entry.is_well_formed());
+    }
+
+    #[test]
+    fn test_is_well_formed_unbalanced() {
+        let entry = StepEntry::new(1, "PRODUCT('A','B','',(#2)");
+        assert!(!entry.is_well_formed());
+
+        let entry = StepEntry::new(1, "PRODUCT('A','B','',#2))");
+        assert!(!entry.is_well_formed());
+
+        let entry = StepEntry::new(1, ")PRODUCT('A','B','',(#2)(");
+        assert!(!entry.is_well_formed());
+    }
+
+    #[test]
+    fn test_is_well_formed_unterminated_string() {
+        let entry = StepEntry::new(1, "PRODUCT('A,'B','',(#2))");
+        assert!(!entry.is_well_formed());
+
+        let entry = StepEntry::new(1, "PRODUCT('It''s)");
+        assert!(!entry.is_well_formed());
+    }
+
+    #[test]
+    fn test_get_strings() {
+        let entry = StepEntry::new(1, "PRODUCT('A','It''s','',(#2))");
+        assert_eq!(entry.get_strings(), vec!["A", "It's", ""]);
+
+        let entry = StepEntry::new(1, "DIRECTION($,(0.,0.,1.))");
+        assert!(entry.get_strings().is_empty());
+    }
+
+    #[test]
+    fn test_decoded_strings() {
+        let entry = StepEntry::new(1, "PRODUCT('Caf\\X2\\00E9\\X0\\','It''s','a\\\\b',(#2))");
+        assert_eq!(
+            entry.get_strings(),
+            vec!["Caf\\X2\\00E9\\X0\\", "It's", "a\\\\b"]
+        );
+        assert_eq!(
+            entry.decoded_strings().unwrap(),
+            vec!["Café", "It's", "a\\b"]
+        );
+
+        let entry = StepEntry::new(1, "PRODUCT('\\X2\\00E\\X0\\')");
+        assert!(entry.decoded_strings().is_err());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("42").unwrap(), 42.0);
+        assert_eq!(parse_number("1.").unwrap(), 1.0);
+        assert_eq!(parse_number("+1.5").unwrap(), 1.5);
+        assert_eq!(parse_number(".5").unwrap(), 0.5);
+        assert_eq!(
+            parse_number("1.745329251994E-02").unwrap(),
+            0.01745329251994
+        );
+        assert_eq!(parse_number("1E10").unwrap(), 1e10);
+        assert_eq!(parse_number("0.1E+3").unwrap(), 100.0);
+
+        let negative_zero = parse_number("-0.").unwrap();
+        assert_eq!(negative_zero, 0.0);
+        assert!(negative_zero.is_sign_negative());
+
+        for invalid in [
+            "", ".", "-", "1.2.3", "1E", "1E+", "inf", "NaN", "1x", "--1",
+        ] {
+            assert!(
+                matches!(parse_number(invalid), Err(Error::InvalidNumber(_))),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_numbers() {
+        let entry = StepEntry::new(1, "CARTESIAN_POINT('P1.5',(1.,-0.,1.745329251994E-02))");
+        assert_eq!(entry.get_numbers(), vec![1.0, 0.0, 0.01745329251994]);
+
+        // references, enumerations and the digits of keywords are skipped
+        let entry = StepEntry::new(1, "AXIS2_PLACEMENT_3D('',#12,#13,#14)");
+        assert!(entry.get_numbers().is_empty());
+        let entry = StepEntry::new(1, "FOO(.T., 3, $, ( 1E10 ,2))");
+        assert_eq!(entry.get_numbers(), vec![3.0, 1e10, 2.0]);
+        let entry = StepEntry::new(
+            1,
+            "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(0.1E-12),#5,'a','b')",
+        );
+        assert_eq!(entry.get_numbers(), vec![0.1e-12]);
+    }
+
+    #[test]
+    fn test_occurrence_edges() {
+        let source = include_str!("../../../test_data/2-cubes-1-sphere.stp");
+        let step_data = StepData::from_str(source).unwrap();
+
+        let edges = step_data.occurrence_edges();
+        let x3d =
+            "http://i3dhub-uploadsvc:8080/data/38c/1f/fb0-f0eb-494f-aa2b-5cc71d3fed67/simple.x3d";
+        // the occurrences of the cube are named `Left Cube` and `Right Cube`, while the edges
+        // refer to the part
+        let expected = [
+            ("My Cubes", "cube"),
+            ("My Cubes", "cube"),
+            ("Root Node", "My Cubes"),
+            ("My Spheres", "(Nicht gespeichert)"),
+            ("Root Node", "My Spheres"),
+            (x3d, "Root Node"),
+            ("Model", x3d),
+        ];
+
+        assert_eq!(edges.len(), expected.len());
+        for ((parent, child), (expected_parent, expected_child)) in
+            edges.iter().zip(expected.iter())
+        {
+            assert_eq!(parent, expected_parent);
+            assert_eq!(child, expected_child);
+        }
+    }
+
+    #[test]
+    fn test_dead_entries() {
+        // the unreferenced entries are roots, e.g., the application protocol definition, or belong
+        // to the presentation, e.g., the surface style boundary referring to a curve style
+        let source = include_str!("../../../test_data/cube-presentation.stp");
+        let mut step_data = StepData::from_str(source).unwrap();
+        assert_eq!(step_data.dead_entries(), Vec::<u64>::new());
+
+        let id = step_data.get_id_range().end;
+        step_data.add_entry(StepEntry::new(id, "CARTESIAN_POINT('',(1.,2.,3.))"));
+        step_data.add_entry(StepEntry::new(
+            id + 1,
+            "PRODUCT_RELATED_PRODUCT_CATEGORY('part',$,(#1))",
+        ));
+        step_data.add_entry(StepEntry::new(id + 2, "APPLICATION_CONTEXT('design')"));
+        step_data.add_entry(StepEntry::new(id + 3, "COLOUR_RGB('',1.,0.,0.)"));
+        step_data.add_entry(StepEntry::new(id + 4, "DIRECTION('',(0.,0.,1.))"));
+
+        assert_eq!(step_data.dead_entries(), vec![id, id + 4]);
+    }
+
+    #[test]
+    fn test_retain_entities() {
+        let source = include_str!("../../../test_data/sphere.stp");
+        let step_data = StepData::from_str(source).unwrap();
+        let num_entries = step_data.get_entries().len();
+
+        // the presentation still references the removed styled items
+        let mut stripped = StepData::from_str(source).unwrap();
+        let dangling = stripped.retain_entities(|e| e.keyword() != "STYLED_ITEM");
+        assert_eq!(stripped.get_entries().len(), num_entries - 3);
+        assert_eq!(dangling, vec![(86, 66), (88, 74)]);
+        assert!(stripped.get_entry_by_id(74).is_none());
+
+        let mut stripped = StepData::from_str(source).unwrap();
+        let dangling = stripped.retain_entities(|e| {
+            !matches!(
+                e.keyword(),
+                "STYLED_ITEM"
+                    | "DRAUGHTING_MODEL"
+                    | "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION"
+            )
+        });
+        assert_eq!(stripped.get_entries().len(), num_entries - 5);
+        assert!(dangling.is_empty());
+        assert!(stripped.validate_references().is_ok());
+    }
+
+    #[test]
+    fn test_merge_duplicates() {
+        let mut step_data = StepData::from_str(concat!(
+            "ISO-10303-21; DATA; ",
+            "#1=DIRECTION('',(0.,0.,1.)); #2=DIRECTION('',(1.,0.,0.)); ",
+            "#3=CARTESIAN_POINT('',(0.,0.,0.)); #4=DIRECTION('',(0.,0.,1.)); ",
+            "#5=AXIS2_PLACEMENT_3D('',#3,#4,#2); #6=DIRECTION('',(0.,0.,1.)); ",
+            "#7=AXIS2_PLACEMENT_3D('',#3,#6,#2); #8=FOO(); #9=FOO(); ENDSEC;"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            step_data.merge_duplicates(&["DIRECTION", "CARTESIAN_POINT"]),
+            2
+        );
+        let definitions: Vec<(u64, &str)> = step_data
+            .get_entries()
+            .iter()
+            .map(|e| (e.get_id(), e.get_definition()))
+            .collect();
+        assert_eq!(
+            definitions,
+            vec![
+                (1, "DIRECTION('',(0.,0.,1.))"),
+                (2, "DIRECTION('',(1.,0.,0.))"),
+                (3, "CARTESIAN_POINT('',(0.,0.,0.))"),
+                (5, "AXIS2_PLACEMENT_3D('',#3,#1,#2)"),
+                (7, "AXIS2_PLACEMENT_3D('',#3,#1,#2)"),
+                (8, "FOO()"),
+                (9, "FOO()"),
+            ]
+        );
+        assert!(step_data.validate_references().is_ok());
+        assert_eq!(step_data.merge_duplicates(&["DIRECTION"]), 0);
+    }
+
+    #[test]
+    fn test_point_bounds() {
+        let source = include_str!("../../../test_data/cube.stp");
+        let bounds = StepData::from_str(source).unwrap().point_bounds().unwrap();
+        assert_eq!(bounds.num_points, 27);
+        assert!(bounds.size().iter().all(|s| (s - 300.0).abs() < 1e-6));
+
+        assert!(StepData::new().point_bounds().is_none());
+    }
+
+    #[test]
+    fn test_reparse_matches() {
+        let sources = [
+            include_str!("../../../test_data/cube.stp"),
+            include_str!("../../../test_data/sphere.stp"),
+            include_str!("../../../test_data/cube-and-sphere.stp"),
+            include_str!("../../../test_data/minimal-structure-with-metadata.stp"),
+            include_str!("../../../test_data/wiki.stp"),
+            "ISO-10303-21; DATA; #1=FOO( 'a  b' ,\n\t#2 /* c */ ) ; #2 = BAR('x\ny'); ENDSEC;",
+        ];
+
+        for source in sources {
+            let step_data = StepData::from_str(source).unwrap();
+            assert!(step_data.reparse_matches().unwrap());
+        }
+
+        let step_data = StepData::from_str(sources[5]).unwrap();
+        let definitions: Vec<&str> = step_data
+            .get_entries()
+            .iter()
+            .map(|e| e.get_definition())
+            .collect();
+        assert_eq!(definitions, vec!["FOO('a  b',#2)", "BAR('x\ny')"]);
+
+        // definitions that are not in canonical form are normalized when being read again
+        let mut step_data = StepData::new();
+        step_data.add_entry(StepEntry::new(1, "FOO( #2 )"));
+        step_data.add_entry(StepEntry::new(2, "BAR()"));
+        assert!(!step_data.reparse_matches().unwrap());
+    }
+
+    #[test]
+    fn test_entity_histogram() {
+        let source = include_str!("../../../test_data/wiki.stp");
+        let histogram = StepData::from_str(source).unwrap().entity_histogram();
+        assert_eq!(histogram.len(), 11);
+        assert_eq!(histogram["PRODUCT"], 1);
+        assert_eq!(histogram["APPLICATION_CONTEXT"], 1);
+
+        let source = include_str!("../../../test_data/cube.stp");
+        let histogram = StepData::from_str(source).unwrap().entity_histogram();
+        assert_eq!(histogram["CARTESIAN_POINT"], 27);
+        assert_eq!(histogram["DIRECTION"], 26);
+        assert_eq!(histogram["ORIENTED_EDGE"], 24);
+        assert_eq!(histogram["LENGTH_UNIT"], 1);
+        assert!(!histogram.contains_key(""));
+    }
+
+    #[test]
+    fn test_reference_keywords() {
+        let source = include_str!("../../../test_data/minimal-structure.stp");
+        let step_data = StepData::from_str(source).unwrap();
+
+        assert_eq!(
+            step_data.reference_keywords(20),
+            vec![
+                (15, "PRODUCT_DEFINITION_SHAPE".to_owned()),
+                (19, "SHAPE_REPRESENTATION".to_owned())
+            ]
+        );
+        assert!(step_data.reference_keywords(999_999).is_empty());
+
+        // repeated and unresolved references
+        let step_data =
+            StepData::from_str("ISO-10303-21; DATA; #1=FOO(#2,(#2,#3)); #2=BAR(); ENDSEC;")
+                .unwrap();
+        assert_eq!(step_data.reference_keywords(1), vec![(2, "BAR".to_owned())]);
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let step_data = StepData::from_str(
+            "ISO-10303-21; DATA; #1=FOO(#2,#7); #2=BAR((#3,#1)); #3=BAZ(#9,'#8'); ENDSEC;",
+        )
+        .unwrap();
+        assert_eq!(step_data.dangling_references(), vec![(1, 7), (3, 9)]);
+
+        let step_data = StepData::from_str(include_str!("../../../test_data/cube.stp")).unwrap();
+        assert!(step_data.dangling_references().is_empty());
+    }
+
     #[test]
     fn test_get_references_simple() {
         let entry = StepEntry::new(1, "IFCFOO('FOO', #2);");
@@ -335,6 +1777,50 @@ mod test {
         assert_eq!(entry.get_references(), vec![2, 3]);
     }
 
+    #[test]
+    fn test_get_references_escaped_quotes() {
+        let entry = StepEntry::new(1, "PRODUCT('it''s a part #1','',#5)");
+        assert_eq!(entry.get_references(), vec![5]);
+
+        let entry = StepEntry::new(1, "FOO('''#2''','#3'#4,'',#6)");
+        assert_eq!(entry.get_references(), vec![4, 6]);
+
+        let entry = StepEntry::new(1, "FOO('Müller #2',#3,'unterminated #4");
+        assert_eq!(entry.get_references(), vec![3]);
+    }
+
+    #[test]
+    fn test_get_references_trailing() {
+        let entry = StepEntry::new(1, "FOO(#12,#42");
+        assert_eq!(entry.get_references(), vec![12, 42]);
+
+        let entry = StepEntry::new(1, "FOO(#12,#42");
+        let entry = entry.update_references(|id| id + 1);
+        assert_eq!(entry.get_definition(), "FOO(#13,#43");
+    }
+
+    #[test]
+    fn test_invalid_references() {
+        let entry = StepEntry::new(1, "FOO(#99999999999999999999,#2,#)");
+
+        assert_eq!(entry.get_references(), vec![2]);
+        assert!(matches!(
+            entry.try_get_references(),
+            Err(Error::InvalidNumber(s)) if s == "#99999999999999999999"
+        ));
+        assert!(matches!(
+            StepEntry::new(1, "FOO(#2,#)").try_get_references(),
+            Err(Error::InvalidNumber(s)) if s == "#"
+        ));
+
+        let updated = entry.update_references(|id| id + 1);
+        assert_eq!(updated.get_definition(), "FOO(#99999999999999999999,#3,#)");
+        assert!(matches!(
+            entry.try_update_references(|id| id + 1),
+            Err(Error::InvalidNumber(_))
+        ));
+    }
+
     #[test]
     fn test_get_reference_complex() {
         let entry = StepEntry::new(1, "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#9531))GLOBAL_UNIT_ASSIGNED_CONTEXT((#8,#9,#7))REPRESENTATION_CONTEXT('',''));");
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{StepData, StepEntry};
+
+/// A navigable graph representation of the STEP data. The model is built once from the parsed
+/// `StepData` and holds an id index as well as the reference and back-reference indices between
+/// the entries.
+#[derive(Default)]
+pub struct StepModel {
+    /// The entries in the order they appear in the STEP file.
+    entries: Vec<StepEntry>,
+
+    /// Maps the id of an entry to its index in the entries list.
+    index: HashMap<u64, usize>,
+
+    /// The references of each entry, stored at the index of the entry.
+    references: Vec<Vec<u64>>,
+
+    /// Maps the id of an entry to the ids of the entries referencing it.
+    referrers: HashMap<u64, Vec<u64>>,
+}
+
+impl StepModel {
+    /// Creates a new model by consuming the given step data.
+    ///
+    /// # Arguments
+    /// * `step_data` - The step data to build the model from.
+    pub fn new(step_data: StepData) -> StepModel {
+        let entries = step_data.entries;
+
+        let mut index = HashMap::with_capacity(entries.len());
+        let mut references = Vec::with_capacity(entries.len());
+        let mut referrers: HashMap<u64, Vec<u64>> = HashMap::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            index.insert(entry.get_id(), i);
+
+            let entry_references = entry.get_references();
+            for reference in entry_references.iter() {
+                referrers
+                    .entry(*reference)
+                    .or_default()
+                    .push(entry.get_id());
+            }
+
+            references.push(entry_references);
+        }
+
+        StepModel {
+            entries,
+            index,
+            references,
+            referrers,
+        }
+    }
+
+    /// Returns the entries of the model in the order they appear in the STEP file.
+    pub fn get_entries(&self) -> &[StepEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry with the given id or None if no such entry exists.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the entry.
+    pub fn get(&self, id: u64) -> Option<&StepEntry> {
+        self.index.get(&id).map(|i| &self.entries[*i])
+    }
+
+    /// Returns the ids referenced by the entry with the given id. Returns an empty list if no
+    /// such entry exists.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the entry.
+    pub fn references(&self, id: u64) -> &[u64] {
+        match self.index.get(&id) {
+            Some(i) => &self.references[*i],
+            None => &[],
+        }
+    }
+
+    /// Returns the ids of the entries referencing the entry with the given id.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the referenced entry.
+    pub fn referrers(&self, id: u64) -> &[u64] {
+        match self.referrers.get(&id) {
+            Some(referrers) => referrers,
+            None => &[],
+        }
+    }
+
+    /// Returns the ids of all entries that are transitively reachable from the entry with the
+    /// given id, including the id itself. References to undefined entries are skipped.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the entry to start from.
+    pub fn closure(&self, id: u64) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            if !self.index.contains_key(&id) || !visited.insert(id) {
+                continue;
+            }
+
+            result.push(id);
+            stack.extend(self.references(id).iter().rev());
+        }
+
+        result
+    }
+
+    /// Returns an iterator over all entries with the given keyword.
+    ///
+    /// # Arguments
+    /// * `keyword` - The keyword of the entries, e.g., `PRODUCT_DEFINITION`.
+    pub fn keyword_entries<'a>(
+        &'a self,
+        keyword: &'a str,
+    ) -> impl Iterator<Item = &'a StepEntry> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.keyword() == keyword)
+    }
+}
+
+impl From<StepData> for StepModel {
+    fn from(step_data: StepData) -> Self {
+        StepModel::new(step_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn load_minimal_structure() -> StepModel {
+        let source = include_str!("../../../test_data/minimal-structure.stp");
+        StepModel::new(StepData::from_str(source).unwrap())
+    }
+
+    #[test]
+    fn test_navigate_shape_definition_representation() {
+        let model = load_minimal_structure();
+
+        let shape_def_reps: Vec<&StepEntry> = model
+            .keyword_entries("SHAPE_DEFINITION_REPRESENTATION")
+            .collect();
+        assert_eq!(shape_def_reps.len(), 3);
+
+        let shape_def_rep = model.get(20).unwrap();
+        let references = model.references(shape_def_rep.get_id());
+        assert_eq!(references, &[15, 19]);
+
+        let shape_rep = model.get(references[1]).unwrap();
+        assert_eq!(shape_rep.keyword(), "SHAPE_REPRESENTATION");
+        assert_eq!(shape_rep.get_id(), 19);
+
+        let product_definition_shape = model.get(references[0]).unwrap();
+        assert_eq!(
+            product_definition_shape.get_definition(),
+            "PRODUCT_DEFINITION_SHAPE('',$,#14)"
+        );
+    }
+
+    #[test]
+    fn test_referrers() {
+        let model = load_minimal_structure();
+
+        assert_eq!(model.referrers(19), &[20, 3006]);
+        assert_eq!(model.referrers(3009), &[] as &[u64]);
+        assert!(model.get(4711).is_none());
+    }
+
+    #[test]
+    fn test_closure() {
+        let model = load_minimal_structure();
+
+        let closure = model.closure(6);
+        assert_eq!(closure, vec![6, 3, 4, 5]);
+
+        let mut closure = model.closure(19);
+        closure.sort();
+        assert_eq!(closure, vec![3, 4, 5, 6, 7, 8, 9, 17, 18, 19]);
+
+        assert!(model.closure(4711).is_empty());
+    }
+}
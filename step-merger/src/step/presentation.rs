@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use super::StepEntry;
+
+/// The keywords of the presentation entities, e.g., colors and styles. The entities referencing
+/// them are determined by the [`PresentationFilter`].
+const PRESENTATION_KEYWORDS: &[&str] = &[
+    "COLOUR_RGB",
+    "CONTEXT_DEPENDENT_OVER_RIDING_STYLED_ITEM",
+    "CURVE_STYLE",
+    "DRAUGHTING_MODEL",
+    "DRAUGHTING_PRE_DEFINED_COLOUR",
+    "DRAUGHTING_PRE_DEFINED_CURVE_FONT",
+    "FILL_AREA_STYLE",
+    "FILL_AREA_STYLE_COLOUR",
+    "INVISIBILITY",
+    "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION",
+    "OVER_RIDING_STYLED_ITEM",
+    "POINT_STYLE",
+    "PRESENTATION_LAYER_ASSIGNMENT",
+    "PRESENTATION_STYLE_ASSIGNMENT",
+    "PRESENTATION_STYLE_BY_CONTEXT",
+    "STYLED_ITEM",
+    "SURFACE_SIDE_STYLE",
+    "SURFACE_STYLE_FILL_AREA",
+    "SURFACE_STYLE_RENDERING",
+    "SURFACE_STYLE_RENDERING_WITH_PROPERTIES",
+    "SURFACE_STYLE_TRANSPARENT",
+    "SURFACE_STYLE_USAGE",
+];
+
+/// Returns true if the given entry is a presentation entity, e.g., a color or a style. For
+/// complex instances, any presentation partial instance is sufficient.
+///
+/// # Arguments
+/// * `entry` - The entry to be checked.
+fn is_presentation_entry(entry: &StepEntry) -> bool {
+    entry
+        .keywords()
+        .into_iter()
+        .any(|keyword| PRESENTATION_KEYWORDS.contains(&keyword))
+}
+
+/// Determines the presentation entries of a step file, i.e., the presentation entities and all
+/// entries referring to them directly or indirectly, e.g., a SURFACE_STYLE_BOUNDARY referring to a
+/// CURVE_STYLE. Dropping all of them does not leave references to dropped entries.
+#[derive(Default)]
+pub(crate) struct PresentationFilter {
+    /// The ids of the presentation entities.
+    presentation_ids: Vec<u64>,
+
+    /// The ids of the entries referring to each id.
+    referrers: HashMap<u64, Vec<u64>>,
+}
+
+impl PresentationFilter {
+    /// Creates a new empty filter.
+    pub fn new() -> Self {
+        PresentationFilter::default()
+    }
+
+    /// Records the given entry of the step file.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to be recorded.
+    pub fn add_entry(&mut self, entry: &StepEntry) {
+        if is_presentation_entry(entry) {
+            self.presentation_ids.push(entry.get_id());
+        }
+
+        for reference in entry.get_references() {
+            self.referrers
+                .entry(reference)
+                .or_default()
+                .push(entry.get_id());
+        }
+    }
+
+    /// Returns the ids of the entries to be dropped for all recorded entries.
+    pub fn into_dropped_ids(self) -> HashSet<u64> {
+        let mut dropped: HashSet<u64> = HashSet::new();
+        let mut stack = self.presentation_ids;
+        while let Some(id) = stack.pop() {
+            if dropped.insert(id) {
+                if let Some(referrers) = self.referrers.get(&id) {
+                    stack.extend(referrers);
+                }
+            }
+        }
+
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presentation_filter() {
+        let mut filter = PresentationFilter::new();
+        for (id, definition) in [
+            (1, "CARTESIAN_POINT('',(0.,0.,0.))"),
+            (2, "COLOUR_RGB('',1.,0.,0.)"),
+            (3, "CURVE_STYLE('',$,POSITIVE_LENGTH_MEASURE(0.1),#2)"),
+            (4, "SURFACE_STYLE_BOUNDARY(#3)"),
+            (5, "FOO(#4,#1)"),
+            (6, "BAR(#1)"),
+        ] {
+            filter.add_entry(&StepEntry::new(id, definition));
+        }
+
+        let dropped = filter.into_dropped_ids();
+        assert_eq!(dropped, HashSet::from([2, 3, 4, 5]));
+    }
+}
@@ -0,0 +1,344 @@
+/// The entities shared by the supported application protocols, sorted alphabetically. The list
+/// covers the entities emitted by the merger and the common geometry, topology and presentation
+/// entities, but not the complete schemas.
+const COMMON_ENTITIES: &[&str] = &[
+    "ADVANCED_BREP_SHAPE_REPRESENTATION",
+    "ADVANCED_FACE",
+    "APPLICATION_CONTEXT",
+    "APPLICATION_PROTOCOL_DEFINITION",
+    "APPLIED_ORGANIZATION_ASSIGNMENT",
+    "APPROVAL",
+    "APPROVAL_STATUS",
+    "AREA_UNIT",
+    "ASSEMBLY_COMPONENT_USAGE",
+    "AXIS1_PLACEMENT",
+    "AXIS2_PLACEMENT_2D",
+    "AXIS2_PLACEMENT_3D",
+    "BEZIER_CURVE",
+    "BEZIER_SURFACE",
+    "BOUNDED_CURVE",
+    "BOUNDED_SURFACE",
+    "BREP_WITH_VOIDS",
+    "B_SPLINE_CURVE",
+    "B_SPLINE_CURVE_WITH_KNOTS",
+    "B_SPLINE_SURFACE",
+    "B_SPLINE_SURFACE_WITH_KNOTS",
+    "CALENDAR_DATE",
+    "CARTESIAN_POINT",
+    "CARTESIAN_TRANSFORMATION_OPERATOR_3D",
+    "CIRCLE",
+    "CLOSED_SHELL",
+    "COLOUR_RGB",
+    "COMPOSITE_CURVE",
+    "COMPOSITE_CURVE_SEGMENT",
+    "CONICAL_SURFACE",
+    "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION",
+    "CONVERSION_BASED_UNIT",
+    "COORDINATED_UNIVERSAL_TIME_OFFSET",
+    "CURVE",
+    "CURVE_STYLE",
+    "CYLINDRICAL_SURFACE",
+    "DATE_AND_TIME",
+    "DEFINITIONAL_REPRESENTATION",
+    "DEGENERATE_TOROIDAL_SURFACE",
+    "DERIVED_UNIT",
+    "DERIVED_UNIT_ELEMENT",
+    "DESCRIPTIVE_REPRESENTATION_ITEM",
+    "DIMENSIONAL_EXPONENTS",
+    "DIRECTION",
+    "DRAUGHTING_MODEL",
+    "DRAUGHTING_PRE_DEFINED_COLOUR",
+    "DRAUGHTING_PRE_DEFINED_CURVE_FONT",
+    "EDGE_CURVE",
+    "EDGE_LOOP",
+    "ELLIPSE",
+    "FACE_BOUND",
+    "FACE_OUTER_BOUND",
+    "FACE_SURFACE",
+    "FILL_AREA_STYLE",
+    "FILL_AREA_STYLE_COLOUR",
+    "GEOMETRICALLY_BOUNDED_WIREFRAME_SHAPE_REPRESENTATION",
+    "GEOMETRIC_CURVE_SET",
+    "GEOMETRIC_REPRESENTATION_CONTEXT",
+    "GEOMETRIC_REPRESENTATION_ITEM",
+    "GEOMETRIC_SET",
+    "GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT",
+    "GLOBAL_UNIT_ASSIGNED_CONTEXT",
+    "HYPERBOLA",
+    "INVISIBILITY",
+    "ITEM_DEFINED_TRANSFORMATION",
+    "LENGTH_MEASURE_WITH_UNIT",
+    "LENGTH_UNIT",
+    "LINE",
+    "LOCAL_TIME",
+    "MANIFOLD_SOLID_BREP",
+    "MANIFOLD_SURFACE_SHAPE_REPRESENTATION",
+    "MAPPED_ITEM",
+    "MASS_MEASURE_WITH_UNIT",
+    "MASS_UNIT",
+    "MEASURE_REPRESENTATION_ITEM",
+    "MEASURE_WITH_UNIT",
+    "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION",
+    "NAMED_UNIT",
+    "NEXT_ASSEMBLY_USAGE_OCCURRENCE",
+    "OFFSET_SURFACE",
+    "OPEN_SHELL",
+    "ORGANIZATION",
+    "ORGANIZATION_ROLE",
+    "ORIENTED_CLOSED_SHELL",
+    "ORIENTED_EDGE",
+    "ORIENTED_FACE",
+    "OVER_RIDING_STYLED_ITEM",
+    "PARABOLA",
+    "PCURVE",
+    "PERSON",
+    "PERSON_AND_ORGANIZATION",
+    "PERSON_AND_ORGANIZATION_ROLE",
+    "PLANE",
+    "PLANE_ANGLE_MEASURE_WITH_UNIT",
+    "PLANE_ANGLE_UNIT",
+    "POLYLINE",
+    "POLY_LOOP",
+    "PRESENTATION_LAYER_ASSIGNMENT",
+    "PRESENTATION_STYLE_ASSIGNMENT",
+    "PRODUCT",
+    "PRODUCT_CATEGORY",
+    "PRODUCT_CATEGORY_RELATIONSHIP",
+    "PRODUCT_CONTEXT",
+    "PRODUCT_DEFINITION",
+    "PRODUCT_DEFINITION_CONTEXT",
+    "PRODUCT_DEFINITION_FORMATION",
+    "PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE",
+    "PRODUCT_DEFINITION_SHAPE",
+    "PRODUCT_RELATED_PRODUCT_CATEGORY",
+    "PROPERTY_DEFINITION",
+    "PROPERTY_DEFINITION_REPRESENTATION",
+    "QUASI_UNIFORM_CURVE",
+    "QUASI_UNIFORM_SURFACE",
+    "RATIONAL_B_SPLINE_CURVE",
+    "RATIONAL_B_SPLINE_SURFACE",
+    "RECTANGULAR_TRIMMED_SURFACE",
+    "REPRESENTATION",
+    "REPRESENTATION_CONTEXT",
+    "REPRESENTATION_ITEM",
+    "REPRESENTATION_MAP",
+    "REPRESENTATION_RELATIONSHIP",
+    "REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION",
+    "SEAM_CURVE",
+    "SECURITY_CLASSIFICATION",
+    "SECURITY_CLASSIFICATION_LEVEL",
+    "SHAPE_ASPECT",
+    "SHAPE_DEFINITION_REPRESENTATION",
+    "SHAPE_REPRESENTATION",
+    "SHAPE_REPRESENTATION_RELATIONSHIP",
+    "SHELL_BASED_SURFACE_MODEL",
+    "SI_UNIT",
+    "SOLID_ANGLE_UNIT",
+    "SPHERICAL_SURFACE",
+    "STYLED_ITEM",
+    "SURFACE",
+    "SURFACE_CURVE",
+    "SURFACE_OF_LINEAR_EXTRUSION",
+    "SURFACE_OF_REVOLUTION",
+    "SURFACE_SIDE_STYLE",
+    "SURFACE_STYLE_FILL_AREA",
+    "SURFACE_STYLE_USAGE",
+    "TOROIDAL_SURFACE",
+    "TRIMMED_CURVE",
+    "UNCERTAINTY_MEASURE_WITH_UNIT",
+    "UNIFORM_CURVE",
+    "UNIFORM_SURFACE",
+    "VALUE_REPRESENTATION_ITEM",
+    "VECTOR",
+    "VERTEX_LOOP",
+    "VERTEX_POINT",
+    "VOLUME_UNIT",
+];
+
+/// The entities only defined by AP203, sorted alphabetically, i.e., the assignments of the
+/// configuration controlled design and the change management.
+const AP203_ENTITIES: &[&str] = &[
+    "CC_DESIGN_APPROVAL",
+    "CC_DESIGN_CERTIFICATION",
+    "CC_DESIGN_CONTRACT",
+    "CC_DESIGN_DATE_AND_TIME_ASSIGNMENT",
+    "CC_DESIGN_PERSON_AND_ORGANIZATION_ASSIGNMENT",
+    "CC_DESIGN_SECURITY_CLASSIFICATION",
+    "CC_DESIGN_SPECIFICATION_REFERENCE",
+    "CHANGE",
+    "CHANGE_REQUEST",
+    "START_REQUEST",
+    "START_WORK",
+];
+
+/// The entities only defined by AP214, sorted alphabetically, i.e., the generic assignments
+/// replacing the ones of the configuration controlled design.
+const AP214_ENTITIES: &[&str] = &[
+    "APPLIED_APPROVAL_ASSIGNMENT",
+    "APPLIED_DATE_AND_TIME_ASSIGNMENT",
+    "APPLIED_DOCUMENT_REFERENCE",
+    "APPLIED_PERSON_AND_ORGANIZATION_ASSIGNMENT",
+    "APPLIED_SECURITY_CLASSIFICATION_ASSIGNMENT",
+    "DATE_TIME_ROLE",
+    "DOCUMENT",
+    "DOCUMENT_TYPE",
+];
+
+/// The entities only defined by AP242, sorted alphabetically, i.e., the generic assignments of
+/// AP214 and the tessellated geometry.
+const AP242_ENTITIES: &[&str] = &[
+    "APPLIED_APPROVAL_ASSIGNMENT",
+    "APPLIED_DATE_AND_TIME_ASSIGNMENT",
+    "APPLIED_DOCUMENT_REFERENCE",
+    "APPLIED_PERSON_AND_ORGANIZATION_ASSIGNMENT",
+    "APPLIED_SECURITY_CLASSIFICATION_ASSIGNMENT",
+    "COMPLEX_TRIANGULATED_FACE",
+    "COORDINATES_LIST",
+    "DATE_TIME_ROLE",
+    "DOCUMENT",
+    "DOCUMENT_TYPE",
+    "TESSELLATED_SHAPE_REPRESENTATION",
+    "TESSELLATED_SHELL",
+    "TESSELLATED_SOLID",
+    "TRIANGULATED_FACE",
+];
+
+/// The application protocols that can be targeted when emitting STEP data.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ApplicationProtocol {
+    /// AP203 edition 2, configuration controlled 3D design of mechanical parts and assemblies.
+    #[default]
+    AP203,
+
+    /// AP214, core data for automotive mechanical design processes.
+    AP214,
+
+    /// AP242, managed model based 3D engineering.
+    AP242,
+
+    /// A custom schema string written to FILE_SCHEMA, e.g., for a specific edition of a
+    /// protocol. All other entries are emitted as for AP203.
+    Custom(String),
+}
+
+impl ApplicationProtocol {
+    /// Returns the schema string being written to the FILE_SCHEMA header entry.
+    pub fn schema(&self) -> &str {
+        match self {
+            ApplicationProtocol::AP203 => "AP203_CONFIGURATION_CONTROLLED_3D_DESIGN_OF_MECHANICAL_PARTS_AND_ASSEMBLIES_MIM_LF { 1 0 10303 403 1 1 4 }",
+            ApplicationProtocol::AP214 => "AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }",
+            ApplicationProtocol::AP242 => "AP242_MANAGED_MODEL_BASED_3D_ENGINEERING_MIM_LF { 1 0 10303 442 1 1 4 }",
+            ApplicationProtocol::Custom(schema) => schema,
+        }
+    }
+
+    /// Checks if the entity with the given keyword is known to the protocol. The supported
+    /// protocols share a common subset of entities and add their own ones. As the entities of a
+    /// custom schema are unknown, any entity is accepted for it.
+    ///
+    /// # Arguments
+    /// * `keyword` - The keyword of the entity, e.g., `CARTESIAN_POINT`.
+    pub fn is_known_entity(&self, keyword: &str) -> bool {
+        let entities = match self {
+            ApplicationProtocol::AP203 => AP203_ENTITIES,
+            ApplicationProtocol::AP214 => AP214_ENTITIES,
+            ApplicationProtocol::AP242 => AP242_ENTITIES,
+            ApplicationProtocol::Custom(_) => return true,
+        };
+
+        COMMON_ENTITIES.binary_search(&keyword).is_ok() || entities.binary_search(&keyword).is_ok()
+    }
+
+    /// Returns the definition of the APPLICATION_CONTEXT entry.
+    pub fn application_context(&self) -> &'static str {
+        match self {
+            ApplicationProtocol::AP203 | ApplicationProtocol::Custom(_) => "APPLICATION_CONTEXT('Configuration controlled 3D designs of mechanical parts and assemblies')",
+            ApplicationProtocol::AP214 => "APPLICATION_CONTEXT('core data for automotive mechanical design processes')",
+            ApplicationProtocol::AP242 => "APPLICATION_CONTEXT('managed model based 3d engineering')",
+        }
+    }
+
+    /// Returns the definition of the APPLICATION_PROTOCOL_DEFINITION entry for the given
+    /// APPLICATION_CONTEXT id.
+    ///
+    /// # Arguments
+    /// * `app_context_id` - The id of the APPLICATION_CONTEXT entry.
+    pub fn application_protocol_definition(&self, app_context_id: u64) -> String {
+        match self {
+            ApplicationProtocol::AP203 | ApplicationProtocol::Custom(_) => format!("APPLICATION_PROTOCOL_DEFINITION('international standard', 'configuration_control_3d_design_ed2_mim',2004, #{})", app_context_id),
+            ApplicationProtocol::AP214 => format!("APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{})", app_context_id),
+            ApplicationProtocol::AP242 => format!("APPLICATION_PROTOCOL_DEFINITION('international standard','ap242_managed_model_based_3d_engineering',2014,#{})", app_context_id),
+        }
+    }
+
+    /// Returns the definition of the PRODUCT_DEFINITION_CONTEXT entry for the given
+    /// APPLICATION_CONTEXT id.
+    ///
+    /// # Arguments
+    /// * `app_context_id` - The id of the APPLICATION_CONTEXT entry.
+    pub fn product_definition_context(&self, app_context_id: u64) -> String {
+        match self {
+            ApplicationProtocol::AP203 | ApplicationProtocol::Custom(_) => format!(
+                "PRODUCT_DEFINITION_CONTEXT('part_definition',#{},'')",
+                app_context_id
+            ),
+            ApplicationProtocol::AP214 | ApplicationProtocol::AP242 => format!(
+                "PRODUCT_DEFINITION_CONTEXT('part definition',#{},'design')",
+                app_context_id
+            ),
+        }
+    }
+
+    /// Returns the definition of the PRODUCT_RELATED_PRODUCT_CATEGORY entry for the given
+    /// PRODUCT id.
+    ///
+    /// # Arguments
+    /// * `product_id` - The id of the PRODUCT entry.
+    pub fn product_category(&self, product_id: u64) -> String {
+        match self {
+            ApplicationProtocol::AP203 | ApplicationProtocol::Custom(_) => format!(
+                "PRODUCT_RELATED_PRODUCT_CATEGORY('component','',(#{}))",
+                product_id
+            ),
+            ApplicationProtocol::AP214 | ApplicationProtocol::AP242 => format!(
+                "PRODUCT_RELATED_PRODUCT_CATEGORY('part',$,(#{}))",
+                product_id
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_known_entity() {
+        for entities in [
+            COMMON_ENTITIES,
+            AP203_ENTITIES,
+            AP214_ENTITIES,
+            AP242_ENTITIES,
+        ] {
+            assert!(entities.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        let protocol = ApplicationProtocol::AP214;
+        assert!(protocol.is_known_entity("CARTESIAN_POINT"));
+        assert!(protocol.is_known_entity("NEXT_ASSEMBLY_USAGE_OCCURRENCE"));
+        assert!(!protocol.is_known_entity("CARTESIAN_PIONT"));
+        assert!(!protocol.is_known_entity(""));
+
+        // the entities of one schema are rejected under another
+        assert!(ApplicationProtocol::AP203.is_known_entity("CC_DESIGN_APPROVAL"));
+        assert!(!ApplicationProtocol::AP214.is_known_entity("CC_DESIGN_APPROVAL"));
+        assert!(ApplicationProtocol::AP242.is_known_entity("TRIANGULATED_FACE"));
+        assert!(!ApplicationProtocol::AP214.is_known_entity("TRIANGULATED_FACE"));
+        assert!(!ApplicationProtocol::AP203.is_known_entity("APPLIED_DATE_AND_TIME_ASSIGNMENT"));
+
+        // custom schemas accept any entity
+        let custom = ApplicationProtocol::Custom("CONFIG_CONTROL_DESIGN".to_owned());
+        assert!(custom.is_known_entity("CC_DESIGN_APPROVAL"));
+        assert!(custom.is_known_entity("CARTESIAN_PIONT"));
+    }
+}
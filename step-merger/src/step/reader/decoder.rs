@@ -0,0 +1,142 @@
+use std::io::{self, Read};
+
+use log::warn;
+
+/// The number of bytes being inspected to detect the encoding.
+const SNIFF_SIZE: usize = 64 * 1024;
+
+/// The byte order mark of UTF-8.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The byte encodings of the read text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Latin1,
+}
+
+/// A reader that detects the byte encoding of the source and provides the read text as UTF-8.
+/// A UTF-8 byte order mark is skipped. If the beginning of the source is not valid UTF-8, the
+/// source is decoded as latin-1.
+pub struct DecodingReader<R: Read> {
+    /// The source for reading new bytes.
+    reader: R,
+
+    /// The detected encoding or None if the encoding has not been detected yet.
+    encoding: Option<TextEncoding>,
+
+    /// The decoded bytes that have not been returned yet.
+    pending: Vec<u8>,
+
+    /// The position of the first pending byte that has not been returned yet.
+    pending_pos: usize,
+}
+
+impl<R: Read> DecodingReader<R> {
+    /// Creates a new decoding reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read from.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            encoding: None,
+            pending: Vec::new(),
+            pending_pos: 0,
+        }
+    }
+
+    /// Reads the beginning of the source and detects its encoding.
+    fn detect_encoding(&mut self) -> io::Result<TextEncoding> {
+        let mut data = Vec::with_capacity(SNIFF_SIZE);
+        (&mut self.reader)
+            .take(SNIFF_SIZE as u64)
+            .read_to_end(&mut data)?;
+
+        if data.starts_with(UTF8_BOM) {
+            data.drain(..UTF8_BOM.len());
+            self.pending = data;
+            return Ok(TextEncoding::Utf8);
+        }
+
+        match std::str::from_utf8(&data) {
+            // a character may be cut off at the end of the inspected bytes
+            Err(e) if e.error_len().is_some() => {
+                warn!("Input is not valid UTF-8, falling back to latin-1");
+                self.pending = decode_latin1(&data);
+                Ok(TextEncoding::Latin1)
+            }
+            _ => {
+                self.pending = data;
+                Ok(TextEncoding::Utf8)
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => {
+                let encoding = self.detect_encoding()?;
+                self.encoding = Some(encoding);
+                encoding
+            }
+        };
+
+        if self.pending_pos == self.pending.len() {
+            match encoding {
+                TextEncoding::Utf8 => return self.reader.read(buf),
+                TextEncoding::Latin1 => {
+                    // every latin-1 byte results in at most two UTF-8 bytes
+                    let mut data = vec![0u8; buf.len().div_ceil(2)];
+                    let read = self.reader.read(&mut data)?;
+                    self.pending = decode_latin1(&data[..read]);
+                    self.pending_pos = 0;
+                }
+            }
+        }
+
+        let pending = &self.pending[self.pending_pos..];
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.pending_pos += n;
+
+        Ok(n)
+    }
+}
+
+/// Decodes the given latin-1 bytes into UTF-8 bytes.
+///
+/// # Arguments
+/// * `data` - The latin-1 encoded bytes.
+fn decode_latin1(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .map(|b| *b as char)
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn decode(data: &[u8]) -> String {
+        let mut result = String::new();
+        DecodingReader::new(Cursor::new(data))
+            .read_to_string(&mut result)
+            .unwrap();
+        result
+    }
+
+    #[test]
+    fn test_decoding_reader() {
+        assert_eq!(decode(b"M\xC3\xBCller"), "Müller");
+        assert_eq!(decode(b"\xEF\xBB\xBFM\xC3\xBCller"), "Müller");
+        assert_eq!(decode(b"M\xFCller"), "Müller");
+        assert_eq!(decode(b""), "");
+    }
+}
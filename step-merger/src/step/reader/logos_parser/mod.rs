@@ -6,22 +6,40 @@ mod stream_lexer;
 use buffered_reader::BufferedReader;
 use log::{debug, trace};
 
-use crate::{step::StepEntry, Error, Result};
+use crate::{
+    step::{StepEntry, StepHeader},
+    Error, Result,
+};
 
 use self::stream_lexer::{Token, TokenIterator};
 
-use super::STEPReaderTrait;
+use super::{
+    check_start_tag, check_zero_reference, decoder::DecodingReader, extract_comments,
+    ExternalReference, STEPReaderTrait,
+};
 
 /// The STEP reader consumes a reader and parses the STEP entries from it. All entries are returned
 /// as `StepEntry` instances in the order they appear in the file.
 /// The reader implements the `Iterator` trait and returns `Result<StepEntry>` instances s.t. the
 /// returned entries can be processed in a streaming fashion.
 pub struct STEPReader<R: Read> {
-    /// The internal buffer reader that reads from the input reader.
-    reader: BufferedReader<R>,
+    /// The internal buffer reader that reads from the decoded input reader.
+    reader: BufferedReader<DecodingReader<R>>,
 
     /// Indicates if the end of the data section has been reached.
     reached_end: bool,
+
+    /// The number of entries that have been read so far.
+    num_entries: usize,
+
+    /// The maximum number of entries to read or None if unlimited.
+    max_entries: Option<usize>,
+
+    /// The content of the HEADER section.
+    header: StepHeader,
+
+    /// The external references declared in the REFERENCE section.
+    external_references: Vec<ExternalReference>,
 }
 
 impl<R: Read> STEPReader<R> {
@@ -32,8 +50,8 @@ impl<R: Read> STEPReader<R> {
     ///
     /// # Arguments
     /// * `p` - The parser that tries to parse the element from the reader.
-    ///         The parser returns an error if the element could not be parsed and Some(T) if the
-    ///         element was successfully parsed.
+    ///   The parser returns an error if the element could not be parsed and Some(T) if the
+    ///   element was successfully parsed.
     fn parse_element<P, T>(&mut self, p: P) -> Result<T>
     where
         P: FnMut(&mut TokenIterator) -> Result<T>,
@@ -75,18 +93,22 @@ impl<R: Read> STEPReader<R> {
     /// formatted.
     fn parse_iso_line(&mut self) -> Result<()> {
         debug!("Parsing ISO line");
-        self.parse_element(|p| {
-            match p.next() {
-                Some(Ok(Token::StartTag)) => {}
+        let leading_comments = self.parse_element(|p| {
+            // the comments preceding the ISO line are kept as leading comments
+            let leading_comments = match p.next() {
+                Some(Ok(Token::StartTag)) => extract_comments(p.preceding_text()),
+                // another tag is only reported once it has been read completely, as indicated by
+                // a following token
                 Some(Ok(token)) => {
-                    return Err(Error::UnexpectedToken(
-                        "ISO-10303-21".to_string(),
-                        token.to_string(),
-                    ))
+                    let tag = token.to_string();
+                    return match p.next() {
+                        Some(_) => Ok(Err(tag)),
+                        None => Err(Error::EndOfInput()),
+                    };
                 }
                 Some(Err(err)) => return Err(err),
                 None => return Err(Error::EndOfInput()),
-            }
+            };
 
             match p.next() {
                 Some(Ok(Token::Sem)) => {}
@@ -97,32 +119,141 @@ impl<R: Read> STEPReader<R> {
                 None => return Err(Error::EndOfInput()),
             }
 
-            Ok(Some(()))
+            Ok(Ok(leading_comments))
         })?;
 
+        match leading_comments {
+            Ok(leading_comments) => self.header.leading_comments = leading_comments,
+            Err(tag) => check_start_tag(&tag)?,
+        }
+
         Ok(())
     }
 
-    /// Searches for the DATA section and fails if it is not found.
+    /// Searches for the DATA section and fails if it is not found. The entities of the HEADER
+    /// section and the declarations of a REFERENCE section are collected on the way.
     fn find_data_section(&mut self) -> Result<()> {
         debug!("Searching for DATA section");
-        self.parse_element(|p| {
+        let (header_definitions, external_references) = self.parse_element(|p| {
+            let mut header_definitions = Vec::new();
+            let mut external_references = Vec::new();
             loop {
                 match p.next() {
                     Some(Ok(Token::Data)) => break,
+                    Some(Ok(Token::Header)) => {
+                        Self::parse_header_section(p, &mut header_definitions)?
+                    }
+                    Some(Ok(Token::ReferenceSection)) => {
+                        Self::parse_reference_section(p, &mut external_references)?
+                    }
                     Some(Ok(_)) => {}
                     Some(Err(err)) => return Err(err),
                     None => return Err(Error::NoDataSection()),
                 }
             }
 
+            Self::expect_token(p, Token::Sem)?;
+
+            Ok((header_definitions, external_references))
+        })?;
+
+        self.header = StepHeader {
+            leading_comments: std::mem::take(&mut self.header.leading_comments),
+            ..StepHeader::from_definitions(&header_definitions)
+        };
+        self.external_references = external_references;
+
+        Ok(())
+    }
+
+    /// Parses the entities of the HEADER section up to and including its ENDSEC.
+    ///
+    /// # Arguments
+    /// * `p` - The token iterator positioned after the HEADER keyword.
+    /// * `header_definitions` - The list to which the definitions of the entities are added.
+    fn parse_header_section(
+        p: &mut TokenIterator,
+        header_definitions: &mut Vec<String>,
+    ) -> Result<()> {
+        Self::expect_token(p, Token::Sem)?;
+
+        loop {
             match p.next() {
-                Some(Ok(Token::Sem)) => Ok(()),
-                Some(Ok(token)) => Err(Error::UnexpectedToken(";".to_string(), token.to_string())),
-                Some(Err(err)) => Err(err),
-                None => Err(Error::EndOfInput()),
+                Some(Ok(Token::Endsec)) => break,
+                Some(Ok(Token::Definition(d))) => {
+                    let mut definition = d.to_owned();
+                    Self::read_definition(p, &mut definition)?;
+                    header_definitions.push(definition);
+                }
+                Some(Ok(token)) => {
+                    return Err(Error::UnexpectedToken(
+                        "header entity".to_string(),
+                        token.to_string(),
+                    ))
+                }
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::EndOfInput()),
             }
-        })
+        }
+
+        Self::expect_token(p, Token::Sem)
+    }
+
+    /// Parses the declarations of the REFERENCE section up to and including its ENDSEC.
+    ///
+    /// # Arguments
+    /// * `p` - The token iterator positioned after the REFERENCE keyword.
+    /// * `external_references` - The list to which the parsed declarations are added.
+    fn parse_reference_section(
+        p: &mut TokenIterator,
+        external_references: &mut Vec<ExternalReference>,
+    ) -> Result<()> {
+        Self::expect_token(p, Token::Sem)?;
+
+        loop {
+            let id = match p.next() {
+                Some(Ok(Token::Reference(id))) => id,
+                Some(Ok(Token::Endsec)) => break,
+                Some(Ok(token)) => {
+                    return Err(Error::UnexpectedToken("#".to_string(), token.to_string()))
+                }
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::EndOfInput()),
+            };
+
+            Self::expect_token(p, Token::Eq)?;
+            let uri = match p.next() {
+                Some(Ok(Token::Uri(uri))) => uri.to_owned(),
+                Some(Ok(token)) => {
+                    return Err(Error::UnexpectedToken("<".to_string(), token.to_string()))
+                }
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::EndOfInput()),
+            };
+            Self::expect_token(p, Token::Sem)?;
+
+            trace!("External reference #{}=<{}>", id, uri);
+            external_references.push(ExternalReference { id, uri });
+        }
+
+        Self::expect_token(p, Token::Sem)
+    }
+
+    /// Reads the next token and fails if it is not the expected one.
+    ///
+    /// # Arguments
+    /// * `p` - The token iterator to read from.
+    /// * `expected` - The expected token.
+    fn expect_token(p: &mut TokenIterator, expected: Token) -> Result<()> {
+        match p.next() {
+            Some(Ok(token)) if token == expected => Ok(()),
+            Some(Ok(token)) => Err(Error::UnexpectedToken(
+                expected.to_string(),
+                token.to_string(),
+            )),
+            Some(Err(err)) => Err(err),
+            None => Err(Error::EndOfInput()),
+        }
     }
 
     /// Reads the next STEP entry and returns none if the end of the section is reached.
@@ -163,28 +294,7 @@ impl<R: Read> STEPReader<R> {
 
             // parse the definition of the STEP entry
             let mut definition = String::new();
-            loop {
-                match p.next() {
-                    Some(Ok(Token::Sem)) => break,
-                    Some(Ok(Token::Whitespace)) => definition.push(' '),
-                    Some(Ok(Token::Comments)) => {}
-                    Some(Ok(Token::Definition(d))) => {
-                        definition.push_str(d);
-                    }
-                    Some(Ok(Token::Eq)) => definition.push('='),
-                    Some(Ok(Token::String(s))) => {
-                        definition.push('\'');
-                        definition.push_str(s);
-                        definition.push('\'');
-                    }
-                    Some(Ok(Token::Reference(r))) => definition.push_str(&format!("#{}", r)),
-                    Some(Ok(token)) => {
-                        return Err(Error::UnexpectedToken(";".to_string(), token.to_string()))
-                    }
-                    Some(Err(err)) => return Err(err),
-                    None => return Err(Error::EndOfInput()),
-                }
-            }
+            Self::read_definition(p, &mut definition)?;
 
             Ok(Some(StepEntry { id, definition }))
         })?;
@@ -193,6 +303,37 @@ impl<R: Read> STEPReader<R> {
 
         Ok(ret)
     }
+
+    /// Reads the tokens of a definition up to and including the terminating semicolon and
+    /// appends them to the given definition.
+    ///
+    /// # Arguments
+    /// * `p` - The token iterator to read from.
+    /// * `definition` - The definition to which the read tokens are appended.
+    fn read_definition(p: &mut TokenIterator, definition: &mut String) -> Result<()> {
+        loop {
+            match p.next() {
+                Some(Ok(Token::Sem)) => return Ok(()),
+                Some(Ok(Token::Whitespace)) => definition.push(' '),
+                Some(Ok(Token::Comments)) => {}
+                Some(Ok(Token::Definition(d))) => {
+                    definition.push_str(d);
+                }
+                Some(Ok(Token::Eq)) => definition.push('='),
+                Some(Ok(Token::String(s))) => {
+                    definition.push('\'');
+                    definition.push_str(s);
+                    definition.push('\'');
+                }
+                Some(Ok(Token::Reference(r))) => definition.push_str(&format!("#{}", r)),
+                Some(Ok(token)) => {
+                    return Err(Error::UnexpectedToken(";".to_string(), token.to_string()))
+                }
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::EndOfInput()),
+            }
+        }
+    }
 }
 
 impl<R: Read> Iterator for STEPReader<R> {
@@ -200,7 +341,21 @@ impl<R: Read> Iterator for STEPReader<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.read_next_entry() {
-            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(Some(entry)) => {
+                if let Err(err) = check_zero_reference(&entry) {
+                    return Some(Err(err));
+                }
+
+                self.num_entries += 1;
+                if let Some(max_entries) = self.max_entries {
+                    if self.num_entries > max_entries {
+                        self.reached_end = true;
+                        return Some(Err(Error::MaxEntriesExceeded(max_entries)));
+                    }
+                }
+
+                Some(Ok(entry))
+            }
             Ok(None) => None,
             Err(err) => Some(Err(err)),
         }
@@ -213,11 +368,15 @@ impl<R: Read> STEPReaderTrait<R> for STEPReader<R> {
     }
 
     fn new(reader: R) -> Result<Self> {
-        let reader = BufferedReader::new(reader);
+        let reader = BufferedReader::new(DecodingReader::new(reader));
 
         let mut step_parser = STEPReader {
             reader,
             reached_end: false,
+            num_entries: 0,
+            max_entries: None,
+            header: StepHeader::default(),
+            external_references: Vec::new(),
         };
 
         step_parser.parse_iso_line()?;
@@ -225,4 +384,20 @@ impl<R: Read> STEPReaderTrait<R> for STEPReader<R> {
 
         Ok(step_parser)
     }
+
+    fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+    }
+
+    fn set_normalize_whitespace(&mut self, _normalize_whitespace: bool) {
+        // the lexer skips the whitespace, i.e., the definitions are always normalized
+    }
+
+    fn header(&self) -> &StepHeader {
+        &self.header
+    }
+
+    fn get_external_references(&self) -> &[ExternalReference] {
+        &self.external_references
+    }
 }
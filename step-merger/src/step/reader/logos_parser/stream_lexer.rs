@@ -14,7 +14,7 @@ pub enum Token<'a> {
     Eq,
     #[token(";")]
     Sem,
-    #[regex(r"[#][\s]*[1-9][0-9]*", |lex| lex.slice()[1..].trim_start().parse::<u64>().unwrap())]
+    #[regex(r"[#][\s]*[0-9]+", |lex| lex.slice()[1..].trim_start().parse::<u64>().ok())]
     Reference(u64),
     #[token("HEADER")]
     Header,
@@ -22,12 +22,16 @@ pub enum Token<'a> {
     Data,
     #[token("ENDSEC")]
     Endsec,
+    #[token("REFERENCE")]
+    ReferenceSection,
     #[token("ISO-10303-21")]
     StartTag,
     #[token("END-ISO-10303-21")]
     EndTag,
-    #[regex(r"\'[^']*\'", |lex| lex.slice().trim_start_matches('\'').trim_end_matches('\''))]
+    #[regex(r"\'([^']|\'\')*\'", |lex| { let s = lex.slice(); &s[1..s.len() - 1] })]
     String(&'a str),
+    #[regex(r"<[^<>\s]*>", |lex| { let s = lex.slice(); &s[1..s.len() - 1] })]
+    Uri(&'a str),
     #[regex(r"[^\s;='/]+", |lex| lex.slice())]
     Definition(&'a str),
 }
@@ -43,10 +47,12 @@ impl<'a> Display for Token<'a> {
             Token::Header => write!(f, "HEADER"),
             Token::Data => write!(f, "DATA"),
             Token::Endsec => write!(f, "ENDSEC"),
+            Token::ReferenceSection => write!(f, "REFERENCE"),
             Token::StartTag => write!(f, "ISO-10303-21"),
             Token::EndTag => write!(f, "END-ISO-10303-21"),
             Token::Definition(s) => write!(f, "{}", s),
             Token::String(s) => write!(f, "'{}'", s),
+            Token::Uri(s) => write!(f, "<{}>", s),
         }
     }
 }
@@ -58,6 +64,12 @@ pub struct TokenIterator<'a> {
 
     /// The number of bytes consumed by the iterator.
     consumed_bytes: usize,
+
+    /// The source string being tokenized.
+    src: &'a str,
+
+    /// The byte offset at which the last returned token starts.
+    last_token_start: usize,
 }
 
 impl<'a> TokenIterator<'a> {
@@ -72,9 +84,17 @@ impl<'a> TokenIterator<'a> {
         Self {
             it,
             consumed_bytes: 0,
+            src,
+            last_token_start: 0,
         }
     }
 
+    /// Returns the source text preceding the last returned token, i.e., skipped whitespace and
+    /// comments as well as the previously returned tokens.
+    pub fn preceding_text(&self) -> &'a str {
+        &self.src[..self.last_token_start]
+    }
+
     /// Returns a reference to the internal consumed bytes counter.
     pub fn consumed_bytes(&self) -> usize {
         self.consumed_bytes
@@ -88,6 +108,7 @@ impl<'a> Iterator for TokenIterator<'a> {
         match self.it.next() {
             Some((Ok(token), span)) => {
                 self.consumed_bytes = span.end;
+                self.last_token_start = span.start;
                 Some(Ok(token))
             }
             Some((Err(_), _)) => Some(Err(Error::ParsingTokenError())),
@@ -142,12 +163,41 @@ mod test {
         assert!(tokens.next().is_none());
     }
 
+    #[test]
+    fn test_string_escaped_quotes() {
+        let mut tokens = TokenIterator::new("'It''s DATA;' ''");
+
+        assert_eq!(
+            Token::String("It''s DATA;"),
+            tokens.next().unwrap().unwrap()
+        );
+        assert_eq!(Token::String(""), tokens.next().unwrap().unwrap());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn test_uri() {
+        let mut tokens = TokenIterator::new("REFERENCE; #10=<http://host/part.stp#body>;");
+
+        assert_eq!(Token::ReferenceSection, tokens.next().unwrap().unwrap());
+        assert_eq!(Token::Sem, tokens.next().unwrap().unwrap());
+        assert_eq!(Token::Reference(10u64), tokens.next().unwrap().unwrap());
+        assert_eq!(Token::Eq, tokens.next().unwrap().unwrap());
+        assert_eq!(
+            Token::Uri("http://host/part.stp#body"),
+            tokens.next().unwrap().unwrap()
+        );
+        assert_eq!(Token::Sem, tokens.next().unwrap().unwrap());
+        assert!(tokens.next().is_none());
+    }
+
     #[test]
     fn test_reference() {
-        let mut tokens = TokenIterator::new("#1 # 2");
+        let mut tokens = TokenIterator::new("#1 # 2 #0");
 
         assert_eq!(Token::Reference(1u64), tokens.next().unwrap().unwrap());
         assert_eq!(Token::Reference(2u64), tokens.next().unwrap().unwrap());
+        assert_eq!(Token::Reference(0u64), tokens.next().unwrap().unwrap());
         assert!(tokens.next().is_none());
     }
 
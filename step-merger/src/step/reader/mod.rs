@@ -1,11 +1,38 @@
+mod decoder;
 mod logos_parser;
 mod plain_parser;
 
 use std::io::Read;
 
-use crate::Result;
+use crate::{Error, Result};
 
-use super::StepEntry;
+use super::{StepEntry, StepHeader};
+
+/// An external reference declared in the REFERENCE section of an edition 3 STEP file, e.g.,
+/// `#10=<part.stp#body>;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalReference {
+    /// The id of the entry instance that is defined externally.
+    pub id: u64,
+
+    /// The URI of the external entry instance without the enclosing angle brackets.
+    pub uri: String,
+}
+
+/// Checks the tag at the start of the file and returns an error naming the actual encoding if it
+/// is not `ISO-10303-21`, e.g., for the XML encoding of ISO 10303-28.
+///
+/// # Arguments
+/// * `tag` - The first token of the file after the leading comments.
+fn check_start_tag(tag: &str) -> Result<()> {
+    if tag == "ISO-10303-21" {
+        Ok(())
+    } else if tag.starts_with('<') {
+        Err(Error::UnsupportedEncoding("ISO-10303-28 (XML)".to_owned()))
+    } else {
+        Err(Error::UnsupportedEncoding(tag.to_owned()))
+    }
+}
 
 /// A trait for STEP readers.
 pub trait STEPReaderTrait<R: Read>: Sized + Iterator<Item = Result<StepEntry>> {
@@ -17,6 +44,30 @@ pub trait STEPReaderTrait<R: Read>: Sized + Iterator<Item = Result<StepEntry>> {
     /// # Arguments
     /// * `reader` - The reader to parse the STEP-data from.
     fn new(reader: R) -> Result<Self>;
+
+    /// Sets the maximum number of entries to parse. Once exceeded, the reader returns an error
+    /// instead of continuing. `None` means unlimited, which is the default.
+    ///
+    /// # Arguments
+    /// * `max_entries` - The maximum number of entries to parse.
+    fn set_max_entries(&mut self, max_entries: Option<usize>);
+
+    /// Enables or disables removing the insignificant whitespace outside of strings from the
+    /// definitions, e.g., `FOO( #5 , #6 )` is read as `FOO(#5,#6)`, which is the canonical form
+    /// of `StepData`. The logos reader always reads the canonical form. The plain reader replaces
+    /// each run of whitespace or comment by a single space unless enabled. Disabled by default.
+    ///
+    /// # Arguments
+    /// * `normalize_whitespace` - Flag to indicate if whitespace should be removed.
+    fn set_normalize_whitespace(&mut self, normalize_whitespace: bool);
+
+    /// Returns the content of the HEADER section, which has been parsed while searching for the
+    /// DATA section.
+    fn header(&self) -> &StepHeader;
+
+    /// Returns the external references declared in the REFERENCE section, which precedes the
+    /// DATA section in edition 3 STEP files.
+    fn get_external_references(&self) -> &[ExternalReference];
 }
 
 pub type STEPReaderPlain<R> = plain_parser::STEPReader<R>;
@@ -24,3 +75,59 @@ pub type STEPReaderLogos<R> = logos_parser::STEPReader<R>;
 
 /// A type alias for the default STEP reader.
 pub type STEPReader<R> = STEPReaderLogos<R>;
+
+/// Checks that the given entry neither has the id 0 nor references #0, which is invalid as ids
+/// start at 1.
+///
+/// # Arguments
+/// * `entry` - The entry to check.
+fn check_zero_reference(entry: &StepEntry) -> Result<()> {
+    if entry.get_id() == 0
+        || (entry.get_definition().contains("#0") && entry.get_references().contains(&0))
+    {
+        return Err(Error::ZeroReference(entry.get_id()));
+    }
+
+    Ok(())
+}
+
+/// Removes all whitespace outside of strings from the given definition.
+///
+/// # Arguments
+/// * `definition` - The definition to remove the whitespace from.
+fn remove_whitespace(definition: &str) -> String {
+    let mut inside_string = false;
+    definition
+        .chars()
+        .filter(|ch| {
+            // escaped quotes are handled as two consecutive strings
+            if *ch == '\'' {
+                inside_string = !inside_string;
+            }
+
+            inside_string || !ch.is_whitespace()
+        })
+        .collect()
+}
+
+/// Extracts the text of all comments in the given text, excluding the enclosing `/*` and `*/`.
+///
+/// # Arguments
+/// * `text` - The text to extract the comments from.
+fn extract_comments(text: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("/*") {
+        let comment = &rest[start + 2..];
+        match comment.find("*/") {
+            Some(end) => {
+                result.push(comment[..end].to_owned());
+                rest = &comment[end + 2..];
+            }
+            None => break,
+        }
+    }
+
+    result
+}
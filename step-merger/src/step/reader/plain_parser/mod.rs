@@ -2,9 +2,15 @@ use std::io::Read;
 
 use self::parser::Parser;
 
-use crate::{step::StepEntry, Error, Result};
+use crate::{
+    step::{StepEntry, StepHeader},
+    Error, Result,
+};
 
-use super::STEPReaderTrait;
+use super::{
+    check_start_tag, check_zero_reference, decoder::DecodingReader, remove_whitespace,
+    ExternalReference, STEPReaderTrait,
+};
 
 mod char_reader;
 mod parser;
@@ -15,19 +21,52 @@ mod tokenizer;
 /// The reader implements the `Iterator` trait and returns `Result<StepEntry>` instances s.t. the
 /// returned entries can be processed in a streaming fashion.
 pub struct STEPReader<R: Read> {
-    /// The parser used to parse the STEP entries.
-    parser: Parser<R>,
+    /// The parser used to parse the STEP entries from the decoded input reader.
+    parser: Parser<DecodingReader<R>>,
 
     /// Indicates if the end of the data section has been reached.
     reached_end: bool,
+
+    /// The number of entries that have been read so far.
+    num_entries: usize,
+
+    /// The maximum number of entries to read or None if unlimited.
+    max_entries: Option<usize>,
+
+    /// Indicates if the whitespace outside of strings is removed from the definitions.
+    normalize_whitespace: bool,
+
+    /// The content of the HEADER section.
+    header: StepHeader,
+
+    /// The external references declared in the REFERENCE section.
+    external_references: Vec<ExternalReference>,
 }
 
 impl<R: Read> STEPReader<R> {
+    /// Enables or disables collecting the text of the comments encountered while reading the
+    /// entries. Disabled by default.
+    ///
+    /// # Arguments
+    /// * `collect_comments` - Flag to indicate if comments should be collected.
+    pub fn set_collect_comments(&mut self, collect_comments: bool) {
+        self.parser.set_collect_comments(collect_comments);
+    }
+
+    /// Returns the comments that have been collected so far and clears the internal list.
+    pub fn take_comments(&mut self) -> Vec<String> {
+        self.parser.take_comments()
+    }
+
     /// Parses the initial ISO String 'ISO-10303-21' and fails if it is not found or not correctly
     /// formatted.
     fn parse_iso_line(&mut self) -> Result<()> {
-        self.parser.skip_whitespace_tokens()?;
-        self.parser.read_exact_sequence("ISO-10303-21")?;
+        // the comments preceding the ISO line are kept as leading comments
+        self.header.leading_comments = self.parser.read_whitespace_comments()?;
+        let tag = self
+            .parser
+            .read_string(|ch| !ch.is_whitespace() && ch != ';', false)?;
+        check_start_tag(&tag)?;
         self.parser.skip_whitespace_tokens()?;
         self.parser.read_exact_sequence(";")?;
 
@@ -49,7 +88,24 @@ impl<R: Read> STEPReader<R> {
                 break;
             }
 
-            let num_skipped = self.parser.skip_until(|ch| !ch.is_ascii_alphabetic())?;
+            if identifier == "HEADER" {
+                self.parse_header_section()?;
+                continue;
+            }
+
+            if identifier == "REFERENCE" {
+                self.parse_reference_section()?;
+                continue;
+            }
+
+            // strings are skipped as a whole, as they may contain the DATA keyword
+            let mut num_skipped = self
+                .parser
+                .skip_until(|ch| !ch.is_ascii_alphabetic() && ch != '\'')?;
+            if self.parser.skip_quoted_string()? {
+                num_skipped += 1;
+            }
+
             if num_skipped == 0 && identifier.is_empty() {
                 return Err(Error::NoDataSection());
             }
@@ -58,6 +114,66 @@ impl<R: Read> STEPReader<R> {
         Ok(())
     }
 
+    /// Parses the entities of the HEADER section up to and including its ENDSEC.
+    fn parse_header_section(&mut self) -> Result<()> {
+        self.parser.skip_whitespace_tokens()?;
+        self.parser.read_exact_sequence(";")?;
+
+        let mut header_definitions = Vec::new();
+        loop {
+            self.parser.skip_whitespace_tokens()?;
+            let definition = self.parser.read_definition()?;
+            self.parser.read_exact_sequence(";")?;
+
+            if definition.trim() == "ENDSEC" {
+                break;
+            }
+
+            header_definitions.push(definition);
+        }
+
+        self.header = StepHeader {
+            leading_comments: std::mem::take(&mut self.header.leading_comments),
+            ..StepHeader::from_definitions(&header_definitions)
+        };
+
+        Ok(())
+    }
+
+    /// Parses the declarations of the REFERENCE section up to and including its ENDSEC.
+    fn parse_reference_section(&mut self) -> Result<()> {
+        self.parser.skip_whitespace_tokens()?;
+        self.parser.read_exact_sequence(";")?;
+
+        loop {
+            self.parser.skip_whitespace_tokens()?;
+            let identifier = self
+                .parser
+                .read_string(|ch| ch.is_ascii_alphabetic(), false)?;
+            if identifier == "ENDSEC" {
+                break;
+            } else if !identifier.is_empty() {
+                return Err(Error::UnexpectedToken("#".to_string(), identifier));
+            }
+
+            self.parser.read_exact_sequence("#")?;
+            let id = self.parser.read_u64()?;
+            self.parser.skip_whitespace_tokens()?;
+            self.parser.read_exact_sequence("=")?;
+            self.parser.skip_whitespace_tokens()?;
+            self.parser.read_exact_sequence("<")?;
+            let uri = self.parser.read_string(|ch| ch != '>', false)?;
+            self.parser.read_exact_sequence(">")?;
+            self.parser.skip_whitespace_tokens()?;
+            self.parser.read_exact_sequence(";")?;
+
+            self.external_references.push(ExternalReference { id, uri });
+        }
+
+        self.parser.skip_whitespace_tokens()?;
+        self.parser.read_exact_sequence(";")
+    }
+
     /// Reads the next STEP entry and returns none if the end of the section is reached.
     /// Otherwise, returns the read STEP entry or an error if the input is invalid.
     fn read_next_entry(&mut self) -> Result<Option<StepEntry>> {
@@ -90,9 +206,13 @@ impl<R: Read> STEPReader<R> {
         self.parser.skip_whitespace_tokens()?;
         self.parser.read_exact_sequence("=")?;
         self.parser.skip_whitespace_tokens()?;
-        let definition = self.parser.read_string(|ch| ch != ';', true)?;
+        let mut definition = self.parser.read_definition()?;
         self.parser.read_exact_sequence(";")?;
 
+        if self.normalize_whitespace {
+            definition = remove_whitespace(&definition);
+        }
+
         Ok(Some(StepEntry { id, definition }))
     }
 }
@@ -102,7 +222,21 @@ impl<R: Read> Iterator for STEPReader<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.read_next_entry() {
-            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(Some(entry)) => {
+                if let Err(err) = check_zero_reference(&entry) {
+                    return Some(Err(err));
+                }
+
+                self.num_entries += 1;
+                if let Some(max_entries) = self.max_entries {
+                    if self.num_entries > max_entries {
+                        self.reached_end = true;
+                        return Some(Err(Error::MaxEntriesExceeded(max_entries)));
+                    }
+                }
+
+                Some(Ok(entry))
+            }
             Ok(None) => None,
             Err(err) => Some(Err(err)),
         }
@@ -116,8 +250,13 @@ impl<R: Read> STEPReaderTrait<R> for STEPReader<R> {
 
     fn new(reader: R) -> Result<Self> {
         let mut step_parser = STEPReader {
-            parser: Parser::new(reader),
+            parser: Parser::new(DecodingReader::new(reader)),
             reached_end: false,
+            num_entries: 0,
+            max_entries: None,
+            normalize_whitespace: false,
+            header: StepHeader::default(),
+            external_references: Vec::new(),
         };
 
         step_parser.parse_iso_line()?;
@@ -125,4 +264,20 @@ impl<R: Read> STEPReaderTrait<R> for STEPReader<R> {
 
         Ok(step_parser)
     }
+
+    fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+    }
+
+    fn set_normalize_whitespace(&mut self, normalize_whitespace: bool) {
+        self.normalize_whitespace = normalize_whitespace;
+    }
+
+    fn header(&self) -> &StepHeader {
+        &self.header
+    }
+
+    fn get_external_references(&self) -> &[ExternalReference] {
+        &self.external_references
+    }
 }
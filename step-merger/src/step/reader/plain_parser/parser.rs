@@ -12,6 +12,9 @@ use crate::{Error, Result};
 pub struct Parser<R: Read> {
     /// The tokenizer used to parse the input.
     tokenizer: Peekable<Tokenizer<CharReader<R>>>,
+
+    /// The collected comments or None if comments are not being collected.
+    comments: Option<Vec<String>>,
 }
 
 impl<R: Read> Parser<R> {
@@ -22,6 +25,43 @@ impl<R: Read> Parser<R> {
     pub fn new(reader: R) -> Self {
         Parser {
             tokenizer: Tokenizer::new(CharReader::new(reader)).peekable(),
+            comments: None,
+        }
+    }
+
+    /// Enables or disables collecting the text of the skipped comments. Disabled by default.
+    ///
+    /// # Arguments
+    /// * `collect_comments` - Flag to indicate if comments should be collected.
+    pub fn set_collect_comments(&mut self, collect_comments: bool) {
+        self.comments = if collect_comments {
+            Some(Vec::new())
+        } else {
+            None
+        };
+    }
+
+    /// Returns the comments that have been collected so far and clears the internal list.
+    pub fn take_comments(&mut self) -> Vec<String> {
+        match self.comments.as_mut() {
+            Some(comments) => std::mem::take(comments),
+            None => Vec::new(),
+        }
+    }
+
+    /// Consumes the next token and stores its text if it is a comment and comments are being
+    /// collected.
+    fn consume_token(&mut self) -> Result<()> {
+        match self.tokenizer.next() {
+            Some(Ok(Token::Comment(text))) => {
+                if let Some(comments) = self.comments.as_mut() {
+                    comments.push(text);
+                }
+
+                Ok(())
+            }
+            Some(Err(err)) => Err(err),
+            _ => Ok(()),
         }
     }
 
@@ -29,21 +69,31 @@ impl<R: Read> Parser<R> {
     pub fn skip_whitespace_tokens(&mut self) -> Result<()> {
         loop {
             match self.tokenizer.peek() {
-                Some(Ok(Token::Whitespace)) => {
-                    if let Some(Err(err)) = self.tokenizer.next() {
-                        return Err(err);
-                    }
-                }
-                Some(Ok(Token::Comment)) => {
-                    if let Some(Err(err)) = self.tokenizer.next() {
-                        return Err(err);
-                    }
+                Some(Ok(Token::Whitespace)) | Some(Ok(Token::Comment(_))) => {
+                    self.consume_token()?;
                 }
                 _ => return Ok(()),
             }
         }
     }
 
+    /// Skips whitespace tokens, i.e., whitespace and comments, and returns the text of the
+    /// skipped comments.
+    pub fn read_whitespace_comments(&mut self) -> Result<Vec<String>> {
+        let mut result = Vec::new();
+
+        loop {
+            match self.tokenizer.peek() {
+                Some(Ok(Token::Comment(text))) => {
+                    result.push(text.clone());
+                    self.consume_token()?;
+                }
+                Some(Ok(Token::Whitespace)) => self.consume_token()?,
+                _ => return Ok(result),
+            }
+        }
+    }
+
     /// Skips until the predicate is not true anymore.
     ///
     /// # Arguments
@@ -69,6 +119,33 @@ impl<R: Read> Parser<R> {
         }
     }
 
+    /// Skips a quoted string including its escaped quotes if the next character is a quote.
+    /// Returns true if a string has been skipped.
+    pub fn skip_quoted_string(&mut self) -> Result<bool> {
+        if !matches!(self.tokenizer.peek(), Some(Ok(Token::Character('\'')))) {
+            return Ok(false);
+        }
+
+        self.consume_token()?;
+        loop {
+            match self.tokenizer.peek() {
+                Some(Ok(Token::Character('\''))) => {
+                    self.consume_token()?;
+
+                    // two consecutive quotes are an escaped quote within the string
+                    if !matches!(self.tokenizer.peek(), Some(Ok(Token::Character('\'')))) {
+                        return Ok(true);
+                    }
+
+                    self.consume_token()?;
+                }
+                Some(Ok(_)) => self.consume_token()?,
+                Some(Err(err)) => return Err(Error::FailedSequence(Box::new(err.clone()))),
+                None => return Err(Error::EndOfInput()),
+            }
+        }
+    }
+
     /// Reads as long as the predicate is true.
     /// Returns an error if the end of the input is reached.
     /// Otherwise, returns the read string.
@@ -76,7 +153,7 @@ impl<R: Read> Parser<R> {
     /// # Arguments
     /// * `predicate` - The predicate to check for.
     /// * `ignore_whitespace` - Whether to ignore whitespace and comments. If encountered, a space
-    ///                         is being added.
+    ///   is being added.
     pub fn read_string(
         &mut self,
         predicate: impl Fn(char) -> bool,
@@ -96,28 +173,48 @@ impl<R: Read> Parser<R> {
                         return Err(err);
                     }
                 }
-                Some(Ok(Token::Whitespace)) => {
+                Some(Ok(Token::Whitespace)) | Some(Ok(Token::Comment(_))) => {
                     if !ignore_whitespace {
                         return Ok(result);
                     } else {
                         result.push(' ');
-                        if let Some(Err(err)) = self.tokenizer.next() {
-                            return Err(err);
-                        }
+                        self.consume_token()?;
                     }
                 }
-                Some(Ok(Token::Comment)) => {
-                    if !ignore_whitespace {
+                Some(Err(err)) => return Err(Error::FailedSequence(Box::new(err.clone()))),
+                None => return Ok(result),
+            }
+        }
+    }
+
+    /// Reads a definition up to, but excluding, the next semicolon outside of a string.
+    /// Whitespace and comments outside of strings are replaced by a space.
+    pub fn read_definition(&mut self) -> Result<String> {
+        let mut result = String::new();
+        let mut inside_string = false;
+
+        loop {
+            match self.tokenizer.peek() {
+                Some(Ok(Token::Character(ch))) => {
+                    let ch = *ch;
+                    if ch == ';' && !inside_string {
                         return Ok(result);
-                    } else {
-                        result.push(' ');
-                        if let Some(Err(err)) = self.tokenizer.next() {
-                            return Err(err);
-                        }
                     }
+
+                    // escaped quotes are handled as two consecutive strings
+                    if ch == '\'' {
+                        inside_string = !inside_string;
+                    }
+
+                    result.push(ch);
+                    self.consume_token()?;
+                }
+                Some(Ok(Token::Whitespace)) | Some(Ok(Token::Comment(_))) => {
+                    result.push(' ');
+                    self.consume_token()?;
                 }
                 Some(Err(err)) => return Err(Error::FailedSequence(Box::new(err.clone()))),
-                None => return Ok(result),
+                None => return Err(Error::EndOfInput()),
             }
         }
     }
@@ -143,7 +240,7 @@ impl<R: Read> Parser<R> {
                         "whitespace".to_string(),
                     ));
                 }
-                Some(Ok(Token::Comment)) => {
+                Some(Ok(Token::Comment(_))) => {
                     return Err(Error::UnexpectedToken(
                         sequence.to_string(),
                         "comment.".to_string(),
@@ -229,6 +326,30 @@ mod tests {
         assert_eq!(s, "c-def".to_string());
     }
 
+    #[test]
+    fn test_skip_quoted_string() {
+        let mut parser = Parser::new(Cursor::new("'It''s DATA;' X"));
+
+        assert!(parser.skip_quoted_string().unwrap());
+        parser.skip_whitespace_tokens().unwrap();
+        assert!(!parser.skip_quoted_string().unwrap());
+        assert_eq!(parser.read_string(|ch| ch != ';', false).unwrap(), "X");
+
+        let mut parser = Parser::new(Cursor::new("'unterminated"));
+        assert!(parser.skip_quoted_string().is_err());
+    }
+
+    #[test]
+    fn test_read_definition() {
+        let mut parser = Parser::new(Cursor::new("FOO('a;b',  1);"));
+
+        assert_eq!(parser.read_definition().unwrap(), "FOO('a;b', 1)");
+        parser.read_exact_sequence(";").unwrap();
+
+        let mut parser = Parser::new(Cursor::new("FOO('a;b'"));
+        assert!(parser.read_definition().is_err());
+    }
+
     #[test]
     fn test_read_u64() {
         let mut input = Cursor::new("123 456");
@@ -240,4 +361,24 @@ mod tests {
         let n = reader.read_u64().unwrap();
         assert_eq!(n, 456);
     }
+
+    #[test]
+    fn test_collect_comments() {
+        let mut input = Cursor::new("/* first */ abc /* second */ def");
+        let mut reader = Parser::new(&mut input);
+
+        reader.skip_whitespace_tokens().unwrap();
+        assert!(reader.take_comments().is_empty());
+
+        let mut input = Cursor::new("/* first */ abc /* second */ def");
+        let mut reader = Parser::new(&mut input);
+        reader.set_collect_comments(true);
+
+        reader.skip_whitespace_tokens().unwrap();
+        assert_eq!(reader.take_comments(), vec![" first ".to_string()]);
+
+        let result = reader.read_string(|_| true, true).unwrap();
+        assert_eq!(result, "abc   def".to_string());
+        assert_eq!(reader.take_comments(), vec![" second ".to_string()]);
+    }
 }
@@ -12,10 +12,11 @@ pub struct Tokenizer<P: Iterator<Item = Result<char>>> {
 }
 
 /// The different tokens that are returned by the tokenizer.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Whitespace,
-    Comment,
+    /// A comment with its text excluding the enclosing `/*` and `*/`.
+    Comment(String),
     Character(char),
 }
 
@@ -39,7 +40,7 @@ impl<P: Iterator<Item = Result<char>>> Tokenizer<P> {
         for token in self {
             match token {
                 Ok(Token::Whitespace) => result.push('\n'),
-                Ok(Token::Comment) => result.push_str("/**/"),
+                Ok(Token::Comment(_)) => result.push_str("/**/"),
                 Ok(Token::Character(ch)) => result.push(ch),
                 Err(err) => return Err(err),
             }
@@ -79,6 +80,29 @@ impl<P: Iterator<Item = Result<char>>> Tokenizer<P> {
             }
         }
     }
+
+    /// Reads the text of a comment until the closing `*/` is reached. Assumes that the opening
+    /// `/*` has already been consumed.
+    /// Returns an error if the end of the input is reached.
+    fn read_comment(&mut self) -> Result<String> {
+        let mut text = String::new();
+
+        loop {
+            match self.parser.next() {
+                Some(Ok('*')) => {
+                    if let Some(Ok('/')) = self.parser.peek() {
+                        self.parser.next();
+                        return Ok(text);
+                    }
+
+                    text.push('*');
+                }
+                Some(Ok(ch)) => text.push(ch),
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::EndOfInput()),
+            }
+        }
+    }
 }
 
 impl<P: Iterator<Item = Result<char>>> Iterator for Tokenizer<P> {
@@ -114,25 +138,9 @@ impl<P: Iterator<Item = Result<char>>> Iterator for Tokenizer<P> {
 
         // case 4: we may have encountered a comment and are outside a string
         if ch == '/' {
-            if let Some(Ok(next_char)) = self.parser.peek() {
-                if *next_char != '*' {
-                    return Some(Ok(Token::Character('/')));
-                }
-
-                loop {
-                    if let Err(err) = self.skip_until(|ch| ch != '*', true) {
-                        return Some(Err(err));
-                    }
-
-                    self.parser.next();
-
-                    if let Some(Ok(next_char)) = self.parser.peek() {
-                        if *next_char == '/' {
-                            self.parser.next();
-                            return Some(Ok(Token::Comment));
-                        }
-                    }
-                }
+            if let Some(Ok('*')) = self.parser.peek() {
+                self.parser.next();
+                return Some(self.read_comment().map(Token::Comment));
             } else {
                 return Some(Ok(Token::Character('/')));
             }
@@ -178,7 +186,10 @@ mod test {
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('l'));
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('o'));
         assert_eq!(parser.next().unwrap().unwrap(), Token::Whitespace);
-        assert_eq!(parser.next().unwrap().unwrap(), Token::Comment);
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Token::Comment("asd".to_string())
+        );
         assert_eq!(parser.next().unwrap().unwrap(), Token::Whitespace);
         assert!(parser.next().is_none());
     }
@@ -195,7 +206,10 @@ mod test {
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('l'));
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('o'));
         assert_eq!(parser.next().unwrap().unwrap(), Token::Whitespace);
-        assert_eq!(parser.next().unwrap().unwrap(), Token::Comment);
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Token::Comment("asd''".to_string())
+        );
         assert_eq!(parser.next().unwrap().unwrap(), Token::Whitespace);
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('\''));
         assert_eq!(parser.next().unwrap().unwrap(), Token::Character('/'));
@@ -207,6 +221,27 @@ mod test {
         assert!(parser.next().is_none());
     }
 
+    #[test]
+    fn test_tokenizer_comment_text() {
+        let input = "/* TODO: fix ** this */#1/**/";
+        let mut parser = Tokenizer::new(input.chars().map(Ok));
+
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Token::Comment(" TODO: fix ** this ".to_string())
+        );
+        assert_eq!(parser.next().unwrap().unwrap(), Token::Character('#'));
+        assert_eq!(parser.next().unwrap().unwrap(), Token::Character('1'));
+        assert_eq!(
+            parser.next().unwrap().unwrap(),
+            Token::Comment(String::new())
+        );
+        assert!(parser.next().is_none());
+
+        let mut parser = Tokenizer::new("/* unterminated *".chars().map(Ok));
+        assert!(parser.next().unwrap().is_err());
+    }
+
     #[test]
     fn test_tokenizer_complex() {
         let input = r#"
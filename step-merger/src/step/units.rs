@@ -0,0 +1,178 @@
+use super::StepEntry;
+
+/// The SI prefixes that can be used for the emitted SI_UNIT entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SiPrefix {
+    /// No prefix, written as `$`.
+    None,
+    Kilo,
+    Centi,
+    #[default]
+    Milli,
+    Micro,
+    Nano,
+}
+
+impl SiPrefix {
+    /// Returns the STEP representation of the prefix, e.g., `.MILLI.` or `$` for no prefix.
+    pub fn to_step(&self) -> &'static str {
+        match self {
+            SiPrefix::None => "$",
+            SiPrefix::Kilo => ".KILO.",
+            SiPrefix::Centi => ".CENTI.",
+            SiPrefix::Milli => ".MILLI.",
+            SiPrefix::Micro => ".MICRO.",
+            SiPrefix::Nano => ".NANO.",
+        }
+    }
+
+    /// Parses the STEP representation of the prefix, i.e., the inverse of `to_step`. Returns
+    /// None for unsupported prefixes.
+    ///
+    /// # Arguments
+    /// * `s` - The STEP representation of the prefix, e.g., `.MILLI.`.
+    pub fn from_step(s: &str) -> Option<SiPrefix> {
+        match s.trim() {
+            "$" => Some(SiPrefix::None),
+            ".KILO." => Some(SiPrefix::Kilo),
+            ".CENTI." => Some(SiPrefix::Centi),
+            ".MILLI." => Some(SiPrefix::Milli),
+            ".MICRO." => Some(SiPrefix::Micro),
+            ".NANO." => Some(SiPrefix::Nano),
+            _ => None,
+        }
+    }
+
+    /// Returns the factor of the prefix, e.g., 1e-3 for milli.
+    pub fn factor(&self) -> f64 {
+        match self {
+            SiPrefix::None => 1.0,
+            SiPrefix::Kilo => 1e3,
+            SiPrefix::Centi => 1e-2,
+            SiPrefix::Milli => 1e-3,
+            SiPrefix::Micro => 1e-6,
+            SiPrefix::Nano => 1e-9,
+        }
+    }
+
+    /// Returns the decimal exponent of the prefix, e.g., -3 for milli.
+    pub fn exponent(&self) -> i32 {
+        match self {
+            SiPrefix::None => 0,
+            SiPrefix::Kilo => 3,
+            SiPrefix::Centi => -2,
+            SiPrefix::Milli => -3,
+            SiPrefix::Micro => -6,
+            SiPrefix::Nano => -9,
+        }
+    }
+
+    /// Returns the scale factor to convert a value given in the unprefixed unit into the
+    /// prefixed unit, e.g., 1000 for converting meters into millimeters.
+    pub fn scale_from_base(&self) -> f64 {
+        match self {
+            SiPrefix::None => 1.0,
+            SiPrefix::Kilo => 1e-3,
+            SiPrefix::Centi => 1e2,
+            SiPrefix::Milli => 1e3,
+            SiPrefix::Micro => 1e6,
+            SiPrefix::Nano => 1e9,
+        }
+    }
+}
+
+/// Returns the definition of the length unit entry with the given prefix.
+///
+/// # Arguments
+/// * `prefix` - The SI prefix of the length unit.
+pub(crate) fn length_unit(prefix: SiPrefix) -> String {
+    format!(
+        "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT({},.METRE.))",
+        prefix.to_step()
+    )
+}
+
+/// Returns the length of the SI length unit defined by the given entry in meters, e.g., 1e-3 for
+/// millimeters, or None if the entry is not an SI length unit with a supported prefix.
+///
+/// # Arguments
+/// * `entry` - The entry defining the unit.
+pub(crate) fn si_length_unit_factor(entry: &StepEntry) -> Option<f64> {
+    if !entry.keywords().contains(&"LENGTH_UNIT") {
+        return None;
+    }
+
+    let definition = entry.get_definition();
+    let start = definition.find("SI_UNIT(")? + "SI_UNIT(".len();
+    let end = start + definition[start..].find(')')?;
+    let (prefix, name) = definition[start..end].split_once(',')?;
+    if name.trim() != ".METRE." {
+        return None;
+    }
+
+    SiPrefix::from_step(prefix).map(|p| p.factor())
+}
+
+/// Returns the definition of the plane angle unit entry. Angles are always given in radians.
+pub(crate) fn plane_angle_unit() -> &'static str {
+    "(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))"
+}
+
+/// Returns the definition of the solid angle unit entry. Solid angles are always given in
+/// steradians.
+pub(crate) fn solid_angle_unit() -> &'static str {
+    "(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_length_unit() {
+        assert_eq!(
+            length_unit(SiPrefix::Milli),
+            "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))"
+        );
+        assert_eq!(
+            length_unit(SiPrefix::None),
+            "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT($,.METRE.))"
+        );
+        assert_eq!(
+            length_unit(SiPrefix::Micro),
+            "(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MICRO.,.METRE.))"
+        );
+    }
+
+    #[test]
+    fn test_si_length_unit_factor() {
+        for prefix in [SiPrefix::None, SiPrefix::Milli, SiPrefix::Micro] {
+            assert_eq!(SiPrefix::from_step(prefix.to_step()), Some(prefix));
+
+            let entry = StepEntry::new(1, &length_unit(prefix));
+            assert_eq!(si_length_unit_factor(&entry), Some(prefix.factor()));
+        }
+
+        let entry = StepEntry::new(1, plane_angle_unit());
+        assert_eq!(si_length_unit_factor(&entry), None);
+        let entry = StepEntry::new(
+            1,
+            "(CONVERSION_BASED_UNIT('INCH',#2)LENGTH_UNIT()NAMED_UNIT(#3))",
+        );
+        assert_eq!(si_length_unit_factor(&entry), None);
+    }
+
+    #[test]
+    fn test_scale_from_base() {
+        assert_eq!(SiPrefix::None.scale_from_base(), 1.0);
+        assert_eq!(SiPrefix::Milli.scale_from_base(), 1000.0);
+        assert_eq!(SiPrefix::Micro.scale_from_base(), 1e6);
+    }
+
+    #[test]
+    fn test_exponent() {
+        assert_eq!(SiPrefix::None.exponent(), 0);
+        assert_eq!(SiPrefix::Kilo.exponent(), 3);
+        assert_eq!(SiPrefix::Nano.exponent(), -9);
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt::Display;
+
+use crate::{Error, Result};
+
+use super::{decode_string, encode_string, extract_keyword, parse_number, StepEntry};
+
+/// A parameter value of an entity instance, e.g., the name of a PRODUCT or the coordinates of a
+/// CARTESIAN_POINT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepValue {
+    /// An integer without decimal point and exponent, e.g., `42`.
+    Integer(i64),
+
+    /// A real with decimal point or exponent, e.g., `1.5E-3`.
+    Real(f64),
+
+    /// A decoded string literal, e.g., `'it''s'` is `it's`.
+    String(String),
+
+    /// An enumeration or logical value without the enclosing dots, e.g., `.T.` is `T`.
+    Enum(String),
+
+    /// A reference to another entry, e.g., `#42`.
+    Reference(u64),
+
+    /// A list or set of values, e.g., `(1.,2.,3.)`.
+    List(Vec<StepValue>),
+
+    /// A value of a defined type, e.g., `LENGTH_MEASURE(2.5)`.
+    Typed(String, Box<StepValue>),
+
+    /// An unset optional value, i.e., `$`.
+    Unset,
+
+    /// A value derived from the other values, i.e., `*`.
+    Derived,
+}
+
+impl Display for StepValue {
+    /// Writes the value in the syntax of the STEP exchange structure, i.e., strings are encoded
+    /// and reals always contain a decimal point.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepValue::Integer(value) => write!(f, "{}", value),
+            StepValue::Real(value) => {
+                let value = value.to_string();
+                if value.contains('.') {
+                    write!(f, "{}", value)
+                } else {
+                    write!(f, "{}.", value)
+                }
+            }
+            StepValue::String(value) => write!(f, "'{}'", encode_string(value)),
+            StepValue::Enum(value) => write!(f, ".{}.", value),
+            StepValue::Reference(id) => write!(f, "#{}", id),
+            StepValue::List(values) => {
+                write!(f, "(")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{}", value)?;
+                }
+                write!(f, ")")
+            }
+            StepValue::Typed(name, value) => write!(f, "{}({})", name, value),
+            StepValue::Unset => write!(f, "$"),
+            StepValue::Derived => write!(f, "*"),
+        }
+    }
+}
+
+impl StepEntry {
+    /// Parses the parameters of the entity instance, e.g., `PRODUCT('A','B','',(#2))` results in
+    /// three strings and a list with a reference. Complex instances are not supported, as their
+    /// parameters belong to different partial instances.
+    pub fn parse_parameters(&self) -> Result<Vec<StepValue>> {
+        let definition = self.get_definition().trim();
+        let keyword = extract_keyword(definition);
+        if keyword.is_empty() {
+            return Err(Error::MalformedEntry(self.get_id()));
+        }
+
+        let mut parser = ParameterParser {
+            definition,
+            pos: keyword.len(),
+            id: self.get_id(),
+        };
+
+        let parameters = match parser.parse_value()? {
+            StepValue::List(parameters) => parameters,
+            _ => return Err(parser.error()),
+        };
+
+        parser.skip_whitespace();
+        if parser.pos != definition.len() {
+            return Err(parser.error());
+        }
+
+        Ok(parameters)
+    }
+}
+
+/// A recursive descent parser for the parameters of a single entity instance.
+struct ParameterParser<'a> {
+    /// The definition of the entity instance.
+    definition: &'a str,
+
+    /// The byte position of the next character to parse.
+    pos: usize,
+
+    /// The id of the parsed entry for reporting errors.
+    id: u64,
+}
+
+impl<'a> ParameterParser<'a> {
+    /// Returns the error for a malformed definition.
+    fn error(&self) -> Error {
+        Error::MalformedEntry(self.id)
+    }
+
+    /// Returns the next byte without consuming it.
+    fn peek(&self) -> Option<u8> {
+        self.definition.as_bytes().get(self.pos).copied()
+    }
+
+    /// Skips the whitespace preceding the next token.
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes the bytes as long as the predicate holds and returns them.
+    ///
+    /// # Arguments
+    /// * `predicate` - The predicate for the bytes to consume.
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+
+        &self.definition[start..self.pos]
+    }
+
+    /// Parses the next value.
+    fn parse_value(&mut self) -> Result<StepValue> {
+        self.skip_whitespace();
+        match self.peek().ok_or_else(|| self.error())? {
+            b'$' => {
+                self.pos += 1;
+                Ok(StepValue::Unset)
+            }
+            b'*' => {
+                self.pos += 1;
+                Ok(StepValue::Derived)
+            }
+            b'#' => {
+                self.pos += 1;
+                self.take_while(|b| b.is_ascii_digit())
+                    .parse()
+                    .map(StepValue::Reference)
+                    .map_err(|_| self.error())
+            }
+            b'\'' => self.parse_string(),
+            b'.' => {
+                self.pos += 1;
+                let value = self.take_while(|b| b.is_ascii_alphanumeric() || b == b'_');
+                if value.is_empty() || self.peek() != Some(b'.') {
+                    return Err(self.error());
+                }
+
+                self.pos += 1;
+                Ok(StepValue::Enum(value.to_owned()))
+            }
+            b'(' => self.parse_list(),
+            b'0'..=b'9' | b'+' | b'-' => self.parse_number(),
+            b if b.is_ascii_alphabetic() => {
+                let name = self.take_while(|b| b.is_ascii_alphanumeric() || b == b'_');
+                self.skip_whitespace();
+                if self.peek() != Some(b'(') {
+                    return Err(self.error());
+                }
+
+                self.pos += 1;
+                let value = self.parse_value()?;
+                self.skip_whitespace();
+                if self.peek() != Some(b')') {
+                    return Err(self.error());
+                }
+
+                self.pos += 1;
+                Ok(StepValue::Typed(name.to_owned(), Box::new(value)))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Parses a string literal starting at the opening quote.
+    fn parse_string(&mut self) -> Result<StepValue> {
+        let start = self.pos + 1;
+        let mut pos = start;
+        let bytes = self.definition.as_bytes();
+
+        loop {
+            match bytes.get(pos) {
+                // two consecutive quotes are an escaped quote inside the string
+                Some(b'\'') if bytes.get(pos + 1) == Some(&b'\'') => pos += 2,
+                Some(b'\'') => break,
+                Some(_) => pos += 1,
+                None => return Err(self.error()),
+            }
+        }
+
+        self.pos = pos + 1;
+        decode_string(&self.definition[start..pos]).map(StepValue::String)
+    }
+
+    /// Parses a list starting at the opening parenthesis.
+    fn parse_list(&mut self) -> Result<StepValue> {
+        self.pos += 1;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b')') {
+            self.pos += 1;
+            return Ok(StepValue::List(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b')') => {
+                    self.pos += 1;
+                    return Ok(StepValue::List(values));
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// Parses an integer or a real.
+    fn parse_number(&mut self) -> Result<StepValue> {
+        let number =
+            self.take_while(|b| matches!(b, b'0'..=b'9' | b'+' | b'-' | b'.' | b'E' | b'e'));
+
+        if number
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b'+' || b == b'-')
+        {
+            if let Ok(integer) = number.parse() {
+                return Ok(StepValue::Integer(integer));
+            }
+        }
+
+        parse_number(number)
+            .map(StepValue::Real)
+            .map_err(|_| self.error())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_parameters() {
+        let entry = StepEntry::new(1, "PRODUCT('A','it''s','',(#2, #3))");
+        assert_eq!(
+            entry.parse_parameters().unwrap(),
+            vec![
+                StepValue::String("A".to_owned()),
+                StepValue::String("it's".to_owned()),
+                StepValue::String(String::new()),
+                StepValue::List(vec![StepValue::Reference(2), StepValue::Reference(3)]),
+            ]
+        );
+
+        let entry = StepEntry::new(
+            2,
+            "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(2.E-005),#194,'d',$)",
+        );
+        assert_eq!(
+            entry.parse_parameters().unwrap(),
+            vec![
+                StepValue::Typed("LENGTH_MEASURE".to_owned(), Box::new(StepValue::Real(2e-5))),
+                StepValue::Reference(194),
+                StepValue::String("d".to_owned()),
+                StepValue::Unset,
+            ]
+        );
+
+        let entry = StepEntry::new(
+            3,
+            "FOO ( .T. , * , -42 , 1.5 , () , ((1,2),(3)) , '\\X2\\00DC\\X0\\' )",
+        );
+        assert_eq!(
+            entry.parse_parameters().unwrap(),
+            vec![
+                StepValue::Enum("T".to_owned()),
+                StepValue::Derived,
+                StepValue::Integer(-42),
+                StepValue::Real(1.5),
+                StepValue::List(Vec::new()),
+                StepValue::List(vec![
+                    StepValue::List(vec![StepValue::Integer(1), StepValue::Integer(2)]),
+                    StepValue::List(vec![StepValue::Integer(3)]),
+                ]),
+                StepValue::String("Ü".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            StepEntry::new(4, "BAR()").parse_parameters().unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        for definition in [
+            "PRODUCT('A','it''s','',(#2,#3))",
+            "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(2.E-005),#194,'d',$)",
+            "FOO(.T.,*,-42,1.5,(),((1,2),(3)),'\\X2\\00DC\\X0\\')",
+        ] {
+            let entry = StepEntry::new(1, definition);
+            let parameters = StepValue::List(entry.parse_parameters().unwrap());
+            let reparsed = StepEntry::new(1, &format!("{}{}", entry.keyword(), parameters));
+            assert_eq!(
+                reparsed.parse_parameters().unwrap(),
+                entry.parse_parameters().unwrap()
+            );
+        }
+
+        assert_eq!(StepValue::Real(1.0).to_string(), "1.");
+        assert_eq!(StepValue::Real(-0.25).to_string(), "-0.25");
+        assert_eq!(
+            StepValue::String("Über".to_owned()).to_string(),
+            "'\\X2\\00DC\\X0\\ber'"
+        );
+    }
+
+    #[test]
+    fn test_parse_parameters_malformed() {
+        for definition in [
+            "FOO('a'",
+            "FOO('a)",
+            "FOO(#)",
+            "FOO(.T)",
+            "FOO(1,,2)",
+            "FOO(1) BAR",
+            "FOO",
+            "(NAMED_UNIT(*)SI_UNIT($,.METRE.)LENGTH_UNIT())",
+        ] {
+            assert!(matches!(
+                StepEntry::new(7, definition).parse_parameters(),
+                Err(Error::MalformedEntry(7))
+            ));
+        }
+    }
+}
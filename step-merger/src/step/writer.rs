@@ -1,8 +1,12 @@
-use std::{io::Write, sync::Arc};
+use std::{
+    fmt::Arguments,
+    io::{BufWriter, Write},
+    sync::Arc,
+};
 
 use log::debug;
 
-use super::{StepData, StepEntry};
+use super::{encode_string, ApplicationProtocol, StepData, StepEntry};
 
 use crate::{Error, Result};
 
@@ -14,6 +18,47 @@ impl From<std::io::Error> for Error {
     }
 }
 
+/// Splits the given line into parts of at most the given width. The line is only split after
+/// commas outside of string literals, i.e., parts without such a comma may exceed the width.
+///
+/// # Arguments
+/// * `line` - The line to split.
+/// * `max_width` - The maximum width of the parts.
+fn wrap_line(line: &str, max_width: usize) -> Vec<&str> {
+    let mut result = Vec::new();
+
+    // the start of the current part and the start of its last unbreakable piece
+    let mut part_start = 0;
+    let mut piece_start = 0;
+    let mut inside_string = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            // escaped quotes are handled as two consecutive strings
+            '\'' => inside_string = !inside_string,
+            ',' if !inside_string => {
+                let piece_end = i + 1;
+                if piece_end - part_start > max_width && piece_start > part_start {
+                    result.push(&line[part_start..piece_start]);
+                    part_start = piece_start;
+                }
+
+                piece_start = piece_end;
+            }
+            _ => {}
+        }
+    }
+
+    if line.len() - part_start > max_width && piece_start > part_start {
+        result.push(&line[part_start..piece_start]);
+        part_start = piece_start;
+    }
+
+    result.push(&line[part_start..]);
+
+    result
+}
+
 /// Writes the given step data to the writer.
 ///
 /// # Arguments
@@ -21,8 +66,29 @@ impl From<std::io::Error> for Error {
 /// * `step` - The step data to write.
 /// * `filename` - The filename string to set in the header.
 pub fn write_step<W: Write>(writer: &mut W, step: &StepData, filename: &str) -> Result<()> {
-    let protocol = vec!["AP203_CONFIGURATION_CONTROLLED_3D_DESIGN_OF_MECHANICAL_PARTS_AND_ASSEMBLIES_MIM_LF { 1 0 10303 403 1 1 4 }".to_owned()];
-    let mut step_writer = StepWriter::new(writer, "2;1", filename, &protocol)?;
+    // keep the schema and the leading comments of the source file if known
+    let protocol = if step.get_header().schemas.is_empty() {
+        vec![ApplicationProtocol::default().schema().to_owned()]
+    } else {
+        step.get_header().schemas.clone()
+    };
+    let header = step.get_header();
+    let header_info = HeaderInfo {
+        author: header.author.clone(),
+        organization: header.organization.clone(),
+        originating_system: header.originating_system.clone(),
+        authorization: header.authorization.clone(),
+        ..Default::default()
+    };
+    let mut step_writer = StepWriter::with_header_info(
+        writer,
+        &header.leading_comments,
+        &header_info,
+        "2;1",
+        filename,
+        &protocol,
+        WriterFormat::default(),
+    )?;
 
     for entry in step.get_entries() {
         step_writer.write_entry(entry)?;
@@ -33,15 +99,143 @@ pub fn write_step<W: Write>(writer: &mut W, step: &StepData, filename: &str) ->
     Ok(())
 }
 
+/// The spacing between the sections of the written STEP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionSpacing {
+    /// An empty line is written after the section keywords and the header entries.
+    #[default]
+    Spaced,
+
+    /// No empty lines are written.
+    Compact,
+}
+
+/// The line terminator of the written STEP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// Lines are terminated by `\n`.
+    #[default]
+    Lf,
+
+    /// Lines are terminated by `\r\n`, as required by some Windows toolchains.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the line terminator as string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The formatting of the written STEP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriterFormat {
+    /// The spacing between the sections.
+    pub spacing: SectionSpacing,
+
+    /// The line terminator used for every written line.
+    pub line_ending: LineEnding,
+
+    /// The maximum width of the lines of an entry. Longer entries are wrapped after commas
+    /// outside of string literals. If None, each entry is written as a single line.
+    pub max_line_width: Option<usize>,
+}
+
+/// The fields of the FILE_NAME header entity identifying the creator of the written STEP file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderInfo {
+    /// The names and addresses of the authors.
+    pub author: Vec<String>,
+
+    /// The organizations of the authors.
+    pub organization: Vec<String>,
+
+    /// The system that has written the file, `step-merger` by default.
+    pub preprocessor_version: String,
+
+    /// The system from which the data originates.
+    pub originating_system: String,
+
+    /// The person who authorized sending the file.
+    pub authorization: String,
+
+    /// The time stamp of the file or None to use the current time. A fixed time stamp makes the
+    /// written file reproducible.
+    pub time_stamp: Option<String>,
+}
+
+impl Default for HeaderInfo {
+    fn default() -> Self {
+        HeaderInfo {
+            author: Vec::new(),
+            organization: Vec::new(),
+            preprocessor_version: "step-merger".to_owned(),
+            originating_system: String::new(),
+            authorization: String::new(),
+            time_stamp: None,
+        }
+    }
+}
+
+/// Formats the given strings as list of STEP string literals. An empty list is written as list
+/// of a single empty string, as required for the lists of FILE_NAME.
+///
+/// # Arguments
+/// * `strings` - The strings to format.
+fn string_list(strings: &[String]) -> String {
+    if strings.is_empty() {
+        return "('')".to_owned();
+    }
+
+    let strings: Vec<String> = strings
+        .iter()
+        .map(|s| format!("'{}'", encode_string(s)))
+        .collect();
+    format!("({})", strings.join(", "))
+}
+
+/// A writer counting the bytes passed to the underlying writer.
+struct ByteCounter<W: Write> {
+    writer: W,
+    num_bytes: u64,
+}
+
+impl<W: Write> Write for ByteCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_bytes = self.writer.write(buf)?;
+        self.num_bytes += num_bytes as u64;
+
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
 /// A step writer for manually writing the step entries. Can be used to stream the
 /// step entries to a writer.
+/// The output is buffered internally, thus the given writer does not need to be buffered.
+/// The writer must be completed by calling `finalize`, which writes the end of the file, flushes
+/// the buffer and reports any write error. A writer dropped without being finalized leaves an
+/// incomplete file, except while panicking, where the file is finalized as a fallback.
 pub struct StepWriter<W: Write> {
-    /// The underlying writer to write to.
-    writer: W,
+    /// The buffered underlying writer to write to.
+    writer: BufWriter<ByteCounter<W>>,
 
     /// Indicates if the writer has been finalized.
     /// If finalized, no further entries can be written.
     is_finalized: bool,
+
+    /// Indicates if the entries are checked to be well-formed before being written.
+    validate_entries: bool,
+
+    /// The formatting of the written file.
+    format: WriterFormat,
 }
 
 impl<W: Write> StepWriter<W> {
@@ -58,75 +252,270 @@ impl<W: Write> StepWriter<W> {
         filename: &str,
         protocol: &[String],
     ) -> Result<Self> {
-        let mut step_writer = StepWriter {
+        Self::with_spacing(
             writer,
+            implementation_level,
+            filename,
+            protocol,
+            SectionSpacing::default(),
+        )
+    }
+
+    /// Creates and initializes a new step writer with the given spacing between the sections.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write to.
+    /// * `implementation_level` - The implementation level string to set in the header.
+    /// * `filename` - The filename string to set in the header.
+    /// * `protocol` - The protocol strings to set in the header.
+    /// * `spacing` - The spacing between the sections.
+    pub fn with_spacing(
+        writer: W,
+        implementation_level: &str,
+        filename: &str,
+        protocol: &[String],
+        spacing: SectionSpacing,
+    ) -> Result<Self> {
+        Self::with_format(
+            writer,
+            implementation_level,
+            filename,
+            protocol,
+            WriterFormat {
+                spacing,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates and initializes a new step writer with the given formatting.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write to.
+    /// * `implementation_level` - The implementation level string to set in the header.
+    /// * `filename` - The filename string to set in the header.
+    /// * `protocol` - The protocol strings to set in the header.
+    /// * `format` - The formatting of the written file.
+    pub fn with_format(
+        writer: W,
+        implementation_level: &str,
+        filename: &str,
+        protocol: &[String],
+        format: WriterFormat,
+    ) -> Result<Self> {
+        Self::with_leading_comments(
+            writer,
+            &[],
+            implementation_level,
+            filename,
+            protocol,
+            format,
+        )
+    }
+
+    /// Creates and initializes a new step writer with the given formatting, which writes the
+    /// given comments before the `ISO-10303-21;` line, e.g., to preserve the comment block of a
+    /// source file.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write to.
+    /// * `leading_comments` - The text of the comments excluding the enclosing `/*` and `*/`.
+    /// * `implementation_level` - The implementation level string to set in the header.
+    /// * `filename` - The filename string to set in the header.
+    /// * `protocol` - The protocol strings to set in the header.
+    /// * `format` - The formatting of the written file.
+    pub fn with_leading_comments(
+        writer: W,
+        leading_comments: &[String],
+        implementation_level: &str,
+        filename: &str,
+        protocol: &[String],
+        format: WriterFormat,
+    ) -> Result<Self> {
+        Self::with_header_info(
+            writer,
+            leading_comments,
+            &HeaderInfo::default(),
+            implementation_level,
+            filename,
+            protocol,
+            format,
+        )
+    }
+
+    /// Creates and initializes a new step writer like `with_leading_comments`, which
+    /// additionally writes the given creator information into the FILE_NAME header entity.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write to.
+    /// * `leading_comments` - The text of the comments excluding the enclosing `/*` and `*/`.
+    /// * `header_info` - The creator information to set in the header.
+    /// * `implementation_level` - The implementation level string to set in the header.
+    /// * `filename` - The filename string to set in the header.
+    /// * `protocol` - The protocol strings to set in the header.
+    /// * `format` - The formatting of the written file.
+    pub fn with_header_info(
+        writer: W,
+        leading_comments: &[String],
+        header_info: &HeaderInfo,
+        implementation_level: &str,
+        filename: &str,
+        protocol: &[String],
+        format: WriterFormat,
+    ) -> Result<Self> {
+        let mut step_writer = StepWriter {
+            writer: BufWriter::new(ByteCounter {
+                writer,
+                num_bytes: 0,
+            }),
             is_finalized: false,
+            validate_entries: false,
+            format,
         };
+        for comment in leading_comments {
+            step_writer.write_line(format_args!("/*{}*/", comment))?;
+        }
+
         debug!("Writing step file header...");
-        step_writer.write_header(implementation_level, filename, protocol)?;
+        step_writer.write_header(header_info, implementation_level, filename, protocol)?;
         debug!("Writing step file header...DONE");
 
         // initialize data block
         debug!("Start writing data...");
-        writeln!(step_writer.writer, "DATA;")?;
+        step_writer.write_line(format_args!("DATA;"))?;
 
         Ok(step_writer)
     }
 
-    /// Writes the given step entry to the writer.
+    /// Enables or disables the validation of the written entries. If enabled, writing an entry
+    /// that is not well-formed or that contains non-ASCII characters, which must be encoded via
+    /// control directives, fails with an error. Disabled by default.
+    ///
+    /// # Arguments
+    /// * `validate_entries` - Flag to indicate if the entries should be validated.
+    pub fn set_validate_entries(&mut self, validate_entries: bool) {
+        self.validate_entries = validate_entries;
+    }
+
+    /// Returns the number of bytes written so far, including the bytes that are still buffered.
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.get_ref().num_bytes + self.writer.buffer().len() as u64
+    }
+
+    /// Writes the given step entry to the writer. Fails if the writer has been finalized.
     ///
     /// # Arguments
     /// * `entry` - The step entry to write.
     pub fn write_entry(&mut self, entry: &StepEntry) -> Result<()> {
-        assert!(
-            !self.is_finalized,
-            "Cannot write entry after finalizing the step writer."
-        );
-        writeln!(self.writer, "#{}={};", entry.id, entry.definition)?;
+        if self.is_finalized {
+            return Err(Error::WriterFinalized());
+        }
+
+        if self.validate_entries && (!entry.is_well_formed() || !entry.definition.is_ascii()) {
+            return Err(Error::MalformedEntry(entry.id));
+        }
+
+        match self.format.max_line_width {
+            Some(max_width) => {
+                let line = format!("#{}={};", entry.id, entry.definition);
+                for part in wrap_line(&line, max_width) {
+                    self.write_line(format_args!("{}", part))?;
+                }
+            }
+            _ => self.write_line(format_args!("#{}={};", entry.id, entry.definition))?,
+        }
 
         Ok(())
     }
 
+    /// Writes the given text as a comment on its own line. An enclosed `*/` is broken up, as it
+    /// would terminate the comment early. Fails if the writer has been finalized.
+    ///
+    /// # Arguments
+    /// * `comment` - The text of the comment excluding the enclosing `/*` and `*/`.
+    pub fn write_comment(&mut self, comment: &str) -> Result<()> {
+        if self.is_finalized {
+            return Err(Error::WriterFinalized());
+        }
+
+        self.write_line(format_args!("/*{}*/", comment.replace("*/", "* /")))
+    }
+
     /// Writes the STEP file header for the given header values.
     ///
     /// # Arguments
+    /// * `header_info` - The creator information to set in the header.
     /// * `implementation_level` - The implementation level string to set in the header.
     /// * `filename` - The filename string to set in the header.
     /// * `protocol` - The protocol strings to set in the header.
     fn write_header(
         &mut self,
+        header_info: &HeaderInfo,
         implementation_level: &str,
         filename: &str,
         protocol: &[String],
     ) -> Result<()> {
-        writeln!(self.writer, "ISO-10303-21;\n")?;
-        writeln!(self.writer, "HEADER;\n")?;
-        writeln!(
-            self.writer,
+        let sep = self.section_separator();
+
+        self.write_line(format_args!("ISO-10303-21;{}", sep))?;
+        self.write_line(format_args!("HEADER;{}", sep))?;
+        self.write_line(format_args!(
             "FILE_DESCRIPTION((''), '{}');",
-            implementation_level
-        )?;
-
-        let current_date: String = chrono::Local::now().to_rfc3339();
-        writeln!(
-            self.writer,
-            "FILE_NAME('{}', '{}', (''), (''), 'step-merger', '', '');",
-            filename, current_date
-        )?;
-
-        writeln!(
-            self.writer,
-            "FILE_SCHEMA(('{}'));\n",
-            protocol.join("'), ('")
-        )?;
-
-        writeln!(self.writer, "ENDSEC;\n")?;
+            encode_string(implementation_level)
+        ))?;
+
+        let time_stamp = match header_info.time_stamp.as_ref() {
+            Some(time_stamp) => encode_string(time_stamp),
+            None => chrono::Local::now().to_rfc3339(),
+        };
+        self.write_line(format_args!(
+            "FILE_NAME('{}', '{}', {}, {}, '{}', '{}', '{}');",
+            encode_string(filename),
+            time_stamp,
+            string_list(&header_info.author),
+            string_list(&header_info.organization),
+            encode_string(&header_info.preprocessor_version),
+            encode_string(&header_info.originating_system),
+            encode_string(&header_info.authorization),
+        ))?;
+
+        let protocol = protocol
+            .iter()
+            .map(|p| encode_string(p))
+            .collect::<Vec<_>>();
+        self.write_line(format_args!(
+            "FILE_SCHEMA(('{}'));{}",
+            protocol.join("'), ('"),
+            sep
+        ))?;
+
+        self.write_line(format_args!("ENDSEC;{}", sep))?;
         self.writer.flush()?;
 
         Ok(())
     }
 
-    /// Finalizes the step writer.
+    /// Writes the given line followed by the configured line terminator.
+    ///
+    /// # Arguments
+    /// * `line` - The formatted line to write.
+    fn write_line(&mut self, line: Arguments) -> Result<()> {
+        self.writer.write_fmt(line)?;
+        self.writer
+            .write_all(self.format.line_ending.as_str().as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Returns the additional separator being written after the section lines.
+    fn section_separator(&self) -> &'static str {
+        match self.format.spacing {
+            SectionSpacing::Spaced => self.format.line_ending.as_str(),
+            SectionSpacing::Compact => "",
+        }
+    }
+
+    /// Finalizes the step writer by writing the end of the file and flushing the buffered output.
     pub fn finalize(&mut self) -> Result<()> {
         debug!("Finalizing step writer...");
 
@@ -136,8 +525,9 @@ impl<W: Write> StepWriter<W> {
             self.is_finalized = true;
         }
 
-        writeln!(self.writer, "ENDSEC;\n")?;
-        writeln!(self.writer, "END-ISO-10303-21;")?;
+        let sep = self.section_separator();
+        self.write_line(format_args!("ENDSEC;{}", sep))?;
+        self.write_line(format_args!("END-ISO-10303-21;"))?;
         self.writer.flush()?;
 
         debug!("Finalizing step writer...DONE");
@@ -148,8 +538,18 @@ impl<W: Write> StepWriter<W> {
 
 impl<W: Write> Drop for StepWriter<W> {
     fn drop(&mut self) {
-        if let Err(err) = self.finalize() {
-            log::error!("Failed to finalize step writer: {}", err);
+        if self.is_finalized {
+            return;
+        }
+
+        // outside of a panic, the writer is only dropped unfinalized if writing has been aborted,
+        // which must not result in a seemingly complete file
+        if std::thread::panicking() {
+            if let Err(err) = self.finalize() {
+                log::error!("Failed to finalize step writer: {}", err);
+            }
+        } else {
+            log::error!("Step writer dropped without being finalized, the output is incomplete");
         }
     }
 }
@@ -177,10 +577,351 @@ mod test {
         let serialized_data = String::from_utf8(serialized_data).unwrap();
         let step2 = step::StepData::from_str(&serialized_data).unwrap();
         assert_eq!(step.get_entries().len(), step2.get_entries().len());
+        assert_eq!(step.get_header().schemas, step2.get_header().schemas);
+        assert_eq!(step2.get_header().name, "minimal-structure.stp");
 
         for (entry1, entry2) in step.get_entries().iter().zip(step2.get_entries().iter()) {
             assert_eq!(entry1.id, entry2.id);
             assert_eq!(entry1.definition, entry2.definition);
         }
     }
+
+    #[test]
+    fn test_writing_validated() {
+        let mut serialized_data: Vec<u8> = Vec::new();
+        let mut writer = StepWriter::new(&mut serialized_data, "2;1", "", &[]).unwrap();
+
+        writer
+            .write_entry(&StepEntry::new(1, "PRODUCT('A','B','',(#2)"))
+            .unwrap();
+
+        writer.set_validate_entries(true);
+        writer
+            .write_entry(&StepEntry::new(2, "PRODUCT('A','B','',(#2))"))
+            .unwrap();
+        assert!(matches!(
+            writer.write_entry(&StepEntry::new(3, "PRODUCT('A','B','',(#2)")),
+            Err(Error::MalformedEntry(3))
+        ));
+        assert!(matches!(
+            writer.write_entry(&StepEntry::new(4, "PRODUCT('Ü','B','',(#2))")),
+            Err(Error::MalformedEntry(4))
+        ));
+    }
+
+    #[test]
+    fn test_writing_compact() {
+        let data = include_str!("../../../test_data/minimal-structure.stp");
+        let step = step::StepData::from_str(data).unwrap();
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::with_spacing(
+                &mut serialized_data,
+                "2;1",
+                "minimal-structure.stp",
+                &[ApplicationProtocol::default().schema().to_owned()],
+                SectionSpacing::Compact,
+            )
+            .unwrap();
+
+            for entry in step.get_entries() {
+                writer.write_entry(entry).unwrap();
+            }
+
+            writer.finalize().unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(!serialized_data.contains("\n\n"));
+
+        let step2 = step::StepData::from_str(&serialized_data).unwrap();
+        assert_eq!(step.get_entries(), step2.get_entries());
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        assert_eq!(wrap_line("#1=FOO(1,2,3);", 80), vec!["#1=FOO(1,2,3);"]);
+        assert_eq!(
+            wrap_line("#1=FOO(1,2,3,4);", 8),
+            vec!["#1=FOO(1,", "2,3,4);"]
+        );
+        assert_eq!(
+            wrap_line("#1=FOO('a,b,c,d',2);", 8),
+            vec!["#1=FOO('a,b,c,d',", "2);"]
+        );
+        assert_eq!(wrap_line("#1=FOO();", 2), vec!["#1=FOO();"]);
+    }
+
+    #[test]
+    fn test_writing_wrapped() {
+        let data = include_str!("../../../test_data/cube.stp");
+        let step = step::StepData::from_str(data).unwrap();
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::with_format(
+                &mut serialized_data,
+                "2;1",
+                "cube.stp",
+                &[ApplicationProtocol::default().schema().to_owned()],
+                WriterFormat {
+                    max_line_width: Some(40),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            for entry in step.get_entries() {
+                writer.write_entry(entry).unwrap();
+            }
+
+            writer.finalize().unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        let data_section = &serialized_data[serialized_data.find("DATA;").unwrap()..];
+        assert!(data_section
+            .lines()
+            .all(|line| line.len() <= 40 || !line.trim_end_matches(',').contains(',')));
+        assert!(data_section.lines().any(|line| !line.ends_with(';')));
+
+        // the wrapped entries only differ by the inserted whitespace
+        let step2 = step::StepData::from_str(&serialized_data).unwrap();
+        let normalize = |step: &step::StepData| -> Vec<(u64, String)> {
+            step.get_entries()
+                .iter()
+                .map(|e| (e.get_id(), e.get_definition().replace(' ', "")))
+                .collect()
+        };
+        assert_eq!(normalize(&step), normalize(&step2));
+    }
+
+    #[test]
+    fn test_writing_crlf() {
+        let data = include_str!("../../../test_data/minimal-structure.stp");
+        let step = step::StepData::from_str(data).unwrap();
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::with_format(
+                &mut serialized_data,
+                "2;1",
+                "minimal-structure.stp",
+                &[ApplicationProtocol::default().schema().to_owned()],
+                WriterFormat {
+                    line_ending: LineEnding::CrLf,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            for entry in step.get_entries() {
+                writer.write_entry(entry).unwrap();
+            }
+
+            writer.finalize().unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.ends_with("END-ISO-10303-21;\r\n"));
+        assert!(serialized_data.contains("HEADER;\r\n\r\n"));
+        assert_eq!(
+            serialized_data.matches('\n').count(),
+            serialized_data.matches("\r\n").count()
+        );
+
+        let step2 = step::StepData::from_str(&serialized_data).unwrap();
+        assert_eq!(step.get_entries(), step2.get_entries());
+    }
+
+    #[test]
+    fn test_writing_comment() {
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+            writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+            writer.write_comment(" from a*/b.stp ").unwrap();
+            writer.write_entry(&StepEntry::new(2, "BAR(#1)")).unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.contains("#1=FOO();\n/* from a* /b.stp */\n#2=BAR(#1);"));
+
+        let step = step::StepData::from_str(&serialized_data).unwrap();
+        assert_eq!(step.get_entries().len(), 2);
+    }
+
+    #[test]
+    fn test_writing_after_finalize() {
+        let mut serialized_data: Vec<u8> = Vec::new();
+        let mut writer = StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+        writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+        writer.finalize().unwrap();
+
+        assert!(matches!(
+            writer.write_entry(&StepEntry::new(2, "BAR(#1)")),
+            Err(Error::WriterFinalized())
+        ));
+        assert!(matches!(
+            writer.write_comment("late"),
+            Err(Error::WriterFinalized())
+        ));
+        drop(writer);
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.ends_with("END-ISO-10303-21;\n"));
+        assert!(!serialized_data.contains("BAR"));
+    }
+
+    #[test]
+    fn test_writing_unfinalized() {
+        // an unfinalized writer leaves an incomplete file
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+            writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.ends_with("#1=FOO();\n"));
+        assert!(step::StepData::from_str(&serialized_data).is_err());
+
+        // unless it is dropped while panicking
+        let mut serialized_data: Vec<u8> = Vec::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut writer = StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+            writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+            panic!("aborted");
+        }));
+        assert!(result.is_err());
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.ends_with("END-ISO-10303-21;\n"));
+    }
+
+    #[test]
+    fn test_bytes_written() {
+        let mut serialized_data: Vec<u8> = Vec::new();
+        let num_bytes = {
+            let mut writer = StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+            let num_header_bytes = writer.bytes_written();
+            writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+            assert_eq!(
+                writer.bytes_written(),
+                num_header_bytes + "#1=FOO();\n".len() as u64
+            );
+
+            writer.finalize().unwrap();
+            writer.bytes_written()
+        };
+
+        assert_eq!(num_bytes, serialized_data.len() as u64);
+    }
+
+    #[test]
+    fn test_writing_leading_comments() {
+        let data = concat!(
+            "/* Exported by some CAD system\n * with a multi-line comment */\n",
+            "/* second block */ ISO-10303-21;\n",
+            "HEADER; FILE_SCHEMA(('CONFIG_CONTROL_DESIGN')); ENDSEC;\n",
+            "DATA; #1=FOO(); ENDSEC; END-ISO-10303-21;"
+        );
+        let step = step::StepData::from_str(data).unwrap();
+        let comments = vec![
+            " Exported by some CAD system\n * with a multi-line comment ".to_owned(),
+            " second block ".to_owned(),
+        ];
+        assert_eq!(step.get_header().leading_comments, comments);
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        write_step(&mut serialized_data, &step, "out.stp").unwrap();
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.starts_with("/* Exported by some CAD system\n"));
+
+        let step2 = step::StepData::from_str(&serialized_data).unwrap();
+        assert_eq!(step2.get_header().leading_comments, comments);
+        assert_eq!(step.get_entries(), step2.get_entries());
+    }
+
+    #[test]
+    fn test_writing_encoded_strings() {
+        let data = concat!(
+            "ISO-10303-21; HEADER; FILE_DESCRIPTION((''),'2;1');\n",
+            "FILE_NAME('pi\\X2\\00E8\\X0\\ce.stp','',('Ren\\X\\E9'),(''),'','','');\n",
+            "FILE_SCHEMA(('CONFIG_CONTROL_DESIGN')); ENDSEC;\n",
+            "DATA; #1=PRODUCT('\\X2\\00C9\\X0\\crou','a\\\\b','',()); ENDSEC; END-ISO-10303-21;"
+        );
+        let step = step::StepData::from_str(data).unwrap();
+        assert_eq!(step.get_header().name, "pièce.stp");
+        assert_eq!(step.get_header().author, vec!["René"]);
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        write_step(&mut serialized_data, &step, "pièce.stp").unwrap();
+
+        // the header strings are encoded again and the entries are kept as they are
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        assert!(serialized_data.is_ascii());
+        let step2 = step::StepData::from_str(&serialized_data).unwrap();
+        assert_eq!(step2.get_header().name, "pièce.stp");
+        assert_eq!(step2.get_header().author, vec!["René"]);
+        assert_eq!(step.get_entries(), step2.get_entries());
+        assert_eq!(
+            step2.get_entries()[0].decoded_strings().unwrap(),
+            vec!["Écrou", "a\\b", ""]
+        );
+    }
+
+    #[test]
+    fn test_writing_header_info() {
+        let header_info = HeaderInfo {
+            author: vec!["Jane Doe".to_owned(), "John Doe".to_owned()],
+            organization: vec!["ACME".to_owned()],
+            preprocessor_version: "my-tool 1.0".to_owned(),
+            originating_system: "Müller CAD".to_owned(),
+            authorization: "it's approved".to_owned(),
+            time_stamp: Some("2024-05-08T10:00:00".to_owned()),
+        };
+
+        let mut serialized_data: Vec<u8> = Vec::new();
+        {
+            let mut writer = StepWriter::with_header_info(
+                &mut serialized_data,
+                &[],
+                &header_info,
+                "2;1",
+                "O'Brien, Inc.stp",
+                &[ApplicationProtocol::default().schema().to_owned()],
+                WriterFormat::default(),
+            )
+            .unwrap();
+            writer.write_entry(&StepEntry::new(1, "FOO()")).unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let serialized_data = String::from_utf8(serialized_data).unwrap();
+        let header = step::StepData::from_str(&serialized_data)
+            .unwrap()
+            .get_header()
+            .clone();
+        assert_eq!(header.name, "O'Brien, Inc.stp");
+        assert_eq!(header.author, header_info.author);
+        assert_eq!(header.organization, header_info.organization);
+        assert_eq!(
+            header.preprocessor_version,
+            header_info.preprocessor_version
+        );
+        assert_eq!(header.originating_system, header_info.originating_system);
+        assert_eq!(header.authorization, header_info.authorization);
+        assert_eq!(header.time_stamp, "2024-05-08T10:00:00");
+
+        // the defaults keep the previous header
+        let mut serialized_data: Vec<u8> = Vec::new();
+        StepWriter::new(&mut serialized_data, "2;1", "out.stp", &[]).unwrap();
+        assert!(String::from_utf8(serialized_data)
+            .unwrap()
+            .contains("(''), (''), 'step-merger', '', '');"));
+    }
 }
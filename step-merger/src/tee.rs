@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+
+/// A writer that writes all data to two sinks, e.g., to store the merged step file and to stream
+/// it to an upload at the same time.
+#[derive(Debug)]
+pub struct TeeWriter<A: Write, B: Write> {
+    first: A,
+    second: B,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    /// Creates a new writer that writes to both given sinks.
+    ///
+    /// # Arguments
+    /// * `first` - The first sink.
+    /// * `second` - The second sink.
+    pub fn new(first: A, second: B) -> Self {
+        TeeWriter { first, second }
+    }
+
+    /// Consumes the writer and returns the two sinks.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // the sinks may accept different amounts, so the whole buffer is passed to both
+        self.first.write_all(buf)?;
+        self.second.write_all(buf)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{merge_assembly_structure_to_step_with_vfs, Assembly, MemoryVfs, MergeOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_tee_writer_merge() {
+        let assembly = include_bytes!("../../test_data/cube-and-sphere.json");
+        let assembly: Assembly = serde_json::from_slice(assembly).unwrap();
+
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("cube.stp", include_bytes!("../../test_data/cube.stp"));
+        vfs.insert("sphere.stp", include_bytes!("../../test_data/sphere.stp"));
+
+        let mut writer = TeeWriter::new(Vec::new(), Vec::new());
+        merge_assembly_structure_to_step_with_vfs(
+            &assembly,
+            &MergeOptions::default(),
+            &mut writer,
+            &vfs,
+        )
+        .unwrap();
+
+        let (first, second) = writer.into_inner();
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+}
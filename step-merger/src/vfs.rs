@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{Error, Result};
+
+/// A virtual file system used to resolve the links of the assembly structure.
+pub trait Vfs {
+    /// Opens the file with the given path and returns a reader for its content.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to open.
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>>;
+}
+
+/// A virtual file system that resolves the paths relative to a root directory on the local
+/// file system.
+#[derive(Debug, Clone)]
+pub struct LocalVfs {
+    /// The root directory for resolving relative paths.
+    root: PathBuf,
+}
+
+impl LocalVfs {
+    /// Creates a new local file system with the given root directory.
+    ///
+    /// # Arguments
+    /// * `root` - The root directory for resolving relative paths.
+    pub fn new<P: AsRef<Path>>(root: P) -> LocalVfs {
+        LocalVfs {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Vfs for LocalVfs {
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>> {
+        let file_path = self.root.join(path);
+        let file = File::open(file_path)
+            .map_err(|e| Error::FailedOpenFile(Arc::new(e), path.to_owned()))?;
+
+        Ok(Box::new(file))
+    }
+}
+
+/// A virtual file system that downloads `http://` and `https://` links with a blocking HTTP
+/// client, e.g., to load the parts stored on an artifact server. All other links are resolved
+/// relative to a root directory on the local file system.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpVfs {
+    /// The file system for resolving the links that are no URLs.
+    local: LocalVfs,
+}
+
+#[cfg(feature = "http")]
+impl HttpVfs {
+    /// Creates a new HTTP file system with the given root directory for links that are no URLs.
+    ///
+    /// # Arguments
+    /// * `root` - The root directory for resolving relative paths.
+    pub fn new<P: AsRef<Path>>(root: P) -> HttpVfs {
+        HttpVfs {
+            local: LocalVfs::new(root),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Vfs for HttpVfs {
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>> {
+        if !path.starts_with("http://") && !path.starts_with("https://") {
+            return self.local.open(path);
+        }
+
+        let response = ureq::get(path).call().map_err(|e| {
+            Error::FailedOpenFile(Arc::new(std::io::Error::other(e)), path.to_owned())
+        })?;
+
+        Ok(Box::new(response.into_reader()))
+    }
+}
+
+/// A virtual file system that keeps the content of all files in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryVfs {
+    /// The files stored by their path.
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryVfs {
+    /// Creates a new empty in-memory file system.
+    pub fn new() -> MemoryVfs {
+        MemoryVfs::default()
+    }
+
+    /// Inserts the given file content for the given path. An existing file is replaced.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file.
+    /// * `data` - The content of the file.
+    pub fn insert<D: Into<Vec<u8>>>(&mut self, path: &str, data: D) {
+        self.files.insert(path.to_owned(), data.into());
+    }
+}
+
+impl<P: Into<String>, D: Into<Vec<u8>>> FromIterator<(P, D)> for MemoryVfs {
+    /// Creates an in-memory file system from the given `(path, content)` pairs.
+    fn from_iter<I: IntoIterator<Item = (P, D)>>(files: I) -> Self {
+        MemoryVfs {
+            files: files
+                .into_iter()
+                .map(|(path, data)| (path.into(), data.into()))
+                .collect(),
+        }
+    }
+}
+
+impl Vfs for MemoryVfs {
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>> {
+        match self.files.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data.as_slice()))),
+            None => Err(Error::FailedOpenFile(
+                Arc::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("File not found: {}", path),
+                )),
+                path.to_owned(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory_vfs() {
+        let mut vfs = MemoryVfs::new();
+        vfs.insert("foo.stp", "ISO-10303-21;");
+
+        let mut content = String::new();
+        vfs.open("foo.stp")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "ISO-10303-21;");
+
+        assert!(matches!(
+            vfs.open("bar.stp"),
+            Err(Error::FailedOpenFile(_, path)) if path == "bar.stp"
+        ));
+    }
+
+    #[test]
+    fn test_memory_vfs_from_iter() {
+        let vfs: MemoryVfs = [("a.stp", "A"), ("b.stp", "B")].into_iter().collect();
+
+        let mut content = String::new();
+        vfs.open("b.stp")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "B");
+        assert!(vfs.open("c.stp").is_err());
+    }
+
+    #[test]
+    fn test_local_vfs() {
+        let vfs = LocalVfs::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../test_data"));
+
+        let mut content = String::new();
+        vfs.open("cube.stp")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert!(content.starts_with("ISO-10303-21;"));
+
+        assert!(vfs.open("does-not-exist.stp").is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_http_vfs() {
+        use std::{io::Write, net::TcpListener};
+
+        // serve a single request with a minimal HTTP server
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/part.stp", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            let body = "ISO-10303-21;";
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let vfs = HttpVfs::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../test_data"));
+
+        let mut content = String::new();
+        vfs.open(&url)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "ISO-10303-21;");
+        server.join().unwrap();
+
+        // links that are no URLs are loaded from the local file system
+        let mut content = String::new();
+        vfs.open("cube.stp")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert!(content.starts_with("ISO-10303-21;"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::Cursor;
+
+use step_merger::step::{STEPReaderLogos, STEPReaderPlain, STEPReaderTrait};
+
+/// Removes all whitespace outside of string literals from the given definition.
+///
+/// # Arguments
+/// * `definition` - The definition to normalize.
+fn normalize(definition: &str) -> String {
+    let mut result = String::with_capacity(definition.len());
+    let mut inside_string = false;
+
+    for c in definition.chars() {
+        if c == '\'' {
+            // escaped quotes are handled as two consecutive strings
+            inside_string = !inside_string;
+        } else if !inside_string && c.is_whitespace() {
+            continue;
+        }
+
+        result.push(c);
+    }
+
+    result
+}
+
+/// Reads the normalized `(id, definition)` pairs of the given data with the given parser.
+///
+/// # Arguments
+/// * `data` - The content of the STEP file.
+fn read_entries<'a, P: STEPReaderTrait<Cursor<&'a [u8]>>>(data: &'a [u8]) -> Vec<(u64, String)> {
+    P::new(Cursor::new(data))
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.get_id(), normalize(entry.get_definition()))
+        })
+        .collect()
+}
+
+/// Asserts that all parsers produce the same entries for the given data.
+///
+/// # Arguments
+/// * `data` - The content of the STEP file.
+fn assert_conformance(data: &[u8]) {
+    let plain = read_entries::<STEPReaderPlain<_>>(data);
+    let logos = read_entries::<STEPReaderLogos<_>>(data);
+
+    assert!(!plain.is_empty());
+    assert_eq!(plain, logos);
+}
+
+#[test]
+fn test_conformance_wiki() {
+    assert_conformance(include_bytes!("../../test_data/wiki.stp"));
+}
+
+#[test]
+fn test_conformance_minimal_structure() {
+    assert_conformance(include_bytes!("../../test_data/minimal-structure.stp"));
+}
+
+#[test]
+fn test_conformance_cube() {
+    assert_conformance(include_bytes!("../../test_data/cube.stp"));
+}
+
+#[test]
+fn test_conformance_strings() {
+    assert_conformance(
+        b"ISO-10303-21; HEADER; FILE_NAME('it''s; DATA;','',(''),(''),'','',''); ENDSEC;
+          DATA;
+          #1 = PRODUCT('it''s','a;b', '' , ( #2 , #3 ) ) ;
+          #2=FOO('/* no comment */',$,*,.T.);
+          /* a comment */ #3=(BAR()BAZ(1.5E-3,'ENDSEC;'));
+          ENDSEC; END-ISO-10303-21;",
+    );
+}
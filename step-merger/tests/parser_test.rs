@@ -1,6 +1,11 @@
 use std::io::Cursor;
 
-use step_merger::step::{STEPReaderLogos, STEPReaderPlain, STEPReaderTrait, StepEntry};
+use step_merger::{
+    step::{
+        ExternalReference, STEPReaderLogos, STEPReaderPlain, STEPReaderTrait, StepEntry, StepHeader,
+    },
+    Error,
+};
 
 fn init_parser<P: STEPReaderTrait<Cursor<&'static str>>>() {
     let input = Cursor::new("ISO-10303-21; DATA;");
@@ -82,6 +87,181 @@ fn read_next_entry2<P: STEPReaderTrait<Cursor<&'static [u8]>>>() {
     );
 }
 
+fn read_max_entries<P: STEPReaderTrait<Cursor<&'static [u8]>>>() {
+    let input = Cursor::new(include_bytes!("../../test_data/wiki.stp").as_slice());
+    let mut parser = P::new(input).unwrap();
+    parser.set_max_entries(Some(3));
+
+    for _ in 0..3 {
+        assert!(parser.next().unwrap().is_ok());
+    }
+
+    assert!(matches!(
+        parser.next(),
+        Some(Err(Error::MaxEntriesExceeded(3)))
+    ));
+    assert!(parser.next().is_none());
+}
+
+fn read_zero_reference<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = Cursor::new("ISO-10303-21; DATA; #1=FOO('#0'); #2=FOO(#1,#0); ENDSEC;");
+    let mut parser = P::new(input).unwrap();
+
+    let entry = parser.next().unwrap().unwrap();
+    assert_eq!(entry.get_definition(), "FOO('#0')");
+    assert!(matches!(parser.next(), Some(Err(Error::ZeroReference(2)))));
+
+    let input = Cursor::new("ISO-10303-21; DATA; #0=FOO(#1); ENDSEC;");
+    let mut parser = P::new(input).unwrap();
+    assert!(matches!(parser.next(), Some(Err(Error::ZeroReference(0)))));
+}
+
+fn read_unsupported_encoding<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = Cursor::new(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<iso_10303_28 version="2.0">
+</iso_10303_28>"#,
+    );
+    assert!(matches!(
+        P::new(input),
+        Err(Error::UnsupportedEncoding(e)) if e == "ISO-10303-28 (XML)"
+    ));
+
+    let input = Cursor::new("/* comment */ ISO-10303-22; DATA; ENDSEC;");
+    assert!(matches!(
+        P::new(input),
+        Err(Error::UnsupportedEncoding(e)) if e == "ISO-10303-22"
+    ));
+}
+
+fn read_normalized_whitespace<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = "ISO-10303-21; DATA; #1=FOO( #5 , #6 ); #2=BAR ( 'a  b' , ( 1. , 2. ) ); ENDSEC;";
+
+    let mut parser = P::new(Cursor::new(input)).unwrap();
+    parser.set_normalize_whitespace(true);
+    assert_eq!(
+        parser.next().unwrap().unwrap().get_definition(),
+        "FOO(#5,#6)"
+    );
+    assert_eq!(
+        parser.next().unwrap().unwrap().get_definition(),
+        "BAR('a  b',(1.,2.))"
+    );
+}
+
+fn read_empty_data_section<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    for input in [
+        "ISO-10303-21; DATA; ENDSEC; END-ISO-10303-21;",
+        "ISO-10303-21;\nHEADER; FILE_SCHEMA(('CONFIG_CONTROL_DESIGN')); ENDSEC;\nDATA;ENDSEC;",
+    ] {
+        let mut parser = P::new(Cursor::new(input)).unwrap();
+        assert!(parser.next().is_none());
+        assert!(parser.next().is_none());
+    }
+}
+
+fn keywords_in_header_strings<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = Cursor::new(
+        "ISO-10303-21; HEADER; FILE_DESCRIPTION(('no DATA; here','ENDSEC;'),'2;1'); \
+         FILE_NAME('it''s DATA;','',(''),(''),'','',''); ENDSEC; DATA; #1=FOO('DATA'); ENDSEC;",
+    );
+    let mut parser = P::new(input).unwrap();
+
+    let entry = parser.next().unwrap().unwrap();
+    assert_eq!(entry.get_id(), 1);
+    assert_eq!(entry.get_definition(), "FOO('DATA')");
+    assert!(parser.next().is_none());
+}
+
+fn read_reference_section<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = Cursor::new(
+        "ISO-10303-21; HEADER; FILE_SCHEMA(('AP242')); ENDSEC; \
+         REFERENCE; #10=<part.stp#body>; #11 = <http://host/parts/screw.stp#shape> ; ENDSEC; \
+         DATA; #1=FOO(#10,#11); ENDSEC;",
+    );
+    let mut parser = P::new(input).unwrap();
+
+    assert_eq!(
+        parser.get_external_references(),
+        &[
+            ExternalReference {
+                id: 10,
+                uri: "part.stp#body".to_owned()
+            },
+            ExternalReference {
+                id: 11,
+                uri: "http://host/parts/screw.stp#shape".to_owned()
+            }
+        ]
+    );
+
+    let entry = parser.next().unwrap().unwrap();
+    assert_eq!(entry.get_definition(), "FOO(#10,#11)");
+    assert!(parser.next().is_none());
+
+    let input = Cursor::new("ISO-10303-21; DATA; #1=FOO(); ENDSEC;");
+    assert!(P::new(input).unwrap().get_external_references().is_empty());
+}
+
+fn read_header<P: STEPReaderTrait<Cursor<&'static [u8]>>>() {
+    let input = Cursor::new(include_bytes!("../../test_data/minimal-structure.stp").as_slice());
+    let parser = P::new(input).unwrap();
+
+    assert_eq!(
+        parser.header(),
+        &StepHeader {
+            description: vec!["".to_owned()],
+            implementation_level: "2;1".to_owned(),
+            name: "out.stp".to_owned(),
+            time_stamp: "2024-05-08T18:23:30.972023+02:00".to_owned(),
+            author: vec!["".to_owned()],
+            organization: vec!["".to_owned()],
+            preprocessor_version: "step-merger".to_owned(),
+            originating_system: "".to_owned(),
+            authorization: "".to_owned(),
+            schemas: vec!["AP203_CONFIGURATION_CONTROLLED_3D_DESIGN_OF_MECHANICAL_PARTS_AND_ASSEMBLIES_MIM_LF { 1 0 10303 403 1 1 4 }".to_owned()],
+            leading_comments: Vec::new(),
+        }
+    );
+}
+
+fn read_leading_comments<P: STEPReaderTrait<Cursor<&'static str>>>() {
+    let input = Cursor::new(
+        "/* Exported by\n   some CAD system */\n/**/ ISO-10303-21; /* not leading */ DATA; ENDSEC;",
+    );
+    let parser = P::new(input).unwrap();
+
+    assert_eq!(
+        parser.header().leading_comments,
+        vec![
+            " Exported by\n   some CAD system ".to_owned(),
+            String::new()
+        ]
+    );
+}
+
+fn read_latin1<P: STEPReaderTrait<Cursor<&'static [u8]>>>() {
+    // latin-1 without byte order mark
+    let input = Cursor::new(b"ISO-10303-21; DATA; #1=PRODUCT('M\xFCller'); ENDSEC;".as_slice());
+    let mut parser = P::new(input).unwrap();
+    assert_eq!(
+        parser.next().unwrap().unwrap().get_definition(),
+        "PRODUCT('M\u{FC}ller')"
+    );
+
+    // UTF-8 with and without byte order mark
+    for input in [
+        b"ISO-10303-21; DATA; #1=PRODUCT('M\xC3\xBCller'); ENDSEC;".as_slice(),
+        b"\xEF\xBB\xBFISO-10303-21; DATA; #1=PRODUCT('M\xC3\xBCller'); ENDSEC;".as_slice(),
+    ] {
+        let mut parser = P::new(Cursor::new(input)).unwrap();
+        assert_eq!(
+            parser.next().unwrap().unwrap().get_definition(),
+            "PRODUCT('M\u{FC}ller')"
+        );
+    }
+}
+
 #[test]
 fn test_init_parser_plain() {
     init_parser::<STEPReaderPlain<Cursor<&'static str>>>();
@@ -111,3 +291,140 @@ fn test_read_next_entry2_plain() {
 fn test_read_next_entry2_logos() {
     read_next_entry2::<STEPReaderLogos<Cursor<&'static [u8]>>>();
 }
+
+#[test]
+fn test_read_max_entries_plain() {
+    read_max_entries::<STEPReaderPlain<Cursor<&'static [u8]>>>();
+}
+
+#[test]
+fn test_read_max_entries_logos() {
+    read_max_entries::<STEPReaderLogos<Cursor<&'static [u8]>>>();
+}
+
+#[test]
+fn test_collect_comments_plain() {
+    let input = Cursor::new("ISO-10303-21; DATA; /* TODO: check */ #1=FOO(/* inner */); ENDSEC;");
+    let mut parser = STEPReaderPlain::new(input).unwrap();
+    parser.set_collect_comments(true);
+
+    let entry = parser.next().unwrap().unwrap();
+    assert_eq!(entry.get_definition(), "FOO( )");
+    assert_eq!(
+        parser.take_comments(),
+        vec![" TODO: check ".to_string(), " inner ".to_string()]
+    );
+}
+
+#[test]
+fn test_read_zero_reference_plain() {
+    read_zero_reference::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_zero_reference_logos() {
+    read_zero_reference::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_canonical_form() {
+    let sources = [
+        include_str!("../../test_data/cube.stp"),
+        include_str!("../../test_data/sphere.stp"),
+        include_str!("../../test_data/minimal-structure-with-metadata.stp"),
+        "ISO-10303-21; DATA; #1=FOO( 'a  b' ,\n\t#2 /* c */ ) ; #2 = BAR('x\ny'); ENDSEC;",
+    ];
+
+    // the plain reader with normalized whitespace reads the same canonical form as the logos
+    // reader
+    for source in sources {
+        let mut plain = STEPReaderPlain::new(Cursor::new(source)).unwrap();
+        plain.set_normalize_whitespace(true);
+        let plain: Vec<StepEntry> = plain.map(|e| e.unwrap()).collect();
+
+        let logos = STEPReaderLogos::new(Cursor::new(source)).unwrap();
+        let logos: Vec<StepEntry> = logos.map(|e| e.unwrap()).collect();
+
+        assert_eq!(plain, logos);
+    }
+}
+
+#[test]
+fn test_read_unsupported_encoding_plain() {
+    read_unsupported_encoding::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_unsupported_encoding_logos() {
+    read_unsupported_encoding::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_normalized_whitespace_plain() {
+    read_normalized_whitespace::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_normalized_whitespace_logos() {
+    read_normalized_whitespace::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_empty_data_section_plain() {
+    read_empty_data_section::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_empty_data_section_logos() {
+    read_empty_data_section::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_keywords_in_header_strings_plain() {
+    keywords_in_header_strings::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_keywords_in_header_strings_logos() {
+    keywords_in_header_strings::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_reference_section_plain() {
+    read_reference_section::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_reference_section_logos() {
+    read_reference_section::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_header_plain() {
+    read_header::<STEPReaderPlain<Cursor<&'static [u8]>>>();
+}
+
+#[test]
+fn test_read_header_logos() {
+    read_header::<STEPReaderLogos<Cursor<&'static [u8]>>>();
+}
+
+#[test]
+fn test_read_leading_comments_plain() {
+    read_leading_comments::<STEPReaderPlain<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_leading_comments_logos() {
+    read_leading_comments::<STEPReaderLogos<Cursor<&'static str>>>();
+}
+
+#[test]
+fn test_read_latin1_plain() {
+    read_latin1::<STEPReaderPlain<Cursor<&'static [u8]>>>();
+}
+
+#[test]
+fn test_read_latin1_logos() {
+    read_latin1::<STEPReaderLogos<Cursor<&'static [u8]>>>();
+}